@@ -0,0 +1,72 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ribozap::protein::{
+    smith_waterman, AlignmentScoring, GenomicInterval, NucleotideSequence, ProteinIndex, ProteinSequence, Strand,
+    DEFAULT_CANDIDATE_LIMIT,
+};
+use ribozap::SmallProtein;
+
+const PROTEIN_COUNT: usize = 5_000;
+
+fn synthetic_proteins() -> Vec<SmallProtein> {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next_base = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        ['A', 'C', 'G', 'T'][(state % 4) as usize]
+    };
+
+    (0..PROTEIN_COUNT).map(|i| {
+        let length = 90 + i % 210;
+        let rna_seq: String = (0..length).map(|_| next_base()).collect();
+        SmallProtein {
+            species: "Homo sapiens".to_string(),
+            id: format!("SPROHSA{i:06}"),
+            rna_seq: NucleotideSequence::new(&rna_seq).unwrap(),
+            aa_seq: ProteinSequence::default(),
+            length: length / 3,
+            chromosome: "chr1".to_string(),
+            start: 0,
+            stop: length,
+            strand: Strand::Forward,
+            blocks: vec![GenomicInterval { start: 1, end: length }],
+            start_codon: NucleotideSequence::new("ATG").ok(),
+            phylo_csf_mean: None,
+            source: "synthetic".to_string(),
+        }
+    }).collect()
+}
+
+fn best_score(query: &str, proteins: &[SmallProtein], indices: impl Iterator<Item = usize>) -> i32 {
+    let scoring = AlignmentScoring::default();
+    indices
+        .filter_map(|i| smith_waterman(query, &proteins[i].rna_seq, &scoring))
+        .map(|alignment| alignment.score)
+        .max()
+        .unwrap_or(0)
+}
+
+fn bench_protein_matching(c: &mut Criterion) {
+    let proteins = synthetic_proteins();
+    let query = proteins[PROTEIN_COUNT / 2].rna_seq[10..70].to_string();
+
+    c.bench_function("index_build", |b| {
+        b.iter(|| ProteinIndex::new(black_box(&proteins)))
+    });
+
+    let index = ProteinIndex::new(&proteins);
+
+    c.bench_function("closest_protein_brute_force", |b| {
+        b.iter(|| best_score(black_box(&query), &proteins, 0..proteins.len()))
+    });
+
+    c.bench_function("closest_protein_indexed", |b| {
+        b.iter(|| {
+            let candidates = index.candidates(black_box(&query), DEFAULT_CANDIDATE_LIMIT).unwrap();
+            best_score(&query, &proteins, candidates.into_iter())
+        })
+    });
+}
+
+criterion_group!(benches, bench_protein_matching);
+criterion_main!(benches);
@@ -1,8 +1,20 @@
 use ratatui::style::Color;
-use crate::protein::{SmallProtein, calculate_dna_similarity, identify_matching_positions, DatasetProgress};
-use crate::sequence::{get_complementary_base, dna_to_mrna, dna_codon_to_amino_acid};
+use crate::protein::{
+    SmallProtein, AlignmentScoring, KarlinAltschulParams, LocalAlignment, ProteinHit, ProteinIndex, ProteinScoring,
+    DatasetProgress, DatasetSource, LoadedDataset, ParseReport, smith_waterman, load_dataset, align_translated_frames, rank_protein_hits,
+    DEFAULT_CANDIDATE_LIMIT, DEFAULT_PEPTIDE_KMER_SIZE, PeptideMassIndex, PmfHit, PmfOptions, read_peak_list, search_peak_list,
+    Profile, ProfileKind, TopologyPrediction, predict_dataset_topology, predict_signal_peptide, predict_tm_helices, DEFAULT_PROFILE_WINDOW, MAX_PROFILE_WINDOW, MIN_PROFILE_WINDOW, PROFILE_PH, PKA_BJELLQVIST,
+};
+use crate::history::{EditHistory, EditKind, EditState};
+use crate::session::RestoredProteins;
+use crate::sequence::{
+    GeneticCode, OpenReadingFrame, OrfOptions, SequenceRecord, clean_sequence_text, read_sequence_file, get_complementary_base, get_reverse_complement, dna_to_mrna, dna_codon_to_amino_acid, find_open_reading_frames, translate_all_reading_frames,
+};
 use std::collections::HashMap;
-use std::sync::mpsc::{self, Receiver};
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
 use std::thread;
 use log::{info, warn, error, debug, trace};
 
@@ -15,12 +27,43 @@ pub struct App {
     pub amino_acids: String,
     pub amino_acids_colored: Vec<(String, Color)>,
     pub current_codon_position: usize,
-    pub small_proteins: Vec<SmallProtein>,
+    /// Insertion point in the edited strand, 0..=len
+    pub cursor: usize,
+    /// Other end of the selection; the selection runs between it and `cursor`
+    pub selection_anchor: Option<usize>,
+    pub overwrite_mode: bool,
+    pub history: EditHistory,
+    /// Shared with background digests of the dataset
+    pub small_proteins: Arc<[SmallProtein]>,
+    pub protein_index: Option<ProteinIndex>,
+    /// Predicted topology of each of `small_proteins`, for the prediction
+    /// filters; empty until the first such filter has it predicted
+    pub topology: Vec<TopologyPrediction>,
+    /// Topology being predicted on a background thread
+    pub topology_receiver: Option<Receiver<Vec<TopologyPrediction>>>,
+    pub protein_hits: Vec<ProteinHit>,
+    pub selected_hit_index: usize,
+    pub max_protein_hits: usize,
+    pub database_length: usize,
+    pub protein_database_length: usize,
+    pub significance_threshold: f64,
     pub closest_protein: Option<SmallProtein>,
+    pub active_hit: Option<ProteinHit>,
+    pub alignment_scoring: AlignmentScoring,
+    pub protein_scoring: ProteinScoring,
+    pub match_mode: MatchMode,
+    pub genetic_code: &'static GeneticCode,
+    pub orf_options: OrfOptions,
+    pub orfs: Vec<OpenReadingFrame>,
+    pub show_orf_panel: bool,
+    pub selected_orf: Option<usize>,
     pub is_loading_proteins: bool,
     pub loading_error: Option<String>,
     pub loaded_proteins_count: usize,
     pub dataset_progress: Option<DatasetProgress>,
+    pub dataset_sources: Vec<DatasetSource>,
+    pub current_dataset_source: Option<String>,
+    pub parse_report: ParseReport,
     pub is_positive_strand: bool,
     pub matching_positions: Vec<bool>,
     pub current_strand_confidence: f64,
@@ -37,8 +80,45 @@ pub struct App {
     pub multi_search_mode: bool,
     pub show_protein_detail: bool,
     pub detailed_protein: Option<SmallProtein>,
+    pub records: Vec<SequenceRecord>,
+    /// Path being typed in the open-file prompt, while it is shown
+    pub file_prompt: Option<String>,
+    pub file_prompt_kind: FilePromptKind,
+    /// Masses of the last opened peak list
+    pub peak_list: Vec<f64>,
+    pub pmf_options: PmfOptions,
+    /// Digests of the dataset, one per set of digest options searched so far
+    pub peptide_indexes: Vec<PeptideMassIndex>,
+    /// Digest being built on a background thread
+    pub peptide_index_receiver: Option<Receiver<PeptideMassIndex>>,
+    pub pmf_hits: Vec<PmfHit>,
+    pub selected_pmf_hit: usize,
+    pub show_pmf_results: bool,
+    /// Profile plotted under the amino acids and in the protein detail view
+    pub profile_kind: ProfileKind,
+    pub profile_window: usize,
+    /// One-off feedback shown in the status bar until the next edit
+    pub status_message: Option<String>,
+    /// Proteins named by a restored session, until the dataset has loaded
+    pub restored_proteins: Option<RestoredProteins>,
+    pub current_record: usize,
     pub progress_receiver: Option<Receiver<DatasetProgress>>,
-    pub protein_receiver: Option<Receiver<Result<Vec<SmallProtein>, String>>>,
+    pub protein_receiver: Option<Receiver<Result<LoadedDataset, String>>>,
+}
+
+/// What the open-file prompt opens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilePromptKind {
+    #[default]
+    Sequences,
+    PeakList,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum MatchMode {
+    #[value(name = "dna")]
+    Nucleotide,
+    Protein,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -52,6 +132,124 @@ pub enum SearchField {
     MaxLength,
     MinPhyloCSF,
     MaxPhyloCSF,
+    Source,
+    /// Predicted transmembrane helices: yes, no or a minimum count
+    TmHelices,
+    /// Predicted N-terminal signal peptide: yes or no
+    SignalPeptide,
+}
+
+impl SearchField {
+    /// In the order the searcher cycles through them with Tab.
+    pub const ALL: [SearchField; 12] = [
+        SearchField::Species, SearchField::Id, SearchField::Chromosome, SearchField::Strand,
+        SearchField::StartCodon, SearchField::MinLength, SearchField::MaxLength,
+        SearchField::MinPhyloCSF, SearchField::MaxPhyloCSF, SearchField::Source,
+        SearchField::TmHelices, SearchField::SignalPeptide,
+    ];
+
+    /// Whether `protein` passes this field's filter. Text fields match
+    /// case-insensitive substrings; numeric bounds that fail to parse match everything.
+    /// Prediction fields take yes or no, matched on the first letter, and use
+    /// `topology` when it is given instead of predicting.
+    pub fn matches(self, protein: &SmallProtein, topology: Option<&TopologyPrediction>, value: &str) -> bool {
+        let search_term = value.to_lowercase();
+
+        match self {
+            SearchField::Species => protein.species.to_lowercase().contains(&search_term),
+            SearchField::Id => protein.id.to_lowercase().contains(&search_term),
+            SearchField::Chromosome => protein.chromosome.to_lowercase().contains(&search_term),
+            SearchField::Strand => protein.strand.to_string().contains(&search_term),
+            SearchField::StartCodon => protein.start_codon.as_ref()
+                .is_some_and(|codon| codon.to_lowercase().contains(&search_term)),
+            SearchField::Source => protein.source.to_lowercase().contains(&search_term),
+            SearchField::MinLength => {
+                if let Ok(min_length) = value.parse::<usize>() {
+                    protein.length >= min_length
+                } else {
+                    true
+                }
+            },
+            SearchField::MaxLength => {
+                if let Ok(max_length) = value.parse::<usize>() {
+                    protein.length <= max_length
+                } else {
+                    true
+                }
+            },
+            SearchField::MinPhyloCSF => {
+                if let Ok(min_phylo) = value.parse::<f64>() {
+                    protein.phylo_csf_mean.is_some_and(|phylo| phylo >= min_phylo)
+                } else {
+                    true
+                }
+            },
+            SearchField::MaxPhyloCSF => {
+                if let Ok(max_phylo) = value.parse::<f64>() {
+                    protein.phylo_csf_mean.is_some_and(|phylo| phylo <= max_phylo)
+                } else {
+                    true
+                }
+            },
+            SearchField::TmHelices => {
+                let helices = || topology.map_or_else(
+                    || TopologyPrediction::predict(&protein.aa_seq).tm_helices.len(),
+                    |topology| topology.tm_helices.len(),
+                );
+                match search_term.chars().next() {
+                    Some('y') => helices() > 0,
+                    Some('n') => helices() == 0,
+                    _ => value.trim().parse::<usize>().map_or(true, |min_helices| helices() >= min_helices),
+                }
+            },
+            SearchField::SignalPeptide => {
+                let signal_peptide = || topology.map_or_else(
+                    || predict_signal_peptide(&protein.aa_seq).is_some(),
+                    |topology| topology.signal_peptide.is_some(),
+                );
+                match search_term.chars().next() {
+                    Some('y') => signal_peptide(),
+                    Some('n') => !signal_peptide(),
+                    _ => true,
+                }
+            },
+        }
+    }
+
+    /// Whether the filter runs the topology predictor.
+    pub fn is_prediction(self) -> bool {
+        matches!(self, SearchField::TmHelices | SearchField::SignalPeptide)
+    }
+
+    /// Field name as accepted on the command line, e.g. `min-length`.
+    pub fn key(self) -> &'static str {
+        match self {
+            SearchField::Species => "species",
+            SearchField::Id => "id",
+            SearchField::Chromosome => "chromosome",
+            SearchField::Strand => "strand",
+            SearchField::StartCodon => "start-codon",
+            SearchField::MinLength => "min-length",
+            SearchField::MaxLength => "max-length",
+            SearchField::MinPhyloCSF => "min-phylocsf",
+            SearchField::MaxPhyloCSF => "max-phylocsf",
+            SearchField::Source => "source",
+            SearchField::TmHelices => "tm-helices",
+            SearchField::SignalPeptide => "signal-peptide",
+        }
+    }
+}
+
+impl std::str::FromStr for SearchField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let key = s.trim().to_lowercase().replace('_', "-");
+        SearchField::ALL.into_iter()
+            .find(|field| field.key() == key)
+            .ok_or_else(|| format!("unknown search field '{s}', expected one of: {}",
+                                   SearchField::ALL.map(SearchField::key).join(", ")))
+    }
 }
 
 impl App {
@@ -64,12 +262,37 @@ impl App {
             amino_acids: String::new(),
             amino_acids_colored: Vec::new(),
             current_codon_position: 0,
-            small_proteins: Vec::new(),
+            cursor: 0,
+            selection_anchor: None,
+            overwrite_mode: false,
+            history: EditHistory::default(),
+            small_proteins: Arc::from([]),
+            protein_index: None,
+            topology: Vec::new(),
+            topology_receiver: None,
+            protein_hits: Vec::new(),
+            selected_hit_index: 0,
+            max_protein_hits: 10,
+            database_length: 0,
+            protein_database_length: 0,
+            significance_threshold: 0.01,
             closest_protein: None,
+            active_hit: None,
+            alignment_scoring: AlignmentScoring::default(),
+            protein_scoring: ProteinScoring::default(),
+            match_mode: MatchMode::Nucleotide,
+            genetic_code: GeneticCode::standard(),
+            orf_options: OrfOptions::default(),
+            orfs: Vec::new(),
+            show_orf_panel: false,
+            selected_orf: None,
             is_loading_proteins: true,
             loading_error: None,
             loaded_proteins_count: 0,
             dataset_progress: Some(DatasetProgress::CheckingCache),
+            dataset_sources: vec![DatasetSource::default()],
+            current_dataset_source: None,
+            parse_report: ParseReport::default(),
             is_positive_strand: true,
             matching_positions: Vec::new(),
             current_strand_confidence: 0.0,
@@ -86,23 +309,41 @@ impl App {
             multi_search_mode: false,
             show_protein_detail: false,
             detailed_protein: None,
+            records: Vec::new(),
+            file_prompt: None,
+            file_prompt_kind: FilePromptKind::Sequences,
+            peak_list: Vec::new(),
+            pmf_options: PmfOptions::default(),
+            peptide_indexes: Vec::new(),
+            peptide_index_receiver: None,
+            pmf_hits: Vec::new(),
+            selected_pmf_hit: 0,
+            show_pmf_results: false,
+            profile_kind: ProfileKind::default(),
+            profile_window: DEFAULT_PROFILE_WINDOW,
+            status_message: None,
+            restored_proteins: None,
+            current_record: 0,
             progress_receiver: None,
             protein_receiver: None,
         }
     }
 
+    pub fn set_dataset_sources(&mut self, sources: Vec<DatasetSource>) {
+        info!("Using {} dataset source(s): {}", sources.len(),
+              sources.iter().map(|source| source.to_string()).collect::<Vec<_>>().join(", "));
+        self.dataset_sources = sources;
+    }
+
     pub fn load_datasets(&mut self) {
-        use crate::protein::download_and_parse_small_protein_dataset_with_progress;
-        
         info!("Starting dataset loading");
         self.is_loading_proteins = true;
         self.loading_error = None;
         self.dataset_progress = Some(DatasetProgress::CheckingCache);
         
-        match download_and_parse_small_protein_dataset_with_progress(None) {
-            Ok(proteins) => {
-                self.loaded_proteins_count = proteins.len();
-                self.small_proteins = proteins;
+        match load_dataset(&self.dataset_sources, None) {
+            Ok(dataset) => {
+                self.set_loaded_dataset(dataset);
                 self.is_loading_proteins = false;
                 self.dataset_progress = Some(DatasetProgress::Complete);
                 info!("Successfully loaded {} proteins", self.loaded_proteins_count);
@@ -116,19 +357,36 @@ impl App {
         }
     }
 
+    pub fn set_small_proteins(&mut self, proteins: Vec<SmallProtein>) {
+        let index = ProteinIndex::new(&proteins);
+        self.set_loaded_dataset(LoadedDataset { proteins, index, report: ParseReport::default(), from_snapshot: false });
+    }
+
+    pub fn set_loaded_dataset(&mut self, dataset: LoadedDataset) {
+        let proteins = dataset.proteins;
+        self.loaded_proteins_count = proteins.len();
+        self.database_length = proteins.iter().map(|protein| protein.rna_seq.len()).sum();
+        self.protein_database_length = proteins.iter().map(|protein| protein.aa_seq.len()).sum();
+        self.protein_index = Some(dataset.index);
+        self.topology.clear();
+        self.topology_receiver = None;
+        self.peptide_indexes.clear();
+        // A digest still running belongs to the old dataset
+        self.peptide_index_receiver = None;
+        self.pmf_hits.clear();
+        self.small_proteins = Arc::from(proteins);
+        self.parse_report = dataset.report;
+    }
+
     pub fn update_progress(&mut self, progress: DatasetProgress) {
         self.dataset_progress = Some(progress);
     }
 
     fn check_and_load_dataset_file(&mut self) -> bool {
-        use crate::protein::dataset::get_data_dir;
-        if let Ok(data_dir) = get_data_dir() {
-            let extracted_file = data_dir.join("small_protein_dataset.txt");
-            if extracted_file.exists() {
-                self.dataset_progress = Some(DatasetProgress::Parsing { lines_parsed: 0 });
-                self.load_datasets();
-                return true;
-            }
+        if self.dataset_sources.iter().all(DatasetSource::is_available_offline) {
+            self.dataset_progress = Some(DatasetProgress::Parsing { lines_parsed: 0, bytes_read: 0, total_bytes: None });
+            self.load_datasets();
+            return true;
         }
         self.dataset_progress = Some(DatasetProgress::CheckingCache);
         false
@@ -147,51 +405,355 @@ impl App {
 
     pub fn find_closest_protein(&mut self) {
         if self.input.is_empty() || self.small_proteins.is_empty() {
-            self.closest_protein = None;
-            self.matching_positions.clear();
+            self.protein_hits.clear();
+            self.selected_hit_index = 0;
+            self.clear_active_match();
             self.current_strand_confidence = 0.0;
             self.opposite_strand_confidence = 0.0;
             debug!("No input or proteins available for matching");
             return;
         }
 
-        trace!("Finding closest protein match for input length: {}", self.input.len());
-        let mut best_match = None;
-        let mut best_similarity = 0.0;
-        let mut best_matching_positions = Vec::new();
+        trace!("Finding closest protein match for input length: {} ({:?} mode)", self.input.len(), self.match_mode);
+        let (hits, positive_strand_similarities, negative_strand_similarities) = match self.match_mode {
+            MatchMode::Nucleotide => self.nucleotide_hits(),
+            MatchMode::Protein => self.translated_hits(),
+        };
+
+        self.current_strand_confidence = self.calculate_strand_confidence(&positive_strand_similarities);
+        self.opposite_strand_confidence = self.calculate_strand_confidence(&negative_strand_similarities);
+
+        self.protein_hits = rank_protein_hits(hits, self.max_protein_hits);
+        self.selected_hit_index = 0;
+
+        match self.protein_hits.first() {
+            Some(hit) if hit.is_significant(self.significance_threshold) => {
+                debug!("Found best protein match: {} (score: {}, E-value: {:.2e}, CIGAR: {}), {} hits kept",
+                       self.small_proteins[hit.protein_index].id, hit.score(), hit.e_value,
+                       hit.alignment.cigar, self.protein_hits.len());
+                self.activate_hit(0);
+            },
+            Some(hit) => {
+                debug!("No significant protein match (best E-value: {:.2e})", hit.e_value);
+                self.clear_active_match();
+            },
+            None => self.clear_active_match(),
+        }
+    }
+
+    fn nucleotide_hits(&self) -> (Vec<ProteinHit>, Vec<f64>, Vec<f64>) {
+        let query_len = self.input.len();
+        let max_score = self.alignment_scoring.max_score(query_len);
+        let statistics = KarlinAltschulParams::for_scoring(&self.alignment_scoring);
+        let mut hits = Vec::new();
 
         let mut positive_strand_similarities = Vec::new();
         let mut negative_strand_similarities = Vec::new();
 
-        for protein in &self.small_proteins {
-            let positive_similarity = calculate_dna_similarity(&self.input, &protein.rna_seq);
-            let negative_similarity = calculate_dna_similarity(&self.complementary, &protein.rna_seq);
+        let candidate_indices = self.match_candidates();
+        trace!("Aligning against {} of {} proteins", candidate_indices.len(), self.small_proteins.len());
 
-            positive_strand_similarities.push(positive_similarity);
-            negative_strand_similarities.push(negative_similarity);
+        for protein_index in candidate_indices {
+            let protein = &self.small_proteins[protein_index];
+            let positive_alignment = smith_waterman(&self.input, &protein.rna_seq, &self.alignment_scoring);
+            let negative_alignment = smith_waterman(&self.complementary, &protein.rna_seq, &self.alignment_scoring);
 
-            let (compare_seq, protein_seq, similarity) = if protein.strand == "-" {
-                (&self.complementary, &protein.rna_seq, negative_similarity)
-            } else {
-                (&self.input, &protein.rna_seq, positive_similarity)
+            positive_strand_similarities.push(positive_alignment.as_ref().map_or(0.0, |a| a.normalized_score(max_score)));
+            negative_strand_similarities.push(negative_alignment.as_ref().map_or(0.0, |a| a.normalized_score(max_score)));
+
+            let best_strand_alignment = match (positive_alignment, negative_alignment) {
+                (Some(positive), Some(negative)) if negative.score > positive.score => Some((negative, false)),
+                (Some(positive), _) => Some((positive, true)),
+                (None, Some(negative)) => Some((negative, false)),
+                (None, None) => None,
             };
 
-            if similarity > best_similarity {
-                best_similarity = similarity;
-                best_match = Some(protein.clone());
-                best_matching_positions = identify_matching_positions(compare_seq, protein_seq);
+            if let Some((alignment, is_positive_strand)) = best_strand_alignment {
+                hits.push(ProteinHit::new(
+                    protein_index, alignment, query_len, is_positive_strand, &statistics, self.database_length,
+                ));
             }
         }
 
-        self.current_strand_confidence = self.calculate_strand_confidence(&positive_strand_similarities);
-        self.opposite_strand_confidence = self.calculate_strand_confidence(&negative_strand_similarities);
+        (hits, positive_strand_similarities, negative_strand_similarities)
+    }
 
-        if let Some(ref protein) = best_match {
-            debug!("Found best protein match: {} (similarity: {:.4})", protein.id, best_similarity);
+    fn translated_hits(&self) -> (Vec<ProteinHit>, Vec<f64>, Vec<f64>) {
+        let frames = match translate_all_reading_frames(&self.input, self.genetic_code) {
+            Ok(frames) => frames,
+            Err(e) => {
+                debug!("Cannot translate query for protein matching: {e}");
+                return (Vec::new(), Vec::new(), Vec::new());
+            }
+        };
+
+        let statistics = KarlinAltschulParams::for_protein_scoring(&self.protein_scoring);
+        let mut hits = Vec::new();
+
+        let mut positive_strand_similarities = Vec::new();
+        let mut negative_strand_similarities = Vec::new();
+
+        let candidate_indices = self.translated_match_candidates(&frames);
+        trace!("Aligning six frames against {} of {} proteins", candidate_indices.len(), self.small_proteins.len());
+
+        for protein_index in candidate_indices {
+            let protein = &self.small_proteins[protein_index];
+            let positive = align_translated_frames(&frames[..3], &protein.aa_seq, &self.protein_scoring);
+            let negative = align_translated_frames(&frames[3..], &protein.aa_seq, &self.protein_scoring)
+                .map(|(frame, alignment)| (frame + 3, alignment));
+
+            let similarity = |best: &Option<(usize, LocalAlignment)>| best.as_ref().map_or(0.0, |(frame, alignment)| {
+                alignment.normalized_score(self.protein_scoring.max_score(&frames[*frame]))
+            });
+            positive_strand_similarities.push(similarity(&positive));
+            negative_strand_similarities.push(similarity(&negative));
+
+            let best_frame_alignment = match (positive, negative) {
+                (Some(positive), Some(negative)) if negative.1.score > positive.1.score => Some(negative),
+                (Some(positive), _) => Some(positive),
+                (None, negative) => negative,
+            };
+
+            if let Some((frame, alignment)) = best_frame_alignment {
+                hits.push(ProteinHit::translated(
+                    protein_index, frame, alignment, frames[frame].len(), &statistics, self.protein_database_length,
+                ));
+            }
         }
 
-        self.closest_protein = best_match;
-        self.matching_positions = best_matching_positions;
+        (hits, positive_strand_similarities, negative_strand_similarities)
+    }
+
+    fn activate_hit(&mut self, hit_index: usize) {
+        if let Some(hit) = self.protein_hits.get(hit_index) {
+            let protein = &self.small_proteins[hit.protein_index];
+            self.matching_positions = if hit.frame.is_some() {
+                hit.alignment.matching_codon_positions(protein.rna_seq.len())
+            } else {
+                hit.alignment.matching_positions(protein.rna_seq.len())
+            };
+            self.closest_protein = Some(protein.clone());
+            self.active_hit = Some(hit.clone());
+        }
+    }
+
+    fn clear_active_match(&mut self) {
+        self.closest_protein = None;
+        self.active_hit = None;
+        self.matching_positions.clear();
+    }
+
+    pub fn next_hit(&mut self) {
+        if !self.protein_hits.is_empty() {
+            self.selected_hit_index = (self.selected_hit_index + 1) % self.protein_hits.len();
+        }
+    }
+
+    pub fn prev_hit(&mut self) {
+        if !self.protein_hits.is_empty() {
+            self.selected_hit_index = if self.selected_hit_index == 0 {
+                self.protein_hits.len() - 1
+            } else {
+                self.selected_hit_index - 1
+            };
+        }
+    }
+
+    pub fn select_current_hit(&mut self) {
+        if self.selected_hit_index < self.protein_hits.len() {
+            info!("Activating protein hit #{}", self.selected_hit_index + 1);
+            self.activate_hit(self.selected_hit_index);
+        }
+    }
+
+    fn match_candidates(&self) -> Vec<usize> {
+        let Some(index) = &self.protein_index else {
+            return (0..self.small_proteins.len()).collect();
+        };
+
+        let positive = index.candidates(&self.input, DEFAULT_CANDIDATE_LIMIT);
+        let negative = index.candidates(&self.complementary, DEFAULT_CANDIDATE_LIMIT);
+
+        match (positive, negative) {
+            (Some(mut candidates), Some(negative)) => {
+                candidates.extend(negative);
+                candidates.sort_unstable();
+                candidates.dedup();
+                candidates
+            },
+            _ => (0..self.small_proteins.len()).collect(),
+        }
+    }
+
+    fn translated_match_candidates(&self, frames: &[String]) -> Vec<usize> {
+        let Some(index) = &self.protein_index else {
+            return (0..self.small_proteins.len()).collect();
+        };
+
+        let mut candidates: Vec<usize> = frames.iter()
+            .filter_map(|frame| index.peptide_candidates(frame, DEFAULT_CANDIDATE_LIMIT))
+            .flatten()
+            .collect();
+
+        if candidates.is_empty() && frames.iter().all(|frame| frame.len() < DEFAULT_PEPTIDE_KMER_SIZE) {
+            return (0..self.small_proteins.len()).collect();
+        }
+
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+
+    pub fn toggle_match_mode(&mut self) {
+        self.match_mode = match self.match_mode {
+            MatchMode::Nucleotide => MatchMode::Protein,
+            MatchMode::Protein => MatchMode::Nucleotide,
+        };
+        info!("Match mode switched to {:?}", self.match_mode);
+        self.find_closest_protein();
+        self.protein_match_needed = false;
+    }
+
+    pub fn cycle_substitution_matrix(&mut self) {
+        self.protein_scoring.matrix = self.protein_scoring.matrix.next();
+        info!("Substitution matrix switched to {}", self.protein_scoring.matrix.name());
+        if self.match_mode == MatchMode::Protein {
+            self.find_closest_protein();
+            self.protein_match_needed = false;
+        }
+    }
+
+    /// Switches the translation table and re-translates the query.
+    pub fn set_genetic_code(&mut self, code: &'static GeneticCode) {
+        info!("Genetic code switched to {code}");
+        self.genetic_code = code;
+        self.update_amino_acids();
+        self.update_orfs();
+        if self.match_mode == MatchMode::Protein {
+            self.find_closest_protein();
+            self.protein_match_needed = false;
+        }
+    }
+
+    pub fn next_genetic_code(&mut self) {
+        self.set_genetic_code(self.genetic_code.next());
+    }
+
+    pub fn prev_genetic_code(&mut self) {
+        self.set_genetic_code(self.genetic_code.previous());
+    }
+
+    /// Cycles the ORF start codons through ATG only, the genetic code's
+    /// starts and the near-cognate set.
+    pub fn cycle_orf_start_codons(&mut self) {
+        let min_length = self.orf_options.min_length;
+        let presets = [
+            OrfOptions::default(),
+            OrfOptions::for_genetic_code(self.genetic_code),
+            OrfOptions::near_cognate(),
+        ].map(|options| options.with_min_length(min_length));
+
+        let current = presets.iter().position(|options| *options == self.orf_options).unwrap_or(presets.len() - 1);
+        self.orf_options = presets[(current + 1) % presets.len()].clone();
+        info!("ORF start codons: {}", self.orf_options.start_label());
+        self.update_orfs();
+    }
+
+    pub fn change_orf_min_length(&mut self, delta: isize) {
+        self.orf_options.min_length = self.orf_options.min_length.saturating_add_signed(delta).max(1);
+        self.update_orfs();
+    }
+
+    pub fn toggle_orf_panel(&mut self) {
+        self.show_orf_panel = !self.show_orf_panel;
+        debug!("ORF panel toggled: {}", self.show_orf_panel);
+    }
+
+    fn update_orfs(&mut self) {
+        let selected = self.selected_orf().cloned();
+        self.orfs = find_open_reading_frames(&self.input, self.genetic_code, &self.orf_options).unwrap_or_else(|e| {
+            debug!("Cannot scan query for ORFs: {e}");
+            Vec::new()
+        });
+        // Keep the selection while the same ORF is still found
+        self.selected_orf = selected.and_then(|selected| self.orfs.iter().position(|orf| {
+            (orf.frame, orf.start, orf.end) == (selected.frame, selected.start, selected.end)
+        }));
+    }
+
+    pub fn selected_orf(&self) -> Option<&OpenReadingFrame> {
+        self.selected_orf.and_then(|orf_index| self.orfs.get(orf_index))
+    }
+
+    pub fn select_orf(&mut self, orf_index: Option<usize>) {
+        self.selected_orf = orf_index.filter(|&orf_index| orf_index < self.orfs.len());
+        if let Some(orf) = self.selected_orf() {
+            debug!("Selected ORF {} {}-{}", orf.frame_label(), orf.start + 1, orf.end);
+        }
+    }
+
+    pub fn next_orf(&mut self) {
+        if !self.orfs.is_empty() {
+            self.select_orf(Some(self.selected_orf.map_or(0, |orf_index| (orf_index + 1) % self.orfs.len())));
+        }
+    }
+
+    pub fn prev_orf(&mut self) {
+        if !self.orfs.is_empty() {
+            let last = self.orfs.len() - 1;
+            self.select_orf(Some(self.selected_orf.map_or(last, |orf_index| orf_index.checked_sub(1).unwrap_or(last))));
+        }
+    }
+
+    /// Selects the innermost ORF in `frame` covering `position`, or clears the
+    /// selection if there is none, as when clicking the ORF map.
+    pub fn select_orf_at(&mut self, frame: usize, position: usize) {
+        let orf_index = self.orfs.iter().enumerate()
+            .filter(|(_, orf)| orf.frame == frame && orf.start <= position && position < orf.end)
+            .min_by_key(|(_, orf)| orf.nucleotide_len())
+            .map(|(orf_index, _)| orf_index);
+        self.select_orf(orf_index);
+    }
+
+    /// The protein the analysis panel describes: the selected ORF's, or the
+    /// +1 frame translation of the whole query with stops left out.
+    pub fn query_protein(&self) -> String {
+        match self.selected_orf() {
+            Some(orf) => orf.protein.clone(),
+            None => self.input.as_bytes()
+                .chunks_exact(3)
+                .filter_map(|codon| self.genetic_code.translate_codon(codon))
+                .filter(|&amino| amino != '*')
+                .collect(),
+        }
+    }
+
+    /// The chosen profile of a protein, or of its nucleotides for GC content.
+    /// Hydropathy marks the predicted TM helices, whatever window is plotted.
+    pub fn profile(&self, protein: &str, nucleotides: &str) -> Profile {
+        match self.profile_kind {
+            ProfileKind::Hydropathy => Profile::hydropathy(protein, self.profile_window)
+                .with_highlights(predict_tm_helices(protein).into_iter().map(|helix| helix.range).collect()),
+            ProfileKind::Charge => Profile::charge(protein, self.profile_window, PROFILE_PH, &PKA_BJELLQVIST),
+            ProfileKind::GcContent => Profile::gc_content(nucleotides, self.profile_window),
+        }
+    }
+
+    pub fn cycle_profile_kind(&mut self) {
+        self.profile_kind = self.profile_kind.next();
+        debug!("Showing {} profile", self.profile_kind.name());
+    }
+
+    /// Widens or narrows the profile window by `steps` odd sizes, so it stays centred.
+    pub fn change_profile_window(&mut self, steps: isize) {
+        let window = self.profile_window as isize + 2 * steps;
+        self.profile_window = (window.max(0) as usize).clamp(MIN_PROFILE_WINDOW, MAX_PROFILE_WINDOW);
+        debug!("Profile window set to {}", self.profile_window);
+    }
+
+    /// Where typing continues; the ORF map highlights it.
+    pub fn cursor_position(&self) -> usize {
+        self.cursor
     }
 
     pub fn calculate_strand_confidence(&self, similarities: &[f64]) -> f64 {
@@ -212,6 +774,7 @@ impl App {
     }
 
     pub fn toggle_strand_mode(&mut self) {
+        let before = self.edit_state();
         info!("Toggling strand mode from {} to {}",
               if self.is_positive_strand { "positive" } else { "negative" },
               if self.is_positive_strand { "negative" } else { "positive" });
@@ -219,10 +782,72 @@ impl App {
         std::mem::swap(&mut self.input, &mut self.complementary);
         self.is_positive_strand = !self.is_positive_strand;
         self.update_sequences();
+        self.record_edit(EditKind::StrandToggle, before);
         self.find_closest_protein();
         self.protein_match_needed = false;
     }
 
+    /// Replaces the edited strand with its reverse complement, keeping the
+    /// cursor on the same base.
+    pub fn reverse_complement(&mut self) {
+        let before = self.edit_state();
+        info!("Reverse-complementing {} nt", self.input.len());
+
+        let strand = self.edited_strand();
+        *strand = get_reverse_complement(strand);
+        let len = self.input.len();
+        self.cursor = len - self.cursor;
+        self.selection_anchor = self.selection_anchor.map(|anchor| len - anchor);
+        self.finish_edit();
+        self.protein_match_needed = true;
+        self.record_edit(EditKind::ReverseComplement, before);
+    }
+
+    fn edit_state(&self) -> EditState {
+        EditState {
+            input: self.input.clone(),
+            complementary: self.complementary.clone(),
+            is_positive_strand: self.is_positive_strand,
+            cursor: self.cursor,
+        }
+    }
+
+    fn restore_edit_state(&mut self, state: EditState) {
+        self.input = state.input;
+        self.complementary = state.complementary;
+        self.is_positive_strand = state.is_positive_strand;
+        self.cursor = state.cursor;
+        self.selection_anchor = None;
+        self.update_sequences();
+        self.protein_match_needed = true;
+    }
+
+    fn record_edit(&mut self, kind: EditKind, before: EditState) {
+        self.history.record(kind, before, self.cursor);
+    }
+
+    pub fn undo(&mut self) {
+        match self.history.undo(self.edit_state()) {
+            Some(state) => {
+                debug!("Undoing edit ({} steps left)", self.history.undo.len());
+                self.restore_edit_state(state);
+                self.status_message = None;
+            },
+            None => self.status_message = Some("Nothing to undo".to_string()),
+        }
+    }
+
+    pub fn redo(&mut self) {
+        match self.history.redo(self.edit_state()) {
+            Some(state) => {
+                debug!("Redoing edit ({} steps left)", self.history.redo.len());
+                self.restore_edit_state(state);
+                self.status_message = None;
+            },
+            None => self.status_message = Some("Nothing to redo".to_string()),
+        }
+    }
+
     pub fn update_sequences(&mut self) {
         trace!("Updating sequences for {} strand",
                if self.is_positive_strand { "positive" } else { "negative" });
@@ -251,9 +876,12 @@ impl App {
                 .collect();
         }
 
-        self.current_codon_position = self.mrna.len() % 3;
+        self.cursor = self.cursor.min(self.input.len());
+        self.selection_anchor = self.selection_anchor.filter(|&anchor| anchor <= self.input.len());
+        self.current_codon_position = self.cursor % 3;
 
         self.update_amino_acids();
+        self.update_orfs();
 
         let current_length = self.input.len();
         if current_length < 10 || 
@@ -262,8 +890,8 @@ impl App {
             self.protein_match_needed = true;
             debug!("Protein matching needed due to sequence length change: {} -> {}",
                    self.last_input_length, current_length);
+            self.last_input_length = current_length;
         }
-        self.last_input_length = current_length;
     }
 
     pub fn perform_protein_matching_if_needed(&mut self) {
@@ -271,21 +899,58 @@ impl App {
             self.find_closest_protein();
             self.protein_match_needed = false;
         }
+        self.resolve_restored_proteins();
     }
 
-    pub fn get_current_partial_codon(&self) -> String {
-        if self.mrna.is_empty() {
-            return String::new();
+    /// Pins the match and re-selects the searcher protein a restored session
+    /// named, once the dataset they come from is loaded.
+    fn resolve_restored_proteins(&mut self) {
+        if self.small_proteins.is_empty() {
+            return;
         }
+        let Some(restored) = self.restored_proteins.take() else {
+            return;
+        };
 
-        let mrna_str = self.mrna.to_uppercase();
-        let codon_start = (self.mrna.len() / 3) * 3;
+        if let Some(id) = &restored.pinned {
+            let hit_index = self.protein_hits.iter().position(|hit| self.small_proteins[hit.protein_index].id == *id);
+            match (hit_index, self.small_proteins.iter().find(|protein| protein.id == *id)) {
+                (Some(hit_index), _) => {
+                    self.selected_hit_index = hit_index;
+                    self.activate_hit(hit_index);
+                },
+                (None, Some(protein)) => {
+                    self.closest_protein = Some(protein.clone());
+                    self.active_hit = None;
+                    self.matching_positions.clear();
+                },
+                (None, None) => warn!("Pinned protein {id} from the session is not in the loaded datasets"),
+            }
+        }
 
-        if codon_start >= mrna_str.len() {
-            return String::new();
+        if self.show_protein_searcher {
+            self.filter_proteins();
+            let selected = restored.selected.as_ref()
+                .and_then(|id| self.filtered_proteins.iter().position(|protein| protein.id == *id));
+            if let Some(index) = selected {
+                self.selected_protein_index = index;
+                if restored.show_detail {
+                    self.detailed_protein = Some(self.filtered_proteins[index].clone());
+                    self.show_protein_detail = true;
+                }
+            }
         }
+    }
+
+    /// The mRNA bases of the codon being edited, up to the cursor.
+    pub fn get_current_partial_codon(&self) -> String {
+        let cursor = self.cursor.min(self.mrna.len());
+        self.mrna[self.edited_codon_start()..cursor].to_uppercase()
+    }
 
-        mrna_str[codon_start..].to_string()
+    /// Start of the codon the cursor is in.
+    pub fn edited_codon_start(&self) -> usize {
+        self.cursor - self.cursor % 3
     }
 
     fn update_amino_acids(&mut self) {
@@ -299,7 +964,7 @@ impl App {
             let codon = &mrna_str[i..i+3];
             let dna_codon = codon.replace('U', "T");
 
-            let amino = dna_codon_to_amino_acid(&dna_codon);
+            let amino = dna_codon_to_amino_acid(&dna_codon, self.genetic_code);
             let color = Color::White;
 
             if !self.amino_acids.is_empty() {
@@ -322,28 +987,378 @@ impl App {
         }
     }
 
+    fn edited_strand(&mut self) -> &mut String {
+        if self.is_positive_strand { &mut self.input } else { &mut self.complementary }
+    }
+
+    /// Re-derives the other tracks after an edit. Edits before the end shift
+    /// the whole alignment, so they always re-run matching.
+    fn finish_edit(&mut self) {
+        let edited_len = if self.is_positive_strand { self.input.len() } else { self.complementary.len() };
+        let edited_mid_sequence = self.cursor < edited_len;
+        self.status_message = None;
+        self.update_sequences();
+        if edited_mid_sequence {
+            self.protein_match_needed = true;
+        }
+    }
+
+    /// Types a nucleotide or IUPAC ambiguity code at the cursor, replacing the
+    /// selection if there is one; RNA's U is stored as T.
     pub fn on_key(&mut self, c: char) {
-        trace!("Adding nucleotide '{}' to {} strand", c,
+        let c = match c.to_ascii_uppercase() {
+            'U' => 'T',
+            base => base,
+        };
+        trace!("Typing nucleotide '{}' at {} on {} strand", c, self.cursor,
                if self.is_positive_strand { "positive" } else { "negative" });
 
-        if self.is_positive_strand {
-            self.input.push(c);
+        let before = self.edit_state();
+        let replaced_selection = self.delete_selection_text();
+        if replaced_selection {
+            self.history.break_group();
+        }
+        let (cursor, overwrite) = (self.cursor, self.overwrite_mode);
+        let strand = self.edited_strand();
+        let overwrote = overwrite && cursor < strand.len();
+        if overwrote {
+            strand.replace_range(cursor..cursor + 1, c.encode_utf8(&mut [0; 4]));
         } else {
-            self.complementary.push(c);
+            strand.insert(cursor, c);
         }
+        self.cursor += 1;
+        self.finish_edit();
+        // Replacing bases at the end changes the sequence without moving its length much
+        if overwrote || replaced_selection {
+            self.protein_match_needed = true;
+        }
+        self.record_edit(EditKind::Insert, before);
+    }
+
+    /// Inserts validated nucleotides at the cursor in one edit, replacing the
+    /// selection; it undoes as a single step.
+    pub fn insert_sequence(&mut self, sequence: &str) {
+        let before = self.edit_state();
+        self.delete_selection_text();
+        let cursor = self.cursor;
+        self.edited_strand().insert_str(cursor, sequence);
+        self.cursor += sequence.len();
+        self.finish_edit();
+        self.record_edit(EditKind::Paste, before);
+    }
+
+    /// Cleans pasted text with `clean_sequence_text` and inserts the result.
+    pub fn paste(&mut self, text: &str) {
+        let cleaned = clean_sequence_text(text);
+        info!("Pasting {} nucleotides ({} characters skipped)", cleaned.sequence.len(), cleaned.rejected);
+
+        if !cleaned.sequence.is_empty() {
+            self.insert_sequence(&cleaned.sequence);
+        }
+        self.status_message = Some(match (cleaned.sequence.len(), cleaned.rejected) {
+            (0, _) => "Nothing to paste: no nucleotides found".to_string(),
+            (pasted, 0) => format!("Pasted {pasted} nt"),
+            (pasted, rejected) => format!("Pasted {pasted} nt, skipped {rejected} non-nucleotide characters"),
+        });
+    }
+
+    /// Removes the selected bases without re-deriving the other tracks.
+    fn delete_selection_text(&mut self) -> bool {
+        let Some(range) = self.selection_range() else {
+            return false;
+        };
+        self.edited_strand().replace_range(range.clone(), "");
+        self.cursor = range.start;
+        self.selection_anchor = None;
+        true
+    }
+
+    pub fn delete_selection(&mut self) -> bool {
+        let before = self.edit_state();
+        let deleted = self.delete_selection_text();
+        if deleted {
+            self.finish_edit();
+            self.record_edit(EditKind::DeleteSelection, before);
+        }
+        deleted
+    }
+
+    /// Bases between the selection anchor and the cursor, if any.
+    pub fn selection_range(&self) -> Option<std::ops::Range<usize>> {
+        self.selection_anchor
+            .filter(|&anchor| anchor != self.cursor)
+            .map(|anchor| anchor.min(self.cursor)..anchor.max(self.cursor))
+    }
+
+    /// Moves the cursor, extending the selection from where it was when `extend` is set.
+    pub fn move_cursor_to(&mut self, position: usize, extend: bool) {
+        if extend {
+            self.selection_anchor.get_or_insert(self.cursor);
+        } else {
+            self.selection_anchor = None;
+        }
+        self.cursor = position.min(self.input.len());
+        self.current_codon_position = self.cursor % 3;
+        self.history.break_group();
+    }
+
+    pub fn move_cursor_left(&mut self, extend: bool) {
+        self.move_cursor_to(self.cursor.saturating_sub(1), extend);
+    }
+
+    pub fn move_cursor_right(&mut self, extend: bool) {
+        self.move_cursor_to(self.cursor + 1, extend);
+    }
+
+    /// To the start of the previous codon, or of this one if the cursor is inside it.
+    pub fn move_cursor_prev_codon(&mut self, extend: bool) {
+        let codon_start = self.edited_codon_start();
+        let target = if codon_start == self.cursor { codon_start.saturating_sub(3) } else { codon_start };
+        self.move_cursor_to(target, extend);
+    }
+
+    pub fn move_cursor_next_codon(&mut self, extend: bool) {
+        self.move_cursor_to(self.edited_codon_start() + 3, extend);
+    }
+
+    pub fn move_cursor_home(&mut self, extend: bool) {
+        self.move_cursor_to(0, extend);
+    }
+
+    pub fn move_cursor_end(&mut self, extend: bool) {
+        self.move_cursor_to(self.input.len(), extend);
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection_anchor = None;
+    }
+
+    pub fn toggle_overwrite_mode(&mut self) {
+        self.overwrite_mode = !self.overwrite_mode;
+        debug!("Overwrite mode: {}", self.overwrite_mode);
+    }
+
+    /// Replaces the query with `dna` on the positive strand, as used by the
+    /// headless `match` command. The edit history starts over.
+    pub fn set_input(&mut self, dna: &str) {
+        self.history.clear();
+        self.is_positive_strand = true;
+        self.input = dna.to_uppercase().replace('U', "T");
+        self.cursor = self.input.len();
+        self.selection_anchor = None;
         self.update_sequences();
     }
 
+    /// Loads records from a sequence file and shows the first one. Records are
+    /// expected to hold validated, uppercase nucleotides.
+    pub fn set_records(&mut self, records: Vec<SequenceRecord>) {
+        info!("Loaded {} sequence records", records.len());
+        self.records = records;
+        self.select_record(0);
+    }
+
+    /// Loads a FASTA/FASTQ file's records into the editor, showing the first.
+    pub fn open_sequence_file(&mut self, path: &Path) -> Result<usize, Box<dyn Error>> {
+        let mut records = read_sequence_file(path)?;
+        for record in &mut records {
+            record.sequence = record.nucleotides().map_err(|e| format!("{}: {e}", path.display()))?.to_string();
+        }
+        if records.is_empty() {
+            return Err(format!("{}: no sequence records", path.display()).into());
+        }
+
+        let count = records.len();
+        self.set_records(records);
+        Ok(count)
+    }
+
+    pub fn open_file_prompt(&mut self) {
+        self.file_prompt = Some(String::new());
+        self.file_prompt_kind = FilePromptKind::Sequences;
+    }
+
+    pub fn open_peak_list_prompt(&mut self) {
+        self.file_prompt = Some(String::new());
+        self.file_prompt_kind = FilePromptKind::PeakList;
+    }
+
+    pub fn file_prompt_on_key(&mut self, c: char) {
+        if let Some(path) = &mut self.file_prompt {
+            path.push(c);
+        }
+    }
+
+    pub fn file_prompt_on_backspace(&mut self) {
+        if let Some(path) = &mut self.file_prompt {
+            path.pop();
+        }
+    }
+
+    pub fn cancel_file_prompt(&mut self) {
+        self.file_prompt = None;
+    }
+
+    /// Opens the typed path, reporting the outcome in the status bar.
+    pub fn submit_file_prompt(&mut self) {
+        let Some(path) = self.file_prompt.take() else {
+            return;
+        };
+        let path = path.trim().to_string();
+
+        let opened = match self.file_prompt_kind {
+            FilePromptKind::Sequences => self.open_sequence_file(Path::new(&path))
+                .map(|count| format!("Opened {path}: {count} record(s)")),
+            FilePromptKind::PeakList => self.open_peak_list(Path::new(&path)).map(|count| match self.is_building_peptide_index() {
+                true => format!("Read {count} peaks from {path}; digesting the dataset"),
+                false => format!("Searched {count} peaks from {path}: {} protein(s) matched", self.pmf_hits.len()),
+            }),
+        };
+        self.status_message = Some(opened.unwrap_or_else(|e| {
+            warn!("Could not open {path}: {e}");
+            format!("Open failed: {e}")
+        }));
+    }
+
+    /// Reads a peak list and searches it against the dataset.
+    pub fn open_peak_list(&mut self, path: &Path) -> Result<usize, Box<dyn Error>> {
+        self.peak_list = read_peak_list(path)?;
+        self.run_pmf_search();
+        self.show_pmf_results = true;
+        Ok(self.peak_list.len())
+    }
+
+    /// Searches the current peak list. Without a digest for the current
+    /// options, the dataset is digested on a background thread and the search
+    /// re-runs once `check_peptide_index` picks the digest up.
+    pub fn run_pmf_search(&mut self) {
+        self.selected_pmf_hit = 0;
+        match self.peptide_indexes.iter().find(|index| index.built_for(&self.pmf_options)) {
+            Some(index) => self.pmf_hits = search_peak_list(index, &self.small_proteins, &self.peak_list, &self.pmf_options),
+            None => {
+                self.pmf_hits.clear();
+                self.start_peptide_index_build();
+            },
+        }
+    }
+
+    fn start_peptide_index_build(&mut self) {
+        // One digest at a time; the search that follows it starts the next
+        if self.peptide_index_receiver.is_some() {
+            return;
+        }
+
+        let (index_tx, index_rx) = mpsc::channel();
+        self.peptide_index_receiver = Some(index_rx);
+        let (proteins, options) = (Arc::clone(&self.small_proteins), self.pmf_options.clone());
+        info!("Digesting {} proteins with {} in the background", proteins.len(), options.digest.protease.name());
+        thread::spawn(move || {
+            let _ = index_tx.send(PeptideMassIndex::new(&proteins, &options));
+        });
+    }
+
+    pub fn is_building_peptide_index(&self) -> bool {
+        self.peptide_index_receiver.is_some()
+    }
+
+    /// Picks up a finished background digest and searches with it.
+    pub fn check_peptide_index(&mut self) {
+        let Some(index_rx) = &self.peptide_index_receiver else {
+            return;
+        };
+        match index_rx.try_recv() {
+            Ok(index) => {
+                self.peptide_index_receiver = None;
+                self.peptide_indexes.push(index);
+                self.run_pmf_search();
+            },
+            Err(TryRecvError::Empty) => {},
+            Err(TryRecvError::Disconnected) => {
+                error!("Peptide digest thread stopped without a result");
+                self.peptide_index_receiver = None;
+            },
+        }
+    }
+
+    pub fn cycle_pmf_protease(&mut self) {
+        self.pmf_options.digest.protease = self.pmf_options.digest.protease.next();
+        info!("Peak list search now uses {}", self.pmf_options.digest.protease.name());
+        self.run_pmf_search();
+    }
+
+    pub fn next_pmf_hit(&mut self) {
+        if !self.pmf_hits.is_empty() {
+            self.selected_pmf_hit = (self.selected_pmf_hit + 1) % self.pmf_hits.len();
+        }
+    }
+
+    pub fn prev_pmf_hit(&mut self) {
+        if !self.pmf_hits.is_empty() {
+            self.selected_pmf_hit = self.selected_pmf_hit.checked_sub(1).unwrap_or(self.pmf_hits.len() - 1);
+        }
+    }
+
+    /// Shows the selected hit's protein in the match panel, as picking it in the searcher does.
+    pub fn select_pmf_hit(&mut self) {
+        if let Some(hit) = self.pmf_hits.get(self.selected_pmf_hit) {
+            self.closest_protein = Some(self.small_proteins[hit.protein_index].clone());
+            self.active_hit = None;
+            self.matching_positions.clear();
+            self.show_pmf_results = false;
+        }
+    }
+
+    pub fn select_record(&mut self, record_index: usize) {
+        let Some(record) = self.records.get(record_index) else {
+            return;
+        };
+        debug!("Showing record {} ({})", record_index + 1, record.id);
+
+        let sequence = record.sequence.clone();
+        self.current_record = record_index;
+        self.set_input(&sequence);
+        // Records of similar length would otherwise keep the previous match
+        self.protein_match_needed = true;
+        self.last_input_length = sequence.len();
+    }
+
+    pub fn next_record(&mut self) {
+        if self.current_record + 1 < self.records.len() {
+            self.select_record(self.current_record + 1);
+        }
+    }
+
+    pub fn prev_record(&mut self) {
+        if self.current_record > 0 {
+            self.select_record(self.current_record - 1);
+        }
+    }
+
+    /// Deletes the selection, or the base before the cursor.
     pub fn on_backspace(&mut self) {
-        trace!("Removing nucleotide from {} strand",
+        trace!("Removing nucleotide before {} from {} strand", self.cursor,
                if self.is_positive_strand { "positive" } else { "negative" });
 
-        if self.is_positive_strand {
-            self.input.pop();
-        } else {
-            self.complementary.pop();
+        if self.delete_selection() || self.cursor == 0 {
+            return;
         }
-        self.update_sequences();
+        let before = self.edit_state();
+        self.cursor -= 1;
+        let cursor = self.cursor;
+        self.edited_strand().remove(cursor);
+        self.finish_edit();
+        self.record_edit(EditKind::Delete, before);
+    }
+
+    /// Deletes the selection, or the base under the cursor.
+    pub fn on_delete(&mut self) {
+        if self.delete_selection() || self.cursor >= self.input.len() {
+            return;
+        }
+        let before = self.edit_state();
+        let cursor = self.cursor;
+        self.edited_strand().remove(cursor);
+        self.finish_edit();
+        self.record_edit(EditKind::Delete, before);
     }
 
     pub fn toggle_protein_searcher(&mut self) {
@@ -375,7 +1390,7 @@ impl App {
 
     pub fn searcher_next_field(&mut self) {
         if self.show_protein_searcher {
-            self.selected_search_field = (self.selected_search_field + 1) % 9;
+            self.selected_search_field = (self.selected_search_field + 1) % SearchField::ALL.len();
             self.update_search_field();
             self.searcher_input.clear();
             self.filter_proteins();
@@ -384,7 +1399,7 @@ impl App {
 
     pub fn searcher_prev_field(&mut self) {
         if self.show_protein_searcher {
-            self.selected_search_field = if self.selected_search_field == 0 { 8 } else { self.selected_search_field - 1 };
+            self.selected_search_field = if self.selected_search_field == 0 { SearchField::ALL.len() - 1 } else { self.selected_search_field - 1 };
             self.update_search_field();
             self.searcher_input.clear();
             self.filter_proteins();
@@ -424,6 +1439,8 @@ impl App {
     pub fn select_detailed_protein(&mut self) {
         if let Some(protein) = &self.detailed_protein {
             self.closest_protein = Some(protein.clone());
+            self.active_hit = None;
+            self.matching_positions.clear();
             self.show_protein_searcher = false;
             self.show_protein_detail = false;
             self.detailed_protein = None;
@@ -441,6 +1458,9 @@ impl App {
             6 => SearchField::MaxLength,
             7 => SearchField::MinPhyloCSF,
             8 => SearchField::MaxPhyloCSF,
+            9 => SearchField::Source,
+            10 => SearchField::TmHelices,
+            11 => SearchField::SignalPeptide,
             _ => SearchField::Species,
         };
     }
@@ -483,31 +1503,82 @@ impl App {
             .collect()
     }
 
+    /// Predicts the topology of the dataset on a background thread, unless it
+    /// is predicted or being predicted already.
+    fn start_topology_prediction(&mut self) {
+        if self.topology.len() == self.small_proteins.len() || self.topology_receiver.is_some() {
+            return;
+        }
+
+        let (topology_tx, topology_rx) = mpsc::channel();
+        self.topology_receiver = Some(topology_rx);
+        let proteins = Arc::clone(&self.small_proteins);
+        info!("Predicting the topology of {} proteins in the background", proteins.len());
+        thread::spawn(move || {
+            let _ = topology_tx.send(predict_dataset_topology(&proteins));
+        });
+    }
+
+    pub fn is_predicting_topology(&self) -> bool {
+        self.topology_receiver.is_some()
+    }
+
+    /// Picks up a finished background prediction and filters with it.
+    pub fn check_topology(&mut self) {
+        let Some(topology_rx) = &self.topology_receiver else {
+            return;
+        };
+        match topology_rx.try_recv() {
+            Ok(topology) => {
+                self.topology_receiver = None;
+                self.topology = topology;
+                self.filter_proteins();
+            },
+            Err(TryRecvError::Empty) => {},
+            Err(TryRecvError::Disconnected) => {
+                error!("Topology prediction thread stopped without a result");
+                self.topology_receiver = None;
+            },
+        }
+    }
+
     fn filter_proteins(&mut self) {
         let initial_count = self.small_proteins.len();
 
+        let needs_topology = (!self.searcher_input.is_empty() && self.searcher_field.is_prediction())
+            || (self.multi_search_mode && self.search_filters.keys().any(|field| field.is_prediction()));
+        if needs_topology {
+            self.start_topology_prediction();
+        }
+
+        // Prediction filters pass everything until the prediction arrives
+        let passes = |field: SearchField, index: usize, protein: &SmallProtein, value: &str| match self.topology.get(index) {
+            None if field.is_prediction() => true,
+            topology => field.matches(protein, topology, value),
+        };
+
         if self.multi_search_mode {
-            self.filtered_proteins = self.small_proteins.iter()
-                .filter(|protein| {
+            self.filtered_proteins = self.small_proteins.iter().enumerate()
+                .filter(|(index, protein)| {
                     for (field, value) in &self.search_filters {
-                        if !self.matches_field_criteria(protein, *field, value) {
+                        if !passes(*field, *index, protein, value) {
                             return false;
                         }
                     }
                     if !self.searcher_input.is_empty()
-                        && !self.matches_field_criteria(protein, self.searcher_field, &self.searcher_input) {
+                        && !passes(self.searcher_field, *index, protein, &self.searcher_input) {
                         return false;
                     }
                     true
                 })
-                .cloned()
+                .map(|(_, protein)| protein.clone())
                 .collect();
         } else if self.searcher_input.is_empty() {
-            self.filtered_proteins = self.small_proteins.clone();
+            self.filtered_proteins = self.small_proteins.to_vec();
         } else {
-            self.filtered_proteins = self.small_proteins.iter()
-                .filter(|protein| self.matches_field_criteria(protein, self.searcher_field, &self.searcher_input))
-                .cloned()
+            self.filtered_proteins = self.small_proteins.iter().enumerate()
+                .filter(|(index, protein)| passes(self.searcher_field, *index, protein, &self.searcher_input))
+                .map(|(_, protein)| protein.clone())
                 .collect();
         }
 
@@ -518,46 +1589,6 @@ impl App {
         debug!("Protein search filtered from {} to {} results", initial_count, self.filtered_proteins.len());
     }
 
-    fn matches_field_criteria(&self, protein: &SmallProtein, field: SearchField, value: &str) -> bool {
-        let search_term = value.to_lowercase();
-
-        match field {
-            SearchField::Species => protein.species.to_lowercase().contains(&search_term),
-            SearchField::Id => protein.id.to_lowercase().contains(&search_term),
-            SearchField::Chromosome => protein.chromosome.to_lowercase().contains(&search_term),
-            SearchField::Strand => protein.strand.to_lowercase().contains(&search_term),
-            SearchField::StartCodon => protein.start_codon.to_lowercase().contains(&search_term),
-            SearchField::MinLength => {
-                if let Ok(min_length) = value.parse::<usize>() {
-                    protein.length >= min_length
-                } else {
-                    true
-                }
-            },
-            SearchField::MaxLength => {
-                if let Ok(max_length) = value.parse::<usize>() {
-                    protein.length <= max_length
-                } else {
-                    true
-                }
-            },
-            SearchField::MinPhyloCSF => {
-                if let Ok(min_phylo) = value.parse::<f64>() {
-                    protein.phylo_csf_mean >= min_phylo
-                } else {
-                    true
-                }
-            },
-            SearchField::MaxPhyloCSF => {
-                if let Ok(max_phylo) = value.parse::<f64>() {
-                    protein.phylo_csf_mean <= max_phylo
-                } else {
-                    true
-                }
-            },
-        }
-    }
-
     pub fn get_search_field_name(&self) -> &'static str {
         match self.searcher_field {
             SearchField::Species => "Species",
@@ -569,19 +1600,18 @@ impl App {
             SearchField::MaxLength => "Max Length",
             SearchField::MinPhyloCSF => "Min PhyloCSF",
             SearchField::MaxPhyloCSF => "Max PhyloCSF",
+            SearchField::Source => "Source",
+            SearchField::TmHelices => "TM Helices",
+            SearchField::SignalPeptide => "Signal Peptide",
         }
     }
 
     pub fn simulate_loading_step(&mut self) -> bool {
-        // Check if file already exists first
-        use crate::protein::dataset::get_data_dir;
-        if let Ok(data_dir) = get_data_dir() {
-            let extracted_file = data_dir.join("small_protein_dataset.txt");
-            if extracted_file.exists() {
-                // File exists, load directly without showing progress
-                self.load_datasets();
-                return true;
-            }
+        // Check if every source is already on disk first
+        if self.dataset_sources.iter().all(DatasetSource::is_available_offline) {
+            // Sources exist, load directly without showing progress
+            self.load_datasets();
+            return true;
         }
 
         match &self.dataset_progress {
@@ -597,8 +1627,8 @@ impl App {
                 let new_downloaded = bytes_downloaded + 2_000_000; // Simulate 2MB chunks
                 if let Some(total) = total_bytes {
                     if new_downloaded >= *total {
-                        // Download complete, move to extracting (restart progress)
-                        self.dataset_progress = Some(DatasetProgress::Extracting);
+                        // Download complete, move to verifying
+                        self.dataset_progress = Some(DatasetProgress::Verifying);
                     } else {
                         // Continue downloading
                         self.dataset_progress = Some(DatasetProgress::Downloading {
@@ -609,8 +1639,8 @@ impl App {
                 }
                 false // Continue loading
             },
-            Some(DatasetProgress::Extracting) => {
-                // Extraction complete, load the actual data
+            Some(DatasetProgress::Verifying) => {
+                // Verification complete, load the actual data
                 self.load_datasets();
                 true // Loading complete
             },
@@ -633,25 +1663,26 @@ impl App {
         self.progress_receiver = Some(progress_rx);
         self.protein_receiver = Some(result_rx);
 
+        let sources = self.dataset_sources.clone();
+
         // Spawn background thread for dataset loading
         thread::spawn(move || {
-            use crate::protein::download_and_parse_small_protein_dataset_with_progress;
-
             debug!("Background thread started for dataset loading");
 
             // Create progress callback that sends updates through channel
-            let progress_callback = Box::new(move |progress: DatasetProgress| {
+            let progress_callback = move |progress: DatasetProgress| {
                 let _ = progress_tx.send(progress);
-            });
+            };
 
-            // Load dataset with progress callback
-            let result = download_and_parse_small_protein_dataset_with_progress(Some(progress_callback));
+            // Load and merge every source with progress callback
+            let result = load_dataset(&sources, Some(&progress_callback));
 
             // Send final result
             let final_result = match result {
-                Ok(proteins) => {
-                    info!("Background loading completed successfully with {} proteins", proteins.len());
-                    Ok(proteins)
+                Ok(dataset) => {
+                    info!("Background loading completed successfully with {} proteins (snapshot: {})",
+                          dataset.proteins.len(), dataset.from_snapshot);
+                    Ok(dataset)
                 },
                 Err(e) => {
                     error!("Background loading failed: {e}");
@@ -669,6 +1700,9 @@ impl App {
         if let Some(ref progress_rx) = self.progress_receiver {
             while let Ok(progress) = progress_rx.try_recv() {
                 trace!("Loading progress update: {progress:?}");
+                if let DatasetProgress::Source { name, index, total } = &progress {
+                    self.current_dataset_source = Some(format!("{name} ({}/{total})", index + 1));
+                }
                 self.dataset_progress = Some(progress);
             }
         }
@@ -677,9 +1711,8 @@ impl App {
         if let Some(ref result_rx) = self.protein_receiver {
             if let Ok(result) = result_rx.try_recv() {
                 match result {
-                    Ok(proteins) => {
-                        self.loaded_proteins_count = proteins.len();
-                        self.small_proteins = proteins;
+                    Ok(dataset) => {
+                        self.set_loaded_dataset(dataset);
                         self.is_loading_proteins = false;
                         self.dataset_progress = Some(DatasetProgress::Complete);
                         info!("Loading completed successfully. {} proteins loaded", self.loaded_proteins_count);
@@ -704,4 +1737,178 @@ impl Default for App {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protein::{MassType, Protease, digest, mass_to_charge, parse_small_protein_dataset};
+
+    #[test]
+    fn test_cursor_editing_keeps_tracks_in_sync() {
+        let mut app = App::new();
+        app.set_input("ATGAAATAG");
+
+        app.move_cursor_home(false);
+        app.move_cursor_next_codon(false);
+        app.on_key('c');
+        assert_eq!(app.input, "ATGCAAATAG");
+        assert_eq!(app.complementary, "TACGTTTATC");
+        assert_eq!(app.get_current_partial_codon(), "G");
+
+        app.toggle_overwrite_mode();
+        app.on_key('u');
+        assert_eq!(app.input, "ATGCTAATAG");
+        assert_eq!(app.amino_acids_colored.len(), 4);
+
+        // Overwriting the last base keeps the length but still re-matches
+        app.move_cursor_end(false);
+        app.move_cursor_left(false);
+        app.perform_protein_matching_if_needed();
+        app.on_key('a');
+        assert_eq!((app.input.as_str(), app.cursor), ("ATGCTAATAA", 10));
+        assert!(app.protein_match_needed);
+
+        app.move_cursor_end(false);
+        app.move_cursor_prev_codon(true);
+        app.move_cursor_prev_codon(true);
+        assert_eq!(app.selection_range(), Some(6..10));
+        app.on_backspace();
+        assert_eq!((app.input.as_str(), app.cursor), ("ATGCTA", 6));
+
+        app.move_cursor_home(false);
+        app.on_delete();
+        assert_eq!(app.input, "TGCTA");
+        assert_eq!(app.mrna.len(), 5);
+    }
+
+    fn wait_for_peptide_index(app: &mut App) {
+        while app.is_building_peptide_index() {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            app.check_peptide_index();
+        }
+    }
+
+    #[test]
+    fn test_peak_list_prompt_ranks_and_pins_proteins() {
+        let proteins = parse_small_protein_dataset(include_str!("../tests/fixtures/smprot_sample.txt").as_bytes(), None).unwrap();
+        let target = proteins[2].clone();
+        let mut app = App::new();
+        app.set_small_proteins(proteins);
+
+        let peaks: Vec<String> = digest(&target.aa_seq, &app.pmf_options.digest).iter()
+            .map(|peptide| mass_to_charge(peptide.mass(MassType::Monoisotopic).unwrap(), 1).to_string())
+            .collect();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("peaks.txt");
+        std::fs::write(&path, peaks.join("\n")).unwrap();
+
+        app.open_peak_list_prompt();
+        app.file_prompt = Some(path.display().to_string());
+        app.submit_file_prompt();
+        assert!(app.show_pmf_results && app.file_prompt.is_none());
+        assert!(app.is_building_peptide_index() && app.pmf_hits.is_empty());
+        wait_for_peptide_index(&mut app);
+        assert_eq!(app.small_proteins[app.pmf_hits[0].protein_index].id, target.id);
+
+        // Each protease is digested once and then reused
+        app.cycle_pmf_protease();
+        wait_for_peptide_index(&mut app);
+        assert_eq!(app.peptide_indexes.len(), 2);
+        app.pmf_options.digest.protease = Protease::Trypsin;
+        app.run_pmf_search();
+        assert!(!app.is_building_peptide_index() && !app.pmf_hits.is_empty());
+        app.select_pmf_hit();
+        assert!(!app.show_pmf_results);
+        assert_eq!(app.closest_protein.map(|protein| protein.id), Some(target.id));
+    }
+
+    #[test]
+    fn test_paste_inserts_cleaned_sequence_at_cursor() {
+        let mut app = App::new();
+        app.set_input("ATGTAG");
+        app.move_cursor_to(3, false);
+        app.protein_match_needed = false;
+
+        app.paste(">clone 7\n1 aaa ccc\n7 gg\n");
+        assert_eq!(app.input, "ATGAAACCCGGTAG");
+        assert_eq!(app.cursor, 11);
+        assert!(app.protein_match_needed);
+        assert_eq!(app.status_message.as_deref(), Some("Pasted 8 nt"));
+
+        app.paste("12 34");
+        assert_eq!(app.input.len(), 14);
+    }
+
+    #[test]
+    fn test_undo_groups_typing_and_pastes() {
+        let mut app = App::new();
+        app.set_input("ATG");
+
+        for c in "AAA".chars() {
+            app.on_key(c);
+        }
+        app.paste("cccggg");
+        app.on_backspace();
+        app.on_backspace();
+        app.toggle_strand_mode();
+        app.reverse_complement();
+        assert_eq!(app.input, "GCCCAAAGTA");
+
+        app.undo();
+        assert!(!app.is_positive_strand);
+        app.undo();
+        assert_eq!((app.input.as_str(), app.is_positive_strand), ("ATGAAACCCG", true));
+        app.undo();
+        assert_eq!(app.input, "ATGAAACCCGGG");
+        app.undo();
+        assert_eq!((app.input.as_str(), app.cursor), ("ATGAAA", 6));
+        app.undo();
+        assert_eq!(app.input, "ATG");
+        app.undo();
+        assert_eq!(app.status_message.as_deref(), Some("Nothing to undo"));
+
+        app.redo();
+        app.redo();
+        assert_eq!(app.input, "ATGAAACCCGGG");
+        app.on_key('T');
+        assert!(!app.history.can_redo());
+    }
+
+    #[test]
+    fn test_hydropathy_profile_marks_tm_helices_at_any_window() {
+        let mut app = App::new();
+        let membrane = format!("MKDE{}RKDE", "L".repeat(20));
+        let helices = app.profile(&membrane, "").highlights;
+        assert_eq!(helices.len(), 1);
+        app.change_profile_window(-10);
+        assert_eq!(app.profile(&membrane, "").highlights, helices);
+        // A short hydrophobic run tops narrow windows but is no helix
+        assert!(app.profile("MKDELLLLLRKDE", "").highlights.is_empty());
+    }
+
+    #[test]
+    fn test_prediction_filters_predict_the_dataset_once() {
+        let proteins = parse_small_protein_dataset(include_str!("../tests/fixtures/smprot_sample.txt").as_bytes(), None).unwrap();
+        let mut app = App::new();
+        app.set_small_proteins(proteins);
+        assert!(app.topology.is_empty());
+
+        app.searcher_field = SearchField::SignalPeptide;
+        app.searcher_input = "yes".to_string();
+        app.filter_proteins();
+        assert!(app.is_predicting_topology());
+        while app.is_predicting_topology() {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            app.check_topology();
+        }
+        assert_eq!(app.topology.len(), app.small_proteins.len());
+        assert_eq!(app.filtered_proteins.iter().map(|protein| protein.id.as_str()).collect::<Vec<_>>(), ["SPROHSA100925"]);
+
+        // Cached predictions are used as they are
+        let secreted = app.small_proteins.iter().position(|protein| protein.id == "SPROHSA100925").unwrap();
+        app.topology[secreted].signal_peptide = None;
+        app.filter_proteins();
+        assert!(app.filtered_proteins.is_empty());
+    }
+}
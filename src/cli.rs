@@ -0,0 +1,775 @@
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use clap::{Args, Subcommand, ValueEnum};
+use log::info;
+use serde_json::{json, Value};
+use crate::app::{App, MatchMode, SearchField};
+use crate::protein::{
+    DatasetSource, DigestOptions, LoadedDataset, MassType, Modification, NucleotideSequence, PeptideMassIndex, PmfOptions,
+    Protease, SmallProtein, SubstitutionMatrix, TopologyPrediction, digest, load_dataset, mass_to_charge, peptide_mass, read_peak_list,
+    search_peak_list,
+};
+use crate::sequence::*;
+
+/// Headless commands; the TUI only starts when none is given.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Translate sequences in frame +1, or in all six frames
+    Translate {
+        #[command(flatten)]
+        input: SequenceInput,
+
+        /// Translate all six reading frames
+        #[arg(long)]
+        all_frames: bool,
+    },
+    /// Print the reverse complement of each sequence
+    Revcomp {
+        #[command(flatten)]
+        input: SequenceInput,
+    },
+    /// Composition, codon and ORF statistics for each sequence
+    Stats {
+        #[command(flatten)]
+        input: SequenceInput,
+    },
+    /// List open reading frames in all six frames, nested ones included
+    Orfs {
+        #[command(flatten)]
+        input: SequenceInput,
+
+        /// Shortest ORF to report, in amino acids
+        #[arg(long, default_value_t = 1)]
+        min_length: usize,
+
+        /// Comma-separated start codons, e.g. ATG,CTG,GTG,TTG,ACG
+        #[arg(long, value_name = "CODONS", value_delimiter = ',', default_value = "ATG", value_parser = parse_start_codon)]
+        start_codons: Vec<String>,
+    },
+    /// Align sequences against the small protein datasets
+    Match {
+        #[command(flatten)]
+        input: SequenceInput,
+
+        /// Align nucleotides directly, or translate and align proteins
+        #[arg(long, value_enum, default_value_t = MatchMode::Nucleotide)]
+        mode: MatchMode,
+
+        /// Substitution matrix for protein matching
+        #[arg(long, value_enum, default_value_t = SubstitutionMatrix::Blosum62)]
+        matrix: SubstitutionMatrix,
+
+        /// Hits to report per sequence
+        #[arg(long, default_value_t = 10)]
+        hits: usize,
+
+        /// Only report hits at or below this E-value
+        #[arg(long, value_name = "E")]
+        max_evalue: Option<f64>,
+    },
+    /// One row per record: composition, translation and best protein hit
+    Batch {
+        #[command(flatten)]
+        input: SequenceInput,
+
+        /// Align nucleotides directly, or translate and align proteins
+        #[arg(long, value_enum, default_value_t = MatchMode::Nucleotide)]
+        mode: MatchMode,
+
+        /// Substitution matrix for protein matching
+        #[arg(long, value_enum, default_value_t = SubstitutionMatrix::Blosum62)]
+        matrix: SubstitutionMatrix,
+
+        /// Skip protein matching, so no dataset is loaded
+        #[arg(long)]
+        no_match: bool,
+    },
+    /// Filter the small protein datasets by field
+    Search {
+        /// FIELD=VALUE, e.g. species=sapiens, min-length=20 or tm-helices=yes; repeat to combine
+        #[arg(long = "filter", value_name = "FIELD=VALUE", value_parser = parse_filter)]
+        filters: Vec<(SearchField, String)>,
+
+        /// Stop after this many proteins
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// List the peptides a protease cuts amino acid sequences or dataset proteins into
+    Digest {
+        /// Amino acid sequences to digest
+        #[arg(value_name = "PROTEIN")]
+        proteins: Vec<String>,
+
+        /// Dataset protein ids to digest; repeat for several
+        #[arg(long = "id", value_name = "ID")]
+        ids: Vec<String>,
+
+        #[command(flatten)]
+        digest: DigestArgs,
+
+        /// Residue masses to use
+        #[arg(long, value_enum, default_value_t = MassType::Monoisotopic)]
+        mass_type: MassType,
+
+        /// Charge state of the m/z column
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        charge: u32,
+    },
+    /// Search a peak list against digests of the small protein datasets
+    Pmf {
+        /// Plain text file with one peptide mass or m/z per line
+        #[arg(value_name = "PEAKS")]
+        peaks: PathBuf,
+
+        #[command(flatten)]
+        digest: DigestArgs,
+
+        /// Residue masses to use
+        #[arg(long, value_enum, default_value_t = MassType::Monoisotopic)]
+        mass_type: MassType,
+
+        /// Mass tolerance in parts per million
+        #[arg(long, default_value_t = 10.0)]
+        tolerance_ppm: f64,
+
+        /// Charge of the listed ions; 0 for neutral masses
+        #[arg(long, visible_alias = "charge", default_value_t = 1)]
+        peak_charge: u32,
+
+        /// Proteins to report
+        #[arg(long, default_value_t = 10)]
+        hits: usize,
+    },
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DigestArgs {
+    #[arg(long, value_enum, default_value_t = Protease::Trypsin)]
+    pub protease: Protease,
+
+    /// Most uncut sites a peptide may span
+    #[arg(long, default_value_t = 1)]
+    pub missed_cleavages: usize,
+
+    /// Shortest peptide, in residues
+    #[arg(long, default_value_t = 5)]
+    pub min_length: usize,
+
+    /// Longest peptide, in residues
+    #[arg(long, default_value_t = 40)]
+    pub max_length: usize,
+
+    /// acetyl, amide, oxidation or phospho, applied wherever a peptide has a
+    /// site for it; repeat to modify several sites
+    #[arg(long = "modification", value_name = "MOD")]
+    pub modifications: Vec<Modification>,
+}
+
+impl DigestArgs {
+    pub fn options(&self) -> DigestOptions {
+        DigestOptions {
+            protease: self.protease,
+            missed_cleavages: self.missed_cleavages,
+            min_length: self.min_length,
+            max_length: self.max_length,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Tsv,
+    Json,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct SequenceInput {
+    /// Sequences to process; read from --file or stdin when none are given
+    #[arg(value_name = "SEQUENCE")]
+    pub sequences: Vec<String>,
+
+    /// Read sequences from a FASTA, FASTQ or one-per-line file, optionally gzipped
+    #[arg(long, short, value_name = "PATH", conflicts_with = "sequences")]
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedSequence {
+    pub name: String,
+    pub sequence: NucleotideSequence,
+}
+
+impl SequenceInput {
+    pub fn read(&self) -> Result<Vec<NamedSequence>, Box<dyn Error>> {
+        if !self.sequences.is_empty() {
+            return self.sequences.iter().enumerate()
+                .map(|(i, sequence)| Ok(NamedSequence {
+                    name: format!("seq{}", i + 1),
+                    sequence: NucleotideSequence::new(sequence).map_err(|e| format!("argument {}: {e}", i + 1))?,
+                }))
+                .collect();
+        }
+
+        let records = match &self.file {
+            Some(path) => read_sequence_file(path)?,
+            None => read_sequence_records(io::stdin().lock())?,
+        };
+
+        Ok(named_sequences(&records)?)
+    }
+}
+
+pub fn named_sequences(records: &[SequenceRecord]) -> Result<Vec<NamedSequence>, String> {
+    records.iter()
+        .map(|record| Ok(NamedSequence { name: record.id.clone(), sequence: record.nucleotides()? }))
+        .collect()
+}
+
+fn parse_filter(spec: &str) -> Result<(SearchField, String), String> {
+    let (field, value) = spec.split_once('=')
+        .ok_or_else(|| format!("filter '{spec}' should look like FIELD=VALUE"))?;
+    Ok((field.parse()?, value.trim().to_string()))
+}
+
+/// Runs `command` and writes its result to `out` in `format`.
+pub fn run(
+    command: Command,
+    format: OutputFormat,
+    code: &'static GeneticCode,
+    sources: &[DatasetSource],
+    out: &mut dyn Write
+) -> Result<(), Box<dyn Error>> {
+    let table = match command {
+        Command::Translate { input, all_frames } => translate_table(&input.read()?, all_frames, code)?,
+        Command::Revcomp { input } => revcomp_table(&input.read()?),
+        Command::Stats { input } => stats_table(&input.read()?, code)?,
+        Command::Orfs { input, min_length, start_codons } => {
+            orfs_table(&input.read()?, &OrfOptions { start_codons, min_length }, code)?
+        },
+        Command::Match { input, mode, matrix, hits, max_evalue } => {
+            // Sequence errors are reported before the possibly slow dataset load
+            let sequences = input.read()?;
+            match_table(load_headless_dataset(sources)?, &sequences, mode, matrix, code, hits, max_evalue)
+        },
+        Command::Batch { input, mode, matrix, no_match } => {
+            let sequences = input.read()?;
+            let dataset = if no_match { None } else { Some(load_headless_dataset(sources)?) };
+            batch_table(dataset, &sequences, mode, matrix, code)?
+        },
+        Command::Search { filters, limit } => {
+            search_table(&load_headless_dataset(sources)?.proteins, &filters, limit)
+        },
+        Command::Digest { proteins, ids, digest, mass_type, charge } => {
+            let mut named: Vec<(String, String)> = proteins.into_iter().enumerate()
+                .map(|(i, protein)| (format!("protein{}", i + 1), protein.trim().to_uppercase()))
+                .collect();
+            if !ids.is_empty() {
+                let dataset = load_headless_dataset(sources)?;
+                for id in ids {
+                    let protein = dataset.proteins.iter().find(|protein| protein.id == id)
+                        .ok_or_else(|| format!("no protein with id '{id}' in the datasets"))?;
+                    named.push((id, protein.aa_seq.to_string()));
+                }
+            }
+            if named.is_empty() {
+                return Err("give amino acid sequences or --id to digest".into());
+            }
+            digest_table(&named, &digest.options(), mass_type, &digest.modifications, charge)?
+        },
+        Command::Pmf { peaks, digest, mass_type, tolerance_ppm, peak_charge, hits } => {
+            let peaks = read_peak_list(&peaks)?;
+            let options = PmfOptions { digest: digest.options(), mass_type, modifications: digest.modifications, tolerance_ppm, peak_charge };
+            pmf_table(&load_headless_dataset(sources)?.proteins, &peaks, &options, hits)
+        },
+    };
+
+    match table.write(format, out) {
+        // Output piped into e.g. `head` closing early is not an error
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+fn load_headless_dataset(sources: &[DatasetSource]) -> Result<LoadedDataset, Box<dyn Error>> {
+    let dataset = load_dataset(sources, None)?;
+    info!("Loaded {} proteins for headless command", dataset.proteins.len());
+    Ok(dataset)
+}
+
+pub fn translate_table(sequences: &[NamedSequence], all_frames: bool, code: &GeneticCode) -> Result<Table, String> {
+    let mut table = Table::new(&["name", "frame", "protein"]);
+
+    for named in sequences {
+        if all_frames {
+            for (frame, protein) in translate_all_reading_frames(&named.sequence, code)?.into_iter().enumerate() {
+                table.push(vec![json!(named.name), json!(READING_FRAME_LABELS[frame]), json!(protein)]);
+            }
+        } else {
+            let complete_len = named.sequence.len() - named.sequence.len() % 3;
+            let protein = translate_dna_to_amino(&named.sequence[..complete_len], code)?;
+            table.push(vec![json!(named.name), json!(READING_FRAME_LABELS[0]), json!(protein)]);
+        }
+    }
+
+    Ok(table)
+}
+
+pub fn revcomp_table(sequences: &[NamedSequence]) -> Table {
+    let mut table = Table::new(&["name", "sequence"]);
+    for named in sequences {
+        table.push(vec![json!(named.name), json!(get_reverse_complement(&named.sequence))]);
+    }
+    table
+}
+
+pub fn stats_table(sequences: &[NamedSequence], code: &GeneticCode) -> Result<Table, String> {
+    let mut table = Table::new(&[
+        "name", "length", "gc_percent", "at_percent", "codons", "start_codons", "stop_codons",
+        "orfs", "longest_orf_aa", "protein_mass_da",
+    ]);
+
+    for named in sequences {
+        let dna = &named.sequence;
+        let complete = &dna[..dna.len() - dna.len() % 3];
+        let orfs = find_open_reading_frames(dna, code, &OrfOptions::default())?;
+
+        table.push(vec![
+            json!(named.name),
+            json!(dna.len()),
+            json!(calculate_gc_content(dna)),
+            json!(calculate_at_content(dna)),
+            json!(count_total_codons(dna)),
+            json!(count_start_codons(dna, code)),
+            json!(count_stop_codons(dna, code)),
+            json!(orfs.len()),
+            json!(orfs.iter().map(|orf| orf.protein.len()).max().unwrap_or(0)),
+            json!(estimate_molecular_weight(complete, code)),
+        ]);
+    }
+
+    Ok(table)
+}
+
+pub fn orfs_table(sequences: &[NamedSequence], options: &OrfOptions, code: &GeneticCode) -> Result<Table, String> {
+    let mut table = Table::new(&["name", "frame", "start", "end", "length_aa", "start_codon", "protein"]);
+
+    for named in sequences {
+        for orf in find_open_reading_frames(&named.sequence, code, options)? {
+            // 1-based inclusive coordinates, like the dataset's
+            table.push(vec![
+                json!(named.name),
+                json!(orf.frame_label()),
+                json!(orf.start + 1),
+                json!(orf.end),
+                json!(orf.protein.len()),
+                json!(orf.start_codon),
+                json!(orf.protein),
+            ]);
+        }
+    }
+
+    Ok(table)
+}
+
+pub fn match_table(
+    dataset: LoadedDataset,
+    sequences: &[NamedSequence],
+    mode: MatchMode,
+    matrix: SubstitutionMatrix,
+    code: &'static GeneticCode,
+    hits: usize,
+    max_evalue: Option<f64>
+) -> Table {
+    let mut table = Table::new(&[
+        "query", "rank", "protein_id", "species", "source", "score", "bit_score", "e_value",
+        "identity", "coverage", "strand", "frame", "cigar",
+    ]);
+
+    let mut app = App::new();
+    app.set_loaded_dataset(dataset);
+    app.match_mode = mode;
+    app.protein_scoring.matrix = matrix;
+    app.genetic_code = code;
+    app.max_protein_hits = hits;
+
+    for named in sequences {
+        app.set_input(&named.sequence);
+        app.find_closest_protein();
+
+        let reported = app.protein_hits.iter()
+            .filter(|hit| max_evalue.is_none_or(|threshold| hit.is_significant(threshold)));
+        for (rank, hit) in reported.enumerate() {
+            let protein = &app.small_proteins[hit.protein_index];
+            table.push(vec![
+                json!(named.name),
+                json!(rank + 1),
+                json!(protein.id),
+                json!(protein.species),
+                json!(protein.source),
+                json!(hit.score()),
+                json!(hit.bit_score),
+                json!(hit.e_value),
+                json!(hit.identity),
+                json!(hit.coverage),
+                json!(if hit.is_positive_strand { "+" } else { "-" }),
+                json!(hit.frame.map(|frame| READING_FRAME_LABELS[frame])),
+                json!(hit.alignment.cigar),
+            ]);
+        }
+    }
+
+    table
+}
+
+pub fn batch_table(
+    dataset: Option<LoadedDataset>,
+    sequences: &[NamedSequence],
+    mode: MatchMode,
+    matrix: SubstitutionMatrix,
+    code: &'static GeneticCode
+) -> Result<Table, String> {
+    let mut table = Table::new(&[
+        "name", "length", "gc_percent", "at_percent", "protein", "longest_orf_aa",
+        "best_hit", "species", "e_value", "identity", "strand", "frame",
+    ]);
+
+    let mut app = dataset.map(|dataset| {
+        let mut app = App::new();
+        app.set_loaded_dataset(dataset);
+        app.match_mode = mode;
+        app.protein_scoring.matrix = matrix;
+        app.genetic_code = code;
+        app
+    });
+
+    for named in sequences {
+        let dna = &named.sequence;
+        let protein = translate_dna_to_amino(&dna[..dna.len() - dna.len() % 3], code)?;
+        let longest_orf = find_open_reading_frames(dna, code, &OrfOptions::default())?.iter().map(|orf| orf.protein.len()).max().unwrap_or(0);
+
+        let mut row = vec![
+            json!(named.name),
+            json!(dna.len()),
+            json!(calculate_gc_content(dna)),
+            json!(calculate_at_content(dna)),
+            json!(protein),
+            json!(longest_orf),
+        ];
+
+        let best_hit = app.as_mut().and_then(|app| {
+            app.set_input(dna);
+            app.find_closest_protein();
+            app.protein_hits.first().map(|hit| (hit.clone(), &app.small_proteins[hit.protein_index]))
+        });
+        match best_hit {
+            Some((hit, protein)) => row.extend([
+                json!(protein.id),
+                json!(protein.species),
+                json!(hit.e_value),
+                json!(hit.identity),
+                json!(if hit.is_positive_strand { "+" } else { "-" }),
+                json!(hit.frame.map(|frame| READING_FRAME_LABELS[frame])),
+            ]),
+            None => row.extend([Value::Null, Value::Null, Value::Null, Value::Null, Value::Null, Value::Null]),
+        }
+
+        table.push(row);
+    }
+
+    Ok(table)
+}
+
+pub fn search_table(proteins: &[SmallProtein], filters: &[(SearchField, String)], limit: Option<usize>) -> Table {
+    let mut table = Table::new(&[
+        "id", "species", "source", "chromosome", "start", "stop", "strand", "length",
+        "start_codon", "phylo_csf_mean", "aa_seq",
+    ]);
+
+    let matching = proteins.iter()
+        .filter(|protein| {
+            let topology = filters.iter().any(|(field, _)| field.is_prediction())
+                .then(|| TopologyPrediction::predict(&protein.aa_seq));
+            filters.iter().all(|(field, value)| field.matches(protein, topology.as_ref(), value))
+        })
+        .take(limit.unwrap_or(usize::MAX));
+    for protein in matching {
+        table.push(vec![
+            json!(protein.id),
+            json!(protein.species),
+            json!(protein.source),
+            json!(protein.chromosome),
+            json!(protein.start),
+            json!(protein.stop),
+            json!(protein.strand.to_string()),
+            json!(protein.length),
+            json!(protein.start_codon.as_deref()),
+            json!(protein.phylo_csf_mean),
+            json!(protein.aa_seq.as_str()),
+        ]);
+    }
+
+    table
+}
+
+pub fn digest_table(
+    proteins: &[(String, String)],
+    options: &DigestOptions,
+    mass_type: MassType,
+    modifications: &[Modification],
+    charge: u32
+) -> Result<Table, String> {
+    let mut table = Table::new(&["protein", "start", "end", "missed_cleavages", "peptide", "modifications", "mass", "charge", "mz"]);
+
+    for (name, sequence) in proteins {
+        let length = sequence.trim().trim_end_matches('*').len();
+        for peptide in digest(sequence, options) {
+            let applied = peptide.modifications(modifications, length);
+            let mass = peptide_mass(&peptide.sequence, mass_type, &applied).map_err(|e| format!("{name}: {e}"))?;
+            // 1-based inclusive coordinates, like the ORF table's
+            table.push(vec![
+                json!(name),
+                json!(peptide.start + 1),
+                json!(peptide.end),
+                json!(peptide.missed_cleavages),
+                json!(peptide.sequence),
+                json!(applied.iter().map(|modification| modification.name()).collect::<Vec<_>>().join(",")),
+                json!(mass),
+                json!(charge),
+                json!(mass_to_charge(mass, charge)),
+            ]);
+        }
+    }
+
+    Ok(table)
+}
+
+pub fn pmf_table(proteins: &[SmallProtein], peaks: &[f64], options: &PmfOptions, hits: usize) -> Table {
+    let mut table = Table::new(&[
+        "rank", "protein_id", "species", "matched", "peptides", "coverage", "mean_error_ppm", "matched_peptides",
+    ]);
+
+    let index = PeptideMassIndex::new(proteins, options);
+    for (rank, hit) in search_peak_list(&index, proteins, peaks, options).iter().take(hits).enumerate() {
+        let protein = &proteins[hit.protein_index];
+        let mean_error = hit.matches.iter().map(|found| found.error_ppm).sum::<f64>() / hit.matched_peptides() as f64;
+        let sequences = hit.matches.iter()
+            .map(|found| &protein.aa_seq[found.peptide.start as usize..found.peptide.end as usize])
+            .collect::<Vec<_>>()
+            .join(",");
+        table.push(vec![
+            json!(rank + 1),
+            json!(protein.id),
+            json!(protein.species),
+            json!(hit.matched_peptides()),
+            json!(hit.candidate_peptides),
+            json!(hit.coverage),
+            json!(mean_error),
+            json!(sequences),
+        ]);
+    }
+
+    table
+}
+
+/// Rows of JSON values under fixed column names, written as aligned text,
+/// TSV or a JSON array of objects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub columns: Vec<&'static str>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    pub fn new(columns: &[&'static str]) -> Table {
+        Table { columns: columns.to_vec(), rows: Vec::new() }
+    }
+
+    pub fn push(&mut self, row: Vec<Value>) {
+        debug_assert_eq!(row.len(), self.columns.len());
+        self.rows.push(row);
+    }
+
+    pub fn write(&self, format: OutputFormat, out: &mut dyn Write) -> io::Result<()> {
+        match format {
+            OutputFormat::Text => self.write_text(out),
+            OutputFormat::Tsv => self.write_tsv(out),
+            OutputFormat::Json => self.write_json(out),
+        }
+    }
+
+    fn write_text(&self, out: &mut dyn Write) -> io::Result<()> {
+        let cells: Vec<Vec<String>> = self.rows.iter()
+            .map(|row| row.iter().map(text_cell).collect())
+            .collect();
+        let widths: Vec<usize> = self.columns.iter().enumerate()
+            .map(|(i, column)| cells.iter().map(|row| row[i].len()).fold(column.len(), usize::max))
+            .collect();
+
+        let header = self.columns.iter().map(|column| column.to_string()).collect::<Vec<_>>();
+        for row in std::iter::once(&header).chain(&cells) {
+            let line = row.iter().zip(&widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(out, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+
+    fn write_tsv(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{}", self.columns.join("\t"))?;
+        for row in &self.rows {
+            let line = row.iter().map(tsv_cell).collect::<Vec<_>>().join("\t");
+            writeln!(out, "{line}")?;
+        }
+        Ok(())
+    }
+
+    fn write_json(&self, out: &mut dyn Write) -> io::Result<()> {
+        let records: Vec<Value> = self.rows.iter()
+            .map(|row| Value::Object(self.columns.iter().map(|column| column.to_string()).zip(row.iter().cloned()).collect()))
+            .collect();
+        serde_json::to_writer_pretty(&mut *out, &records)?;
+        writeln!(out)
+    }
+}
+
+fn text_cell(value: &Value) -> String {
+    match value {
+        Value::Null => "NA".to_string(),
+        Value::String(text) => text.clone(),
+        Value::Number(number) => match number.as_f64() {
+            Some(float) if number.is_f64() && float != 0.0 && float.abs() < 1e-3 => format!("{float:.2e}"),
+            Some(float) if number.is_f64() => format!("{float:.2}"),
+            _ => number.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+fn tsv_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protein::{ParseReport, ProteinIndex, parse_small_protein_dataset};
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/smprot_sample.txt");
+
+    fn sequences(text: &str) -> Vec<NamedSequence> {
+        named_sequences(&parse_sequence_records(text.as_bytes()).unwrap()).unwrap()
+    }
+
+    fn render(table: &Table, format: OutputFormat) -> String {
+        let mut out = Vec::new();
+        table.write(format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_sequence_lines_and_formats() {
+        let sequences = sequences("# query\natgAAAtag\n\nACGTT\n");
+        assert_eq!(sequences.len(), 2);
+        assert_eq!(sequences[1].name, "seq2");
+
+        let table = revcomp_table(&sequences);
+        assert_eq!(render(&table, OutputFormat::Tsv), "name\tsequence\nseq1\tCTATTTCAT\nseq2\tAACGT\n");
+        assert_eq!(render(&table, OutputFormat::Text), "name  sequence\nseq1  CTATTTCAT\nseq2  AACGT\n");
+
+        let json: Value = serde_json::from_str(&render(&translate_table(&sequences, false, GeneticCode::standard()).unwrap(), OutputFormat::Json)).unwrap();
+        assert_eq!(json[0]["protein"], "MK*");
+        assert_eq!(json[1]["protein"], "T");
+    }
+
+    #[test]
+    fn test_genetic_code_changes_translation() {
+        let sequences = sequences("ATGTGAAGATAA\n");
+        let mitochondrial = parse_genetic_code("2").unwrap();
+
+        let standard = translate_table(&sequences, false, GeneticCode::standard()).unwrap();
+        assert_eq!(standard.rows[0][2], "M*R*");
+        assert_eq!(translate_table(&sequences, false, mitochondrial).unwrap().rows[0][2], "MW**");
+
+        let stats = stats_table(&sequences, mitochondrial).unwrap();
+        // TGA is read through, so the ORF runs to the AGA stop
+        assert_eq!(stats.rows[0][8], json!(2));
+        assert!(parse_genetic_code("7").is_err());
+    }
+
+    #[test]
+    fn test_orfs_in_both_strands() {
+        // ATG AAA TAG forward, and its reverse complement embedded after a spacer
+        let sequences = sequences(">fwd_and_rev\nATGAAATAGCCC\nTTACTTCAT\n");
+        let table = orfs_table(&sequences, &OrfOptions::default(), GeneticCode::standard()).unwrap();
+        let rows: Vec<(String, u64, u64)> = table.rows.iter()
+            .map(|row| (row[1].as_str().unwrap().to_string(), row[2].as_u64().unwrap(), row[3].as_u64().unwrap()))
+            .collect();
+        assert_eq!(rows, vec![("+1".to_string(), 1, 9), ("-1".to_string(), 13, 21)]);
+    }
+
+    #[test]
+    fn test_search_and_match_against_fixture() {
+        let proteins = parse_small_protein_dataset(FIXTURE.as_bytes(), None).unwrap();
+
+        let filters = vec![("strand".parse().unwrap(), "-".to_string()), ("min-length".parse().unwrap(), "60".to_string())];
+        let found = search_table(&proteins, &filters, None);
+        assert!(!found.rows.is_empty());
+        assert!(found.rows.iter().all(|row| row[6] == "-" && row[7].as_u64().unwrap() >= 60));
+        assert!(parse_filter("colour=red").is_err());
+
+        let secreted = search_table(&proteins, &[parse_filter("signal-peptide=yes").unwrap()], None);
+        assert_eq!(secreted.rows.iter().map(|row| row[0].as_str().unwrap()).collect::<Vec<_>>(), ["SPROHSA100925"]);
+        let membrane = vec![parse_filter("tm-helices=yes").unwrap(), parse_filter("signal-peptide=no").unwrap()];
+        assert!(search_table(&proteins, &membrane, None).rows.is_empty());
+
+        let target = proteins[3].clone();
+        let query = NamedSequence { name: "q".to_string(), sequence: NucleotideSequence::new(&target.rna_seq[20..140]).unwrap() };
+        let index = ProteinIndex::new(&proteins);
+        let dataset = LoadedDataset { proteins, index, report: ParseReport::default(), from_snapshot: false };
+        let hits = match_table(dataset.clone(), std::slice::from_ref(&query), MatchMode::Nucleotide, SubstitutionMatrix::Blosum62, GeneticCode::standard(), 3, None);
+        assert_eq!(hits.rows[0][2], json!(target.id));
+        assert_eq!(hits.rows[0][10], "+");
+
+        let batch = batch_table(Some(dataset), &[query.clone(), query], MatchMode::Protein, SubstitutionMatrix::Pam250, GeneticCode::standard()).unwrap();
+        assert_eq!(batch.rows.len(), 2);
+        assert_eq!(batch.rows[1][6], json!(target.id));
+        assert_eq!(batch_table(None, &sequences("ATG"), MatchMode::Protein, SubstitutionMatrix::Blosum62, GeneticCode::standard()).unwrap().rows[0][6], Value::Null);
+    }
+
+    #[test]
+    fn test_digest_and_pmf_tables() {
+        let proteins = parse_small_protein_dataset(FIXTURE.as_bytes(), None).unwrap();
+        let options = PmfOptions::default();
+
+        let target = &proteins[7];
+        let named = [(target.id.clone(), target.aa_seq.to_string())];
+        let peptides = digest_table(&named, &options.digest, options.mass_type, &[], 1).unwrap();
+        assert!(!peptides.rows.is_empty());
+        assert!(peptides.rows.iter().all(|row| row[0] == json!(target.id) && row[3].as_u64().unwrap() <= 1));
+        assert!(digest_table(&[("x".to_string(), "MAKXXXXXR".to_string())], &options.digest, options.mass_type, &[], 1).is_err());
+
+        // Doubly charged ions of the N-terminally acetylated protein
+        let modified = digest_table(&named, &options.digest, options.mass_type, &["acetyl".parse().unwrap()], 2).unwrap();
+        assert_eq!(modified.rows[0][1], 1);
+        assert!(modified.rows.iter().all(|row| (row[1] == 1) == (row[5] == "N-terminal acetylation")));
+        let acetyl = modified.rows[0][6].as_f64().unwrap() - peptides.rows[0][6].as_f64().unwrap();
+        assert!((acetyl - 42.010_565).abs() < 1e-6);
+        let last = peptides.rows.len() - 1;
+        assert!((modified.rows[last][8].as_f64().unwrap() - mass_to_charge(peptides.rows[last][6].as_f64().unwrap(), 2)).abs() < 1e-9);
+
+        let peaks: Vec<f64> = peptides.rows.iter().map(|row| row[8].as_f64().unwrap()).collect();
+        let ranked = pmf_table(&proteins, &peaks, &options, 3);
+        assert_eq!(ranked.rows[0][1], json!(target.id));
+        assert_eq!(ranked.rows[0][3], json!(peaks.len()));
+        assert!(ranked.rows.len() <= 3);
+    }
+}
@@ -0,0 +1,106 @@
+use log::trace;
+
+/// Steps kept before the oldest are dropped.
+pub const DEFAULT_HISTORY_LIMIT: usize = 200;
+
+/// Editor contents as they were before or after an undoable edit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditState {
+    pub input: String,
+    pub complementary: String,
+    pub is_positive_strand: bool,
+    pub cursor: usize,
+}
+
+/// What an edit did. Runs of typing or deleting at the cursor merge into one
+/// undo step; everything else is a step of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditKind {
+    Insert,
+    Delete,
+    DeleteSelection,
+    Paste,
+    StrandToggle,
+    ReverseComplement,
+}
+
+impl EditKind {
+    fn merges(self) -> bool {
+        matches!(self, EditKind::Insert | EditKind::Delete)
+    }
+}
+
+/// Undo and redo stacks of editor snapshots.
+#[derive(Debug, Clone)]
+pub struct EditHistory {
+    pub undo: Vec<EditState>,
+    pub redo: Vec<EditState>,
+    pub limit: usize,
+    /// Kind and resulting cursor of the last edit, while later edits may join its step
+    open_group: Option<(EditKind, usize)>,
+}
+
+impl EditHistory {
+    pub fn new(limit: usize) -> Self {
+        EditHistory { undo: Vec::new(), redo: Vec::new(), limit, open_group: None }
+    }
+
+    /// Records an edit that turned `before` into a state with the cursor at
+    /// `cursor_after`. Typing or deleting that carries on from where the
+    /// previous edit of the same kind left the cursor joins its step.
+    pub fn record(&mut self, kind: EditKind, before: EditState, cursor_after: usize) {
+        let continues = kind.merges()
+            && self.open_group == Some((kind, before.cursor))
+            && !self.undo.is_empty();
+        if !continues {
+            trace!("New undo step for {kind:?}");
+            self.undo.push(before);
+            if self.undo.len() > self.limit {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+        self.open_group = kind.merges().then_some((kind, cursor_after));
+    }
+
+    /// Ends the current typing run, so the next edit starts a new step.
+    pub fn break_group(&mut self) {
+        self.open_group = None;
+    }
+
+    /// The state to restore, with `current` kept for redo.
+    pub fn undo(&mut self, current: EditState) -> Option<EditState> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        self.open_group = None;
+        Some(previous)
+    }
+
+    /// The state to restore, with `current` kept for undo.
+    pub fn redo(&mut self, current: EditState) -> Option<EditState> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        self.open_group = None;
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.open_group = None;
+    }
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_LIMIT)
+    }
+}
@@ -1,7 +1,10 @@
 pub mod app;
+pub mod cli;
+pub mod history;
 pub mod logging;
 pub mod protein;
+pub mod session;
 pub mod sequence;
 pub mod ui;
-pub use app::{App, SearchField};
+pub use app::{App, MatchMode, SearchField};
 pub use protein::SmallProtein;
\ No newline at end of file
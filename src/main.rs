@@ -4,16 +4,70 @@ use std::panic;
 use std::path::PathBuf;
 use log::{info, warn, error, debug};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event, KeyCode,
+        MouseButton, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
+    layout::Rect,
     Terminal,
 };
+use clap::{parser::ValueSource, CommandFactory, FromArgMatches, Parser};
 
-use ribozap::{App, ui::render_ui, logging};
+use ribozap::{App, cli, ui::{render_ui, orf_map_geometry}, logging};
+use ribozap::session::{Session, default_session_path};
+use ribozap::protein::{DatasetSource, load_configured_sources, drop_plain_text_caches};
+use ribozap::sequence::{GeneticCode, is_iupac_nucleotide, parse_genetic_code, read_sequence_file};
+
+#[derive(Parser, Debug)]
+#[command(name = "ribozap", version, about = "Interactive DNA/RNA sequence explorer with small protein matching")]
+struct Cli {
+    /// Run a single command and print the result instead of starting the TUI
+    #[command(subcommand)]
+    command: Option<cli::Command>,
+
+    /// Output format for commands
+    #[arg(long, value_enum, global = true, default_value_t = cli::OutputFormat::Text)]
+    format: cli::OutputFormat,
+
+    /// Dataset to load as [NAME=]URL|PATH; repeat to merge several datasets.
+    /// Overrides datasets.conf in the config directory.
+    #[arg(long = "dataset", value_name = "[NAME=]URL|PATH", global = true)]
+    datasets: Vec<DatasetSource>,
+
+    /// NCBI genetic code table used for every translation, e.g. 2 for vertebrate
+    /// mitochondria or 11 for bacteria and plastids
+    #[arg(long, value_name = "ID", global = true, default_value = "1", value_parser = parse_genetic_code)]
+    genetic_code: &'static GeneticCode,
+
+    /// Open a FASTA/FASTQ file (optionally gzipped) and step through its records with [ and ]
+    #[arg(long, short, value_name = "PATH")]
+    input: Option<PathBuf>,
+
+    /// Session file to restore on startup and save on quit; defaults to
+    /// session.json in the data directory
+    #[arg(long, value_name = "FILE")]
+    session: Option<PathBuf>,
+
+    /// Delete decompressed .txt copies of remote datasets left by older versions
+    #[arg(long)]
+    drop_text_cache: bool,
+}
+
+fn resolve_dataset_sources(cli_sources: Vec<DatasetSource>) -> Result<Vec<DatasetSource>, Box<dyn Error>> {
+    if !cli_sources.is_empty() {
+        return Ok(cli_sources);
+    }
+
+    match load_configured_sources()? {
+        Some(sources) if !sources.is_empty() => Ok(sources),
+        _ => Ok(vec![DatasetSource::default()]),
+    }
+}
 
 fn setup_logging() -> Result<PathBuf, Box<dyn Error>> {
     // Set log level from environment or default
@@ -62,7 +116,8 @@ fn setup_panic_handler() {
         let _ = execute!(
             io::stdout(),
             LeaveAlternateScreen,
-            DisableMouseCapture
+            DisableMouseCapture,
+            DisableBracketedPaste
         );
 
         warn!("Terminal state restored after panic");
@@ -80,13 +135,48 @@ fn cleanup_terminal() -> Result<(), Box<dyn Error>> {
     execute!(
         io::stdout(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     info!("Terminal cleanup completed successfully");
     Ok(())
 }
 
+/// The session file to use, and its contents if there is one. A default
+/// session that cannot be read is left behind with a warning; an explicit
+/// `--session` file that cannot be read is an error.
+fn resolve_session(path: Option<PathBuf>) -> Result<(Option<PathBuf>, Option<Session>), Box<dyn Error>> {
+    let explicit = path.is_some();
+    let path = match path {
+        Some(path) => path,
+        None => match default_session_path() {
+            Ok(path) => path,
+            Err(e) => {
+                warn!("No default session location: {e}");
+                return Ok((None, None));
+            },
+        },
+    };
+
+    if !path.exists() {
+        debug!("No session at {path:?}, starting fresh");
+        return Ok((Some(path), None));
+    }
+    match Session::load(&path) {
+        Ok(session) => Ok((Some(path), Some(session))),
+        Err(e) if explicit => Err(e),
+        Err(e) => {
+            warn!("Ignoring unreadable session: {e}");
+            Ok((Some(path), None))
+        },
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let matches = Cli::command().get_matches();
+    let genetic_code_given = matches.value_source("genetic_code") == Some(ValueSource::CommandLine);
+    let cli = Cli::from_arg_matches(&matches)?;
+
     // Initialize comprehensive logging first
     let log_file = match setup_logging() {
         Ok(file) => {
@@ -107,17 +197,52 @@ fn main() -> Result<(), Box<dyn Error>> {
     if let Some(ref file) = log_file {
         info!("Logs are being written to: {file:?}");
     }
+
+    // Resolve datasets before touching the terminal so config errors print normally
+    let dataset_sources = resolve_dataset_sources(cli.datasets)?;
+    if cli.drop_text_cache {
+        let removed = drop_plain_text_caches(&dataset_sources)?;
+        info!("Dropped {removed} plain-text dataset cache(s)");
+    }
+
+    if let Some(command) = cli.command {
+        info!("Running headless command {command:?}");
+        return cli::run(command, cli.format, cli.genetic_code, &dataset_sources, &mut io::stdout().lock());
+    }
+    let records = match &cli.input {
+        Some(path) => {
+            let mut records = read_sequence_file(path)?;
+            for record in &mut records {
+                record.sequence = record.nucleotides()?.to_string();
+            }
+            records
+        },
+        None => Vec::new(),
+    };
+    let (session_path, session) = resolve_session(cli.session)?;
     debug!("Setting up terminal");
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     info!("Terminal initialized successfully");
 
     let mut app = App::new();
+    app.set_dataset_sources(dataset_sources);
+    let session_restored = session.is_some();
+    if let Some(session) = session {
+        session.restore(&mut app);
+    }
+    // A restored session keeps its genetic code unless one is asked for
+    if genetic_code_given || !session_restored {
+        app.set_genetic_code(cli.genetic_code);
+    }
+    if !records.is_empty() {
+        app.set_records(records);
+    }
     debug!("App instance created");
 
     // Start threaded loading immediately
@@ -140,6 +265,14 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     }
 
+    // Autosave the session once the terminal is back to normal
+    if let Some(path) = &session_path {
+        if let Err(e) = Session::capture(&app).save(path) {
+            error!("Failed to save session to {path:?}: {e}");
+            eprintln!("Failed to save session to {}: {e}", path.display());
+        }
+    }
+
     // Handle any errors from the main loop
     if let Err(ref e) = loop_result {
         error!("Main loop error: {e}");
@@ -158,20 +291,35 @@ fn run_main_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mu
 
         if !app.is_loading_proteins {
             app.perform_protein_matching_if_needed();
+            app.check_peptide_index();
+            app.check_topology();
         }
 
         terminal.draw(|f| render_ui(f, app))?;
 
         // Use a timeout for event reading to allow progress updates
         if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
+            let event = event::read()?;
+            if let Event::Mouse(mouse) = event {
+                if !app.is_loading_proteins && !app.show_protein_searcher && !app.show_pmf_results {
+                    let size = terminal.size()?;
+                    handle_mouse(&mouse, app, Rect::new(0, 0, size.width, size.height));
+                }
+            }
+            if let Event::Paste(text) = &event {
+                handle_paste(text, app);
+            }
+            if let Event::Key(key) = event {
                 debug!("Key event received: {key:?}");
                 match key.code {
+                    _ if app.file_prompt.is_some() => {
+                        handle_file_prompt_keys(&key, app);
+                    },
                     KeyCode::Char('q') => {
                         info!("Quit command received");
                         break;
                     },
-                    KeyCode::Char('r') if app.loading_error.is_some() => {
+                    KeyCode::Char('r') if app.loading_error.is_some() && !key.modifiers.contains(event::KeyModifiers::CONTROL) => {
                         // Retry loading on error
                         warn!("Retrying protein data loading after error");
                         app.start_threaded_loading();
@@ -183,6 +331,25 @@ fn run_main_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mu
                     _ if app.show_protein_searcher => {
                         handle_protein_searcher_keys(&key, app)?;
                     },
+                    _ if app.show_pmf_results => {
+                        handle_pmf_keys(&key, app);
+                    },
+                    KeyCode::Char('o') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                        app.open_file_prompt();
+                    },
+                    KeyCode::Char('f') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                        app.open_peak_list_prompt();
+                    },
+                    KeyCode::Char('z') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                        app.undo();
+                    },
+                    KeyCode::Char('y') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                        app.redo();
+                    },
+                    KeyCode::Char('r') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                        debug!("Reverse-complementing the edited strand");
+                        app.reverse_complement();
+                    },
                     KeyCode::Char('p') => {
                         debug!("Toggling protein searcher");
                         app.toggle_protein_searcher();
@@ -191,9 +358,50 @@ fn run_main_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mu
                         debug!("Toggling strand mode");
                         app.toggle_strand_mode();
                     },
+                    KeyCode::Char('x') => {
+                        debug!("Toggling match mode");
+                        app.toggle_match_mode();
+                    },
+                    KeyCode::Char('m') => {
+                        app.cycle_substitution_matrix();
+                    },
+                    KeyCode::Char(']') => {
+                        app.next_record();
+                    },
+                    KeyCode::Char('[') => {
+                        app.prev_record();
+                    },
+                    KeyCode::Char('>') => {
+                        app.next_genetic_code();
+                    },
+                    KeyCode::Char('<') => {
+                        app.prev_genetic_code();
+                    },
+                    KeyCode::Char('l') => {
+                        app.cycle_profile_kind();
+                    },
+                    KeyCode::Char('{') => {
+                        app.change_profile_window(-1);
+                    },
+                    KeyCode::Char('}') => {
+                        app.change_profile_window(1);
+                    },
+                    KeyCode::Char('o') => {
+                        app.toggle_orf_panel();
+                    },
+                    KeyCode::Char('n') if app.show_orf_panel => {
+                        app.cycle_orf_start_codons();
+                    },
+                    KeyCode::Char('+') if app.show_orf_panel => {
+                        app.change_orf_min_length(1);
+                    },
+                    KeyCode::Char('-') if app.show_orf_panel => {
+                        app.change_orf_min_length(-1);
+                    },
                     KeyCode::Char(c) if c.is_ascii_alphabetic() => {
-                        let upper_c = c.to_uppercase().next().unwrap();
-                        if matches!(upper_c, 'A' | 'T' | 'G' | 'C') {
+                        let upper_c = c.to_ascii_uppercase();
+                        // Lowercase letters bound above (s, x, p, ...) need Shift to be typed
+                        if is_iupac_nucleotide(upper_c) {
                             debug!("Processing nucleotide input: {upper_c}");
                             app.on_key(upper_c);
                         }
@@ -202,6 +410,35 @@ fn run_main_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mu
                         debug!("Processing backspace");
                         app.on_backspace();
                     },
+                    KeyCode::Delete => {
+                        app.on_delete();
+                    },
+                    KeyCode::Insert => {
+                        app.toggle_overwrite_mode();
+                    },
+                    KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End => {
+                        handle_cursor_keys(&key, app);
+                    },
+                    KeyCode::Down => {
+                        app.next_hit();
+                    },
+                    KeyCode::Up => {
+                        app.prev_hit();
+                    },
+                    KeyCode::Enter => {
+                        debug!("Selecting protein hit");
+                        app.select_current_hit();
+                    },
+                    KeyCode::Tab => {
+                        app.next_orf();
+                    },
+                    KeyCode::BackTab => {
+                        app.prev_orf();
+                    },
+                    KeyCode::Esc => {
+                        app.clear_selection();
+                        app.select_orf(None);
+                    },
                     _ => {
                         debug!("Unhandled key event: {key:?}");
                     }
@@ -213,6 +450,71 @@ fn run_main_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mu
     Ok(())
 }
 
+fn handle_paste(text: &str, app: &mut App) {
+    debug!("Paste of {} bytes received", text.len());
+    if let Some(path) = &mut app.file_prompt {
+        path.push_str(text.trim());
+    } else if app.show_protein_searcher {
+        for c in text.trim().chars() {
+            app.searcher_on_key(c);
+        }
+    } else if !app.is_loading_proteins {
+        app.paste(text);
+    }
+}
+
+fn handle_file_prompt_keys(key: &event::KeyEvent, app: &mut App) {
+    match key.code {
+        KeyCode::Enter => app.submit_file_prompt(),
+        KeyCode::Esc => app.cancel_file_prompt(),
+        KeyCode::Backspace => app.file_prompt_on_backspace(),
+        KeyCode::Char(c) => app.file_prompt_on_key(c),
+        _ => {},
+    }
+}
+
+fn handle_pmf_keys(key: &event::KeyEvent, app: &mut App) {
+    match key.code {
+        KeyCode::Down => app.next_pmf_hit(),
+        KeyCode::Up => app.prev_pmf_hit(),
+        KeyCode::Enter => app.select_pmf_hit(),
+        KeyCode::Tab => {
+            debug!("Re-searching peak list with the next protease");
+            app.cycle_pmf_protease();
+        },
+        KeyCode::Char('f') if key.modifiers.contains(event::KeyModifiers::CONTROL) => app.open_peak_list_prompt(),
+        KeyCode::Esc => app.show_pmf_results = false,
+        _ => {},
+    }
+}
+
+/// Arrows move by base, Ctrl+arrows by codon; Shift extends the selection.
+fn handle_cursor_keys(key: &event::KeyEvent, app: &mut App) {
+    let extend = key.modifiers.contains(event::KeyModifiers::SHIFT);
+    let by_codon = key.modifiers.contains(event::KeyModifiers::CONTROL);
+
+    match key.code {
+        KeyCode::Left if by_codon => app.move_cursor_prev_codon(extend),
+        KeyCode::Right if by_codon => app.move_cursor_next_codon(extend),
+        KeyCode::Left => app.move_cursor_left(extend),
+        KeyCode::Right => app.move_cursor_right(extend),
+        KeyCode::Home => app.move_cursor_home(extend),
+        KeyCode::End => app.move_cursor_end(extend),
+        _ => {},
+    }
+}
+
+fn handle_mouse(mouse: &event::MouseEvent, app: &mut App, area: Rect) {
+    if mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+        return;
+    }
+
+    if let Some((frame, position)) = orf_map_geometry(app, area).hit(mouse.column, mouse.row) {
+        debug!("ORF map clicked at frame {frame}, position {position}");
+        app.select_orf_at(frame, position);
+    }
+}
+
 fn handle_protein_searcher_keys(key: &event::KeyEvent, app: &mut App) -> Result<(), Box<dyn Error>> {
     match key.code {
         KeyCode::Char('t') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
@@ -231,6 +533,15 @@ fn handle_protein_searcher_keys(key: &event::KeyEvent, app: &mut App) -> Result<
             debug!("Clearing all filters");
             app.clear_all_filters();
         },
+        KeyCode::Tab if app.show_protein_detail => {
+            app.cycle_profile_kind();
+        },
+        KeyCode::Char('{') if app.show_protein_detail => {
+            app.change_profile_window(-1);
+        },
+        KeyCode::Char('}') if app.show_protein_detail => {
+            app.change_profile_window(1);
+        },
         KeyCode::Char(c) if c.is_ascii_alphanumeric() || c == ' ' || c == '.' || c == '-' => {
             app.searcher_on_key(c);
         },
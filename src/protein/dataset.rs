@@ -1,33 +1,73 @@
+use std::cell::Cell;
 use std::error::Error;
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader, Read, Write};
-use std::path::PathBuf;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use flate2::read::GzDecoder;
 use reqwest::blocking::Client;
 use log::{info, warn, error, debug, trace};
+use crate::protein::download::{download_verified, verify_cached_download, discard_download};
+use crate::protein::model::{SmallProtein, NucleotideSequence, ProteinSequence, parse_blocks};
+use crate::protein::source::{DatasetSource, SourceLocation};
 
-#[derive(Debug, Clone)]
-pub struct SmallProtein {
-    pub species: String,
-    pub id: String,
-    pub rna_seq: String,
-    pub aa_seq: String,
-    pub length: usize,
-    pub chromosome: String,
-    pub start: usize,
-    pub stop: usize,
-    pub strand: String,
-    pub blocks: String,
-    pub start_codon: String,
-    pub phylo_csf_mean: f64,
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Proteins parsed from one or more sources, with the rows that were rejected.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedDataset {
+    pub proteins: Vec<SmallProtein>,
+    pub report: ParseReport,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseReport {
+    pub rows_read: usize,
+    pub rejected: Vec<RejectedRow>,
+}
+
+/// A data row that was skipped, with the 1-based line number in its source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedRow {
+    pub source: String,
+    pub line: usize,
+    pub field: String,
+    pub reason: String,
+}
+
+impl ParseReport {
+    pub fn is_clean(&self) -> bool {
+        self.rejected.is_empty()
+    }
+
+    pub fn merge(&mut self, other: ParseReport) {
+        self.rows_read += other.rows_read;
+        self.rejected.extend(other.rejected);
+    }
+
+    pub fn summary(&self) -> String {
+        format!("{} of {} rows rejected", self.rejected.len(), self.rows_read)
+    }
+}
+
+impl std::fmt::Display for RejectedRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.source.is_empty() {
+            write!(f, "line {}: {}: {}", self.line, self.field, self.reason)
+        } else {
+            write!(f, "{} line {}: {}: {}", self.source, self.line, self.field, self.reason)
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum DatasetProgress {
     CheckingCache,
+    ReadingSnapshot,
     Downloading { bytes_downloaded: u64, total_bytes: Option<u64> },
-    Extracting,
-    Parsing { lines_parsed: usize },
+    Verifying,
+    Parsing { lines_parsed: usize, bytes_read: u64, total_bytes: Option<u64> },
+    Source { name: String, index: usize, total: usize },
     Complete,
     Error(String),
 }
@@ -49,237 +89,400 @@ pub fn download_and_parse_small_protein_dataset() -> Result<Vec<SmallProtein>, B
     download_and_parse_small_protein_dataset_with_progress(None)
 }
 
-//noinspection HttpUrlsUsage
 pub fn download_and_parse_small_protein_dataset_with_progress(
     progress_callback: Option<Box<dyn Fn(DatasetProgress)>>
 ) -> Result<Vec<SmallProtein>, Box<dyn Error>> {
-    let url = "http://bigdata.ibp.ac.cn/SmProt/datadownload/SmProt2_LiteratureMining.txt.gz";
-
-    info!("Starting protein dataset download and parsing with progress tracking");
-    debug!("Dataset URL: {url}");
+    Ok(load_dataset_sources(&[DatasetSource::default()], progress_callback.as_deref())?.proteins)
+}
 
-    let data_dir = get_data_dir()?;
-    let temp_file = data_dir.join("small_protein_dataset.txt.gz");
-    let extracted_file = data_dir.join("small_protein_dataset.txt");
+/// Loads every source in order and merges the proteins into one list, each
+/// tagged with the name of the source it came from.
+pub fn load_dataset_sources(
+    sources: &[DatasetSource],
+    progress_callback: Option<&dyn Fn(DatasetProgress)>
+) -> Result<ParsedDataset, Box<dyn Error>> {
+    info!("Loading {} dataset source(s)", sources.len());
+    let mut merged = ParsedDataset::default();
+
+    for (index, source) in sources.iter().enumerate() {
+        if let Some(callback) = progress_callback {
+            callback(DatasetProgress::Source { name: source.name.clone(), index, total: sources.len() });
+        }
 
-    debug!("Temp file path: {temp_file:?}");
-    debug!("Extracted file path: {extracted_file:?}");
+        let loaded = load_dataset_source(source, progress_callback)
+            .map_err(|e| format!("{}: {e}", source.name))?;
+        merged.proteins.extend(loaded.proteins);
+        merged.report.merge(loaded.report);
+    }
 
-    if let Some(ref callback) = progress_callback {
-        callback(DatasetProgress::CheckingCache);
+    if let Some(callback) = progress_callback {
+        callback(DatasetProgress::Complete);
     }
 
-    if !extracted_file.exists() {
-        info!("Extracted file does not exist, checking for compressed file");
-        
-        if !temp_file.exists() {
-            info!("Compressed file does not exist, starting download");
-            
-            if let Some(ref callback) = progress_callback {
-                callback(DatasetProgress::Downloading { bytes_downloaded: 0, total_bytes: None });
-            }
+    Ok(merged)
+}
 
-            let client = Client::new();
-            debug!("HTTP client created, sending request to: {url}");
-            
-            let mut response = client.get(url).send()
-                .map_err(|e| {
-                    error!("Failed to send HTTP request: {e}");
-                    e
-                })?;
-
-            let total_size = response.content_length();
-            debug!("Response received, content length: {total_size:?}");
-            
-            let mut downloaded = 0u64;
-
-            let mut file = File::create(&temp_file)
-                .map_err(|e| {
-                    error!("Failed to create temp file {temp_file:?}: {e}");
-                    e
-                })?;
-            
-            info!("Starting file download to {temp_file:?}");
-            let mut buffer = [0; 8192];
-
-            loop {
-                let bytes_read = response.read(&mut buffer)
-                    .map_err(|e| {
-                        error!("Error reading from HTTP response: {e}");
-                        e
-                    })?;
-                
-                if bytes_read == 0 {
-                    break;
-                }
-
-                file.write_all(&buffer[..bytes_read])
-                    .map_err(|e| {
-                        error!("Error writing to temp file: {e}");
-                        e
-                    })?;
-                
-                downloaded += bytes_read as u64;
-
-                if downloaded % (1024 * 1024) == 0 { // Log every MB
-                    trace!("Downloaded {downloaded} bytes");
-                }
-
-                if let Some(ref callback) = progress_callback {
-                    callback(DatasetProgress::Downloading {
-                        bytes_downloaded: downloaded,
-                        total_bytes: total_size,
-                    });
-                }
+pub fn load_dataset_source(
+    source: &DatasetSource,
+    progress_callback: Option<&dyn Fn(DatasetProgress)>
+) -> Result<ParsedDataset, Box<dyn Error>> {
+    info!("Loading dataset source {source}");
+
+    let mut parsed = match &source.location {
+        SourceLocation::Remote(url) => {
+            // Decompressed copies from older versions cannot be verified
+            let text_cache = source.cache_file("txt")?;
+            if text_cache.exists() {
+                warn!("Ignoring plain-text cache {text_cache:?}; --drop-text-cache deletes it");
             }
-            
-            info!("Download completed successfully. Total bytes: {downloaded}");
-        } else {
-            info!("Compressed file already exists, skipping download");
-        }
-
-        info!("Starting file extraction");
-        if let Some(ref callback) = progress_callback {
-            callback(DatasetProgress::Extracting);
-        }
+            load_remote_dataset(source, url, progress_callback)?
+        },
+        SourceLocation::LocalFile(path) => parse_dataset_file(path, progress_callback)?,
+        SourceLocation::InMemory(data) => {
+            parse_dataset_stream(&data[..], Some(data.len() as u64), progress_callback)?
+        },
+    };
+
+    for protein in &mut parsed.proteins {
+        protein.source.clone_from(&source.name);
+    }
+    for row in &mut parsed.report.rejected {
+        row.source.clone_from(&source.name);
+    }
 
-        let compressed_file = File::open(&temp_file)
-            .map_err(|e| {
-                error!("Failed to open compressed file {temp_file:?}: {e}");
-                e
-            })?;
-        
-        let decoder = GzDecoder::new(compressed_file);
-        let mut reader = BufReader::new(decoder);
-        let mut extracted_content = String::new();
-        
-        reader.read_to_string(&mut extracted_content)
-            .map_err(|e| {
-                error!("Failed to decompress file: {e}");
-                e
-            })?;
+    Ok(parsed)
+}
 
-        debug!("Decompressed content size: {} bytes", extracted_content.len());
+/// Wraps `reader` in a gzip decoder when the content starts with the gzip magic bytes.
+fn open_dataset_reader<'a, R: Read + 'a>(reader: R) -> Result<Box<dyn BufRead + 'a>, Box<dyn Error>> {
+    let mut reader = BufReader::new(reader);
+    let is_gzip = reader.fill_buf()?.starts_with(&GZIP_MAGIC);
+    debug!("Dataset content is {}", if is_gzip { "gzip-compressed" } else { "plain text" });
 
-        fs::write(&extracted_file, extracted_content)
-            .map_err(|e| {
-                error!("Failed to write extracted file {extracted_file:?}: {e}");
-                e
-            })?;
-        
-        info!("File extraction completed successfully");
+    if is_gzip {
+        Ok(Box::new(BufReader::new(GzDecoder::new(reader))))
     } else {
-        info!("Extracted file already exists, proceeding to parsing");
+        Ok(Box::new(reader))
     }
+}
 
-    info!("Starting protein data parsing");
-    let file = File::open(&extracted_file)
+fn parse_dataset_file(path: &Path, progress_callback: Option<&dyn Fn(DatasetProgress)>) -> Result<ParsedDataset, Box<dyn Error>> {
+    info!("Starting protein data parsing from {path:?}");
+    let file = File::open(path)
         .map_err(|e| {
-            error!("Failed to open extracted file {extracted_file:?}: {e}");
+            error!("Failed to open dataset file {path:?}: {e}");
             e
         })?;
-    
-    let reader = BufReader::new(file);
+    let total_bytes = file.metadata().ok().map(|metadata| metadata.len());
+
+    parse_dataset_stream(file, total_bytes, progress_callback)
+}
+
+fn load_remote_dataset(
+    source: &DatasetSource,
+    url: &str,
+    progress_callback: Option<&dyn Fn(DatasetProgress)>
+) -> Result<ParsedDataset, Box<dyn Error>> {
+    debug!("Dataset URL: {url}");
+
+    let archive_file = source.cache_file("txt.gz")?;
+    debug!("Archive file path: {archive_file:?}");
+
+    if let Some(callback) = progress_callback {
+        callback(DatasetProgress::CheckingCache);
+    }
+
+    let client = Client::new();
+
+    if verify_cached_download(&archive_file, &source.integrity) {
+        info!("Verified compressed file already exists, skipping download");
+    } else {
+        if archive_file.exists() {
+            warn!("Cached archive {archive_file:?} failed verification, re-fetching");
+            discard_download(&archive_file);
+        }
+        download_verified(&client, url, &archive_file, &source.integrity, progress_callback)?;
+    }
+
+    match parse_dataset_file(&archive_file, progress_callback) {
+        Ok(parsed) => Ok(parsed),
+        Err(e) => {
+            warn!("Failed to read {archive_file:?} ({e}), re-fetching");
+            discard_download(&archive_file);
+            download_verified(&client, url, &archive_file, &source.integrity, progress_callback)?;
+            parse_dataset_file(&archive_file, progress_callback)
+        }
+    }
+}
+
+/// Deletes decompressed `.txt` copies written by older versions; the loader
+/// now parses the verified archive directly.
+pub fn drop_plain_text_caches(sources: &[DatasetSource]) -> Result<usize, Box<dyn Error>> {
+    let mut removed = 0;
+
+    for source in sources.iter().filter(|source| matches!(source.location, SourceLocation::Remote(_))) {
+        let text_cache = source.cache_file("txt")?;
+        if text_cache.exists() {
+            fs::remove_file(&text_cache)?;
+            info!("Removed plain-text cache {text_cache:?}");
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+pub fn parse_small_protein_dataset<R: BufRead>(
+    reader: R,
+    progress_callback: Option<&dyn Fn(DatasetProgress)>
+) -> Result<Vec<SmallProtein>, Box<dyn Error>> {
+    Ok(parse_records(reader, None, progress_callback)?.proteins)
+}
+
+pub fn parse_small_protein_dataset_with_report<R: BufRead>(
+    reader: R,
+    progress_callback: Option<&dyn Fn(DatasetProgress)>
+) -> Result<ParsedDataset, Box<dyn Error>> {
+    parse_records(reader, None, progress_callback)
+}
+
+/// Parses a (possibly compressed) dataset stream, reporting progress against
+/// the bytes pulled from `reader` rather than the decompressed text.
+pub fn parse_dataset_stream<'a, R: Read + 'a>(
+    reader: R,
+    total_bytes: Option<u64>,
+    progress_callback: Option<&dyn Fn(DatasetProgress)>
+) -> Result<ParsedDataset, Box<dyn Error>> {
+    let bytes_read = Rc::new(Cell::new(0));
+    let counting_reader = CountingReader { inner: reader, bytes_read: Rc::clone(&bytes_read) };
+    let position = InputPosition { bytes_read, total_bytes };
+
+    parse_records(open_dataset_reader(counting_reader)?, Some(&position), progress_callback)
+}
+
+struct CountingReader<R> {
+    inner: R,
+    bytes_read: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes = self.inner.read(buf)?;
+        self.bytes_read.set(self.bytes_read.get() + bytes as u64);
+        Ok(bytes)
+    }
+}
+
+struct InputPosition {
+    bytes_read: Rc<Cell<u64>>,
+    total_bytes: Option<u64>,
+}
+
+fn parse_records<R: BufRead>(
+    mut reader: R,
+    position: Option<&InputPosition>,
+    progress_callback: Option<&dyn Fn(DatasetProgress)>
+) -> Result<ParsedDataset, Box<dyn Error>> {
     let mut proteins = Vec::new();
-    let mut lines_parsed = 0;
-    let mut errors_encountered = 0;
+    let mut report = ParseReport::default();
+    let mut line_num = 0;
+    let mut bytes_parsed = 0u64;
+    let mut line = String::new();
+
+    let report_progress = |lines_parsed: usize, bytes_parsed: u64| {
+        if let Some(callback) = progress_callback {
+            callback(DatasetProgress::Parsing {
+                lines_parsed,
+                bytes_read: position.map_or(bytes_parsed, |p| p.bytes_read.get()),
+                total_bytes: position.and_then(|p| p.total_bytes),
+            });
+        }
+    };
 
-    for (line_num, line) in reader.lines().enumerate().skip(1) {
-        let line = line
+    loop {
+        // One reused line buffer keeps memory flat regardless of input size
+        line.clear();
+        let bytes = reader.read_line(&mut line)
             .map_err(|e| {
                 error!("Error reading line {}: {}", line_num + 1, e);
                 e
             })?;
-        
-        let fields: Vec<&str> = line.split('\t').collect();
-
-        if fields.len() < 12 {
-            warn!("Line {} has insufficient fields ({}), skipping", line_num + 1, fields.len());
-            errors_encountered += 1;
-            continue;
+        if bytes == 0 {
+            break;
         }
 
-        let protein = SmallProtein {
-            species: fields[0].to_string(),
-            id: fields[1].to_string(),
-            rna_seq: fields[2].to_string(),
-            aa_seq: fields[3].to_string(),
-            length: parse_usize_field(fields[4], line_num + 1, "length", &mut errors_encountered),
-            chromosome: fields[5].to_string(),
-            start: parse_usize_field(fields[6], line_num + 1, "start", &mut errors_encountered),
-            stop: parse_usize_field(fields[7], line_num + 1, "stop", &mut errors_encountered),
-            strand: fields[8].to_string(),
-            blocks: fields[9].to_string(),
-            start_codon: fields[10].to_string(),
-            phylo_csf_mean: parse_float_field(fields[11], line_num + 1, "phylo_csf_mean", &mut errors_encountered),
-        };
+        line_num += 1;
+        bytes_parsed += bytes as u64;
+        if line_num == 1 {
+            continue; // header
+        }
 
-        proteins.push(protein);
-        lines_parsed += 1;
+        let record = line.trim_end_matches(['\n', '\r']);
+        if record.trim().is_empty() {
+            continue;
+        }
 
-        if lines_parsed % 1000 == 0 {
-            trace!("Parsed {lines_parsed} lines");
-            if let Some(ref callback) = progress_callback {
-                callback(DatasetProgress::Parsing { lines_parsed });
+        report.rows_read += 1;
+        match parse_protein_row(record) {
+            Ok(protein) => proteins.push(protein),
+            Err((field, reason)) => {
+                debug!("Line {line_num}: rejected ({field}: {reason})");
+                report.rejected.push(RejectedRow {
+                    source: String::new(),
+                    line: line_num,
+                    field: field.to_string(),
+                    reason,
+                });
             }
         }
+
+        if report.rows_read.is_multiple_of(1000) {
+            trace!("Parsed {} lines", report.rows_read);
+            report_progress(proteins.len(), bytes_parsed);
+        }
     }
 
-    if errors_encountered > 0 {
-        warn!("Parsing completed with {errors_encountered} errors encountered");
+    report_progress(proteins.len(), bytes_parsed);
+
+    if !report.is_clean() {
+        warn!("Parsing completed with {}", report.summary());
     }
 
     info!("Protein data parsing completed successfully. {} proteins loaded", proteins.len());
-    
-    if let Some(ref callback) = progress_callback {
-        callback(DatasetProgress::Complete);
-    }
 
-    Ok(proteins)
+    Ok(ParsedDataset { proteins, report })
 }
 
-fn parse_field<T>(
-    field: &str,
-    line_num: usize,
-    field_name: &str,
-    errors_encountered: &mut usize,
-    default_value: T,
-    additional_invalid_values: &[&str]
-) -> T
-where
-    T: std::str::FromStr + std::fmt::Display + Copy,
-    T::Err: std::fmt::Display,
-{
-    let trimmed = field.trim();
-
-    // Common invalid values
-    let common_invalid = ["", "NA", "NULL", "null", "N/A", "n/a", "-", "."];
-    let all_invalid: Vec<&str> = common_invalid.iter()
-        .chain(additional_invalid_values.iter())
-        .copied()
-        .collect();
-
-    if all_invalid.contains(&trimmed) {
-        debug!("Line {line_num}: {field_name} is '{trimmed}', using default value {default_value}");
-        return default_value;
+const EXPECTED_FIELDS: usize = 12;
+
+/// Parses one TSV row, returning the offending column and reason on failure.
+fn parse_protein_row(record: &str) -> Result<SmallProtein, (&'static str, String)> {
+    let fields: Vec<&str> = record.split('\t').collect();
+    if fields.len() < EXPECTED_FIELDS {
+        return Err(("row", format!("expected {EXPECTED_FIELDS} fields, found {}", fields.len())));
     }
 
-    trimmed.parse().unwrap_or_else(|e| {
-        // Only warn for truly unexpected parsing errors, not common placeholders
-        if !trimmed.is_empty() && !all_invalid.contains(&trimmed) {
-            warn!("Line {line_num}: Failed to parse {field_name} '{trimmed}': {e}");
-            *errors_encountered += 1;
-        } else {
-            debug!("Line {line_num}: {field_name} is '{trimmed}', using default value {default_value}");
-        }
-        default_value
+    let required = |index: usize, name: &'static str| -> Result<&str, (&'static str, String)> {
+        let value = fields[index].trim();
+        if is_missing(value) { Err((name, "missing value".to_string())) } else { Ok(value) }
+    };
+    let number = |index: usize, name: &'static str| -> Result<usize, (&'static str, String)> {
+        let value = required(index, name)?;
+        value.parse().map_err(|_| (name, format!("'{value}' is not a non-negative integer")))
+    };
+
+    let start = number(6, "start")?;
+    let stop = number(7, "stop")?;
+    if start > stop {
+        return Err(("stop", format!("stop {stop} is before start {start}")));
+    }
+
+    let start_codon = match fields[10].trim() {
+        value if is_missing(value) => None,
+        value => Some(NucleotideSequence::new(value).map_err(|e| ("start_codon", e))?),
+    };
+
+    let phylo_csf_mean = match fields[11].trim() {
+        value if is_missing(value) || value.eq_ignore_ascii_case("nan") => None,
+        value => Some(value.parse::<f64>().map_err(|_| ("phylo_csf_mean", format!("'{value}' is not a number")))?),
+    };
+
+    Ok(SmallProtein {
+        species: fields[0].trim().to_string(),
+        id: required(1, "id")?.to_string(),
+        rna_seq: NucleotideSequence::new(required(2, "rna_seq")?).map_err(|e| ("rna_seq", e))?,
+        aa_seq: ProteinSequence::new(required(3, "aa_seq")?).map_err(|e| ("aa_seq", e))?,
+        length: number(4, "length")?,
+        chromosome: fields[5].trim().to_string(),
+        start,
+        stop,
+        strand: fields[8].parse().map_err(|e| ("strand", e))?,
+        blocks: parse_blocks(required(9, "blocks")?).map_err(|e| ("blocks", e))?,
+        start_codon,
+        phylo_csf_mean,
+        source: String::new(),
     })
 }
 
-fn parse_float_field(field: &str, line_num: usize, field_name: &str, errors_encountered: &mut usize) -> f64 {
-    parse_field(field, line_num, field_name, errors_encountered, 0.0, &["NaN", "nan"])
+fn is_missing(value: &str) -> bool {
+    matches!(value, "" | "NA" | "NULL" | "null" | "N/A" | "n/a" | "-" | ".")
 }
 
-fn parse_usize_field(field: &str, line_num: usize, field_name: &str, errors_encountered: &mut usize) -> usize {
-    parse_field(field, line_num, field_name, errors_encountered, 0, &[])
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::io::Write;
+    use flate2::{write::GzEncoder, Compression};
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/smprot_sample.txt");
+
+    #[test]
+    fn test_gzip_stream_reports_compressed_progress() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(FIXTURE.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let updates = RefCell::new(Vec::new());
+        let callback = |progress: DatasetProgress| updates.borrow_mut().push(progress);
+        let proteins = parse_dataset_stream(&compressed[..], Some(compressed.len() as u64), Some(&callback)).unwrap().proteins;
+
+        let plain = parse_small_protein_dataset(FIXTURE.as_bytes(), None).unwrap();
+        assert_eq!(proteins.len(), plain.len());
+        assert_eq!(proteins.last().unwrap().aa_seq, plain.last().unwrap().aa_seq);
+
+        let final_progress = updates.borrow().last().cloned();
+        match final_progress {
+            Some(DatasetProgress::Parsing { lines_parsed, bytes_read, total_bytes }) => {
+                assert_eq!(lines_parsed, plain.len());
+                assert_eq!(bytes_read, compressed.len() as u64);
+                assert_eq!(total_bytes, Some(compressed.len() as u64));
+            },
+            other => panic!("unexpected final progress {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parser_handles_crlf_and_short_lines() {
+        let data = FIXTURE.replace('\n', "\r\n") + "truncated\trow\r\n";
+        let proteins = parse_small_protein_dataset(data.as_bytes(), None).unwrap();
+
+        assert_eq!(proteins.len(), FIXTURE.lines().count() - 1);
+        assert_eq!(proteins[0].phylo_csf_mean, Some(25.4604));
+        assert!(proteins.iter().all(|protein| !protein.aa_seq.ends_with('\r')));
+    }
+
+    #[test]
+    fn test_parse_report_records_rejected_rows() {
+        let header = FIXTURE.lines().next().unwrap();
+        let good = FIXTURE.lines().nth(1).unwrap();
+        let fields: Vec<&str> = good.split('\t').collect();
+        let with_field = |index: usize, value: &str| {
+            let mut row = fields.clone();
+            row[index] = value;
+            row.join("\t")
+        };
+
+        let data = [
+            header.to_string(),
+            good.to_string(),
+            with_field(8, "."),
+            with_field(2, "ATGXXA"),
+            with_field(6, "30674x"),
+            with_field(11, "NA"),
+            with_field(9, "30674665-30674700,30674800-30674842"),
+        ].join("\n");
+        let parsed = parse_small_protein_dataset_with_report(data.as_bytes(), None).unwrap();
+
+        assert_eq!(parsed.proteins.len(), 3);
+        assert_eq!(parsed.proteins[1].phylo_csf_mean, None);
+        assert_eq!(parsed.proteins[2].blocks.len(), 2);
+        assert_eq!(parsed.report.rows_read, 6);
+
+        let rejected: Vec<(usize, &str)> = parsed.report.rejected.iter()
+            .map(|row| (row.line, row.field.as_str()))
+            .collect();
+        assert_eq!(rejected, vec![(3, "strand"), (4, "rna_seq"), (5, "start")]);
+        assert!(parsed.report.rejected[1].reason.contains("position 4"));
+    }
 }
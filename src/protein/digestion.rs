@@ -0,0 +1,148 @@
+use crate::protein::molecular_weights::{MassType, Modification, peptide_mass};
+
+/// Cleavage rules, as listed by ExPASy PeptideCutter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Protease {
+    /// After K or R, not before P
+    #[default]
+    Trypsin,
+    /// After K, not before P
+    #[value(name = "lys-c")]
+    LysC,
+    /// After E, not before P (bicarbonate buffer)
+    #[value(name = "glu-c")]
+    GluC,
+    /// After F, Y or W, not before P (high specificity)
+    Chymotrypsin,
+}
+
+impl Protease {
+    pub const ALL: [Protease; 4] = [Protease::Trypsin, Protease::LysC, Protease::GluC, Protease::Chymotrypsin];
+
+    /// Whether the bond between `residue` and `next` is cut.
+    pub fn cleaves(self, residue: char, next: char) -> bool {
+        let after = match self {
+            Protease::Trypsin => matches!(residue, 'K' | 'R'),
+            Protease::LysC => residue == 'K',
+            Protease::GluC => residue == 'E',
+            Protease::Chymotrypsin => matches!(residue, 'F' | 'Y' | 'W'),
+        };
+        after && next != 'P'
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Protease::Trypsin => "Trypsin",
+            Protease::LysC => "Lys-C",
+            Protease::GluC => "Glu-C",
+            Protease::Chymotrypsin => "Chymotrypsin",
+        }
+    }
+
+    pub fn next(self) -> Protease {
+        let position = Protease::ALL.iter().position(|protease| *protease == self).unwrap_or(0);
+        Protease::ALL[(position + 1) % Protease::ALL.len()]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DigestOptions {
+    pub protease: Protease,
+    /// Most uncut sites a peptide may span
+    pub missed_cleavages: usize,
+    /// Peptide length range in residues, inclusive
+    pub min_length: usize,
+    pub max_length: usize,
+}
+
+impl Default for DigestOptions {
+    fn default() -> Self {
+        DigestOptions { protease: Protease::Trypsin, missed_cleavages: 1, min_length: 5, max_length: 40 }
+    }
+}
+
+/// A peptide cut from a protein; `start` and `end` are 0-based, end-exclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Peptide {
+    pub sequence: String,
+    pub start: usize,
+    pub end: usize,
+    pub missed_cleavages: usize,
+}
+
+impl Peptide {
+    pub fn mass(&self, mass_type: MassType) -> Result<f64, String> {
+        peptide_mass(&self.sequence, mass_type, &[])
+    }
+
+    /// The modifications of `wanted` this peptide has sites for, each kept at
+    /// most once per site. Terminal ones only apply at the termini of the
+    /// protein, which is `protein_length` residues long.
+    pub fn modifications(&self, wanted: &[Modification], protein_length: usize) -> Vec<Modification> {
+        let mut applied: Vec<Modification> = Vec::new();
+        for &modification in wanted {
+            let fits = match modification {
+                Modification::Acetylation => self.start == 0,
+                Modification::Amidation => self.end == protein_length,
+                Modification::Oxidation | Modification::Phosphorylation => true,
+            };
+            let sites_left = modification.sites(&self.sequence) > applied.iter().filter(|&&other| other == modification).count();
+            if fits && sites_left {
+                applied.push(modification);
+            }
+        }
+        applied
+    }
+}
+
+/// Cuts `protein` at every site the protease cleaves and joins neighbouring
+/// pieces up to the allowed missed cleavages. A trailing stop is ignored.
+pub fn digest(protein: &str, options: &DigestOptions) -> Vec<Peptide> {
+    let residues: Vec<char> = protein.trim().trim_end_matches('*').chars().map(|c| c.to_ascii_uppercase()).collect();
+
+    let mut boundaries = vec![0];
+    boundaries.extend((1..residues.len()).filter(|&i| options.protease.cleaves(residues[i - 1], residues[i])));
+    boundaries.push(residues.len());
+    boundaries.dedup();
+
+    let mut peptides = Vec::new();
+    for (first, &start) in boundaries.iter().enumerate() {
+        for (missed, &end) in boundaries[first + 1..].iter().take(options.missed_cleavages + 1).enumerate() {
+            let length = end - start;
+            if length > options.max_length {
+                break;
+            }
+            if length >= options.min_length {
+                peptides.push(Peptide { sequence: residues[start..end].iter().collect(), start, end, missed_cleavages: missed });
+            }
+        }
+    }
+    peptides
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trypsin_skips_proline_and_joins_missed_cleavages() {
+        let options = DigestOptions { missed_cleavages: 1, min_length: 1, max_length: 50, ..DigestOptions::default() };
+        let peptides = digest("MAKPLRGEKW*", &options);
+        let sequences: Vec<(&str, usize)> = peptides.iter().map(|peptide| (peptide.sequence.as_str(), peptide.missed_cleavages)).collect();
+        assert_eq!(sequences, [("MAKPLR", 0), ("MAKPLRGEK", 1), ("GEK", 0), ("GEKW", 1), ("W", 0)]);
+        assert_eq!((peptides[2].start, peptides[2].end), (6, 9));
+
+        let short = DigestOptions { min_length: 4, max_length: 6, ..options };
+        assert_eq!(digest("MAKPLRGEKW", &short).iter().map(|peptide| peptide.sequence.as_str()).collect::<Vec<_>>(), ["MAKPLR", "GEKW"]);
+
+        let chymotrypsin = DigestOptions { protease: Protease::Chymotrypsin, missed_cleavages: 0, ..options };
+        assert_eq!(digest("AFPGWKYL", &chymotrypsin).len(), 3);
+
+        // Acetylation only on the N-terminal peptide, oxidation once per methionine
+        let wanted = [Modification::Acetylation, Modification::Oxidation, Modification::Oxidation];
+        let applied: Vec<Vec<Modification>> = digest("MAKMMR", &DigestOptions { missed_cleavages: 0, ..options }).iter()
+            .map(|peptide| peptide.modifications(&wanted, 6))
+            .collect();
+        assert_eq!(applied, [wanted[..2].to_vec(), vec![Modification::Oxidation; 2]]);
+    }
+}
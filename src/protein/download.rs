@@ -0,0 +1,482 @@
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{CONTENT_RANGE, ETAG, IF_RANGE, RANGE};
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use log::{info, warn, error, debug, trace};
+use crate::protein::DatasetProgress;
+
+pub const MAX_DOWNLOAD_ATTEMPTS: usize = 3;
+
+/// Expected properties of a downloaded file. Unset fields are not checked,
+/// but the size reported by the server is always enforced.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntegrityCheck {
+    pub size: Option<u64>,
+    pub sha256: Option<String>,
+}
+
+/// Sidecar written next to a download. While the `.part` file is incomplete
+/// it records the server's ETag and size so a resume can send `If-Range`;
+/// `sha256` is only filled in once the file has been verified and renamed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DownloadMetadata {
+    pub etag: Option<String>,
+    pub size: Option<u64>,
+    pub sha256: Option<String>,
+}
+
+impl DownloadMetadata {
+    pub fn read(path: &Path) -> Option<DownloadMetadata> {
+        let file = File::open(path).ok()?;
+        let mut metadata = DownloadMetadata::default();
+
+        for line in BufReader::new(file).lines() {
+            let line = line.ok()?;
+            match line.split_once('=') {
+                Some(("etag", value)) => metadata.etag = Some(value.to_string()),
+                Some(("size", value)) => metadata.size = value.parse().ok(),
+                Some(("sha256", value)) => metadata.sha256 = Some(value.to_string()),
+                _ => debug!("Ignoring unknown metadata line in {path:?}: {line}"),
+            }
+        }
+
+        Some(metadata)
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut contents = String::new();
+        if let Some(etag) = &self.etag {
+            contents.push_str(&format!("etag={etag}\n"));
+        }
+        if let Some(size) = self.size {
+            contents.push_str(&format!("size={size}\n"));
+        }
+        if let Some(sha256) = &self.sha256 {
+            contents.push_str(&format!("sha256={sha256}\n"));
+        }
+        fs::write(path, contents)
+    }
+}
+
+pub fn part_path(destination: &Path) -> PathBuf {
+    append_extension(destination, "part")
+}
+
+pub fn metadata_path(destination: &Path) -> PathBuf {
+    append_extension(destination, "meta")
+}
+
+fn append_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 65536];
+
+    loop {
+        let bytes_read = file.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+
+    Ok(hasher.finalize().iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// The sidecar of a completed, verified download whose size still matches
+/// it and `check`. Cheap: the file itself is not hashed.
+pub fn recorded_download(destination: &Path, check: &IntegrityCheck) -> Option<DownloadMetadata> {
+    let Some(metadata) = DownloadMetadata::read(&metadata_path(destination)) else {
+        debug!("No download metadata for {destination:?}");
+        return None;
+    };
+    let (Some(recorded_sha256), Ok(file_size)) = (&metadata.sha256, fs::metadata(destination).map(|m| m.len())) else {
+        debug!("Download {destination:?} is missing or was never verified");
+        return None;
+    };
+
+    if metadata.size.is_some_and(|size| size != file_size) || check.size.is_some_and(|size| size != file_size) {
+        warn!("Cached download {destination:?} has unexpected size {file_size}");
+        return None;
+    }
+    if check.sha256.as_ref().is_some_and(|expected| !expected.eq_ignore_ascii_case(recorded_sha256)) {
+        warn!("Cached download {destination:?} was verified against another SHA-256 ({recorded_sha256})");
+        return None;
+    }
+
+    Some(metadata)
+}
+
+/// Checks a completed download against its sidecar and `check`. Files without
+/// a verified sidecar (e.g. left by older versions) are treated as corrupt.
+pub fn verify_cached_download(destination: &Path, check: &IntegrityCheck) -> bool {
+    let Some(recorded_sha256) = recorded_download(destination, check).and_then(|metadata| metadata.sha256) else {
+        return false;
+    };
+
+    match sha256_file(destination) {
+        Ok(actual) if actual == recorded_sha256 => true,
+        Ok(actual) => {
+            warn!("Cached download {destination:?} has unexpected SHA-256 {actual}");
+            false
+        },
+        Err(e) => {
+            warn!("Failed to hash cached download {destination:?}: {e}");
+            false
+        }
+    }
+}
+
+/// Removes a download together with its part-file and sidecar.
+pub fn discard_download(destination: &Path) {
+    for path in [destination.to_path_buf(), part_path(destination), metadata_path(destination)] {
+        if path.exists() {
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("Failed to remove {path:?}: {e}");
+            }
+        }
+    }
+}
+
+/// Downloads `url` to `destination` through a `.part` file, resuming with an
+/// HTTP Range request when a previous attempt was interrupted. The file is only
+/// renamed into place once its size and SHA-256 pass `check`.
+pub fn download_verified(
+    client: &Client,
+    url: &str,
+    destination: &Path,
+    check: &IntegrityCheck,
+    progress_callback: Option<&dyn Fn(DatasetProgress)>
+) -> Result<DownloadMetadata, Box<dyn Error>> {
+    let part_file = part_path(destination);
+    let metadata_file = metadata_path(destination);
+    let mut last_error: Option<Box<dyn Error>> = None;
+
+    for attempt in 1..=MAX_DOWNLOAD_ATTEMPTS {
+        debug!("Download attempt {attempt}/{MAX_DOWNLOAD_ATTEMPTS} for {url}");
+        match download_to_part_file(client, url, &part_file, &metadata_file, progress_callback) {
+            Ok(metadata) => return finish_download(destination, &part_file, &metadata_file, metadata, check, progress_callback),
+            Err(e) => {
+                warn!("Download attempt {attempt} for {url} failed: {e}");
+                last_error = Some(e);
+            }
+        }
+    }
+
+    error!("Giving up on {url} after {MAX_DOWNLOAD_ATTEMPTS} attempts");
+    Err(last_error.unwrap_or_else(|| "download failed".into()))
+}
+
+fn download_to_part_file(
+    client: &Client,
+    url: &str,
+    part_file: &Path,
+    metadata_file: &Path,
+    progress_callback: Option<&dyn Fn(DatasetProgress)>
+) -> Result<DownloadMetadata, Box<dyn Error>> {
+    let resume_from = fs::metadata(part_file).map(|m| m.len()).unwrap_or(0);
+    let previous = DownloadMetadata::read(metadata_file).unwrap_or_default();
+
+    let mut request = client.get(url);
+    if resume_from > 0 {
+        info!("Resuming download of {url} from byte {resume_from}");
+        request = request.header(RANGE, format!("bytes={resume_from}-"));
+        if let Some(etag) = &previous.etag {
+            request = request.header(IF_RANGE, etag.as_str());
+        }
+    }
+
+    let mut response = request.send()?;
+    let status = response.status();
+    debug!("Response status {status} for {url}");
+
+    let (mut file, offset, total_size) = match status {
+        StatusCode::PARTIAL_CONTENT if content_range_start(&response) == Some(resume_from) => {
+            let total_size = content_range_total(&response);
+            (OpenOptions::new().append(true).open(part_file)?, resume_from, total_size)
+        },
+        StatusCode::RANGE_NOT_SATISFIABLE => {
+            // The remote file no longer matches what we have, start over next attempt
+            fs::remove_file(part_file)?;
+            return Err(format!("server rejected resume from byte {resume_from}").into());
+        },
+        status if status.is_success() => {
+            if resume_from > 0 {
+                info!("Server sent the full file, restarting download of {url}");
+            }
+            let total_size = response.content_length();
+            (File::create(part_file)?, 0, total_size)
+        },
+        status => return Err(format!("HTTP {status} for {url}").into()),
+    };
+
+    let etag = response.headers().get(ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let metadata = DownloadMetadata { etag, size: total_size, sha256: None };
+    metadata.write(metadata_file)?;
+
+    stream_response(&mut response, &mut file, offset, total_size, progress_callback)?;
+    Ok(metadata)
+}
+
+fn stream_response(
+    response: &mut Response,
+    file: &mut File,
+    offset: u64,
+    total_size: Option<u64>,
+    progress_callback: Option<&dyn Fn(DatasetProgress)>
+) -> Result<(), Box<dyn Error>> {
+    let mut downloaded = offset;
+    let mut buffer = [0; 8192];
+
+    loop {
+        let bytes_read = response.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+
+        file.write_all(&buffer[..bytes_read])?;
+        downloaded += bytes_read as u64;
+
+        if downloaded.is_multiple_of(1024 * 1024) { // Log every MB
+            trace!("Downloaded {downloaded} bytes");
+        }
+
+        if let Some(callback) = progress_callback {
+            callback(DatasetProgress::Downloading {
+                bytes_downloaded: downloaded,
+                total_bytes: total_size,
+            });
+        }
+    }
+
+    file.flush()?;
+    info!("Download stream finished at {downloaded} bytes");
+
+    if let Some(total) = total_size {
+        if downloaded < total {
+            return Err(format!("connection closed after {downloaded} of {total} bytes").into());
+        }
+    }
+
+    Ok(())
+}
+
+fn finish_download(
+    destination: &Path,
+    part_file: &Path,
+    metadata_file: &Path,
+    mut metadata: DownloadMetadata,
+    check: &IntegrityCheck,
+    progress_callback: Option<&dyn Fn(DatasetProgress)>
+) -> Result<DownloadMetadata, Box<dyn Error>> {
+    if let Some(callback) = progress_callback {
+        callback(DatasetProgress::Verifying);
+    }
+
+    let size = fs::metadata(part_file)?.len();
+    let expected_size = check.size.or(metadata.size);
+    if expected_size.is_some_and(|expected| expected != size) {
+        discard_download(destination);
+        return Err(format!("downloaded {size} bytes, expected {}", expected_size.unwrap_or_default()).into());
+    }
+
+    let sha256 = sha256_file(part_file)?;
+    if let Some(expected) = &check.sha256 {
+        if !expected.eq_ignore_ascii_case(&sha256) {
+            discard_download(destination);
+            return Err(format!("SHA-256 mismatch: expected {expected}, got {sha256}").into());
+        }
+    }
+
+    fs::rename(part_file, destination)?;
+    metadata.size = Some(size);
+    metadata.sha256 = Some(sha256);
+    metadata.write(metadata_file)?;
+
+    info!("Verified download {destination:?} ({size} bytes)");
+    Ok(metadata)
+}
+
+fn content_range_start(response: &Response) -> Option<u64> {
+    parse_content_range(response).map(|(start, _)| start)
+}
+
+fn content_range_total(response: &Response) -> Option<u64> {
+    parse_content_range(response).and_then(|(_, total)| total)
+}
+
+/// Parses `Content-Range: bytes START-END/TOTAL`, where TOTAL may be `*`.
+fn parse_content_range(response: &Response) -> Option<(u64, Option<u64>)> {
+    let value = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let start = range.split_once('-')?.0.parse().ok()?;
+    Some((start, total.parse().ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    /// Minimal HTTP/1.1 stand-in that serves one body with ETag and Range
+    /// support, optionally cutting off the first response early.
+    struct TestServer {
+        url: String,
+        requests: Arc<Mutex<Vec<Vec<String>>>>,
+    }
+
+    impl TestServer {
+        fn start(body: Vec<u8>, etag: &'static str, truncate_first_at: Option<usize>) -> TestServer {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}/dataset.txt.gz", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let recorded = Arc::clone(&requests);
+
+            thread::spawn(move || {
+                for (index, stream) in listener.incoming().enumerate() {
+                    let Ok(stream) = stream else { break };
+                    let truncate_at = if index == 0 { truncate_first_at } else { None };
+                    let headers = serve(stream, &body, etag, truncate_at);
+                    recorded.lock().unwrap().push(headers);
+                }
+            });
+
+            TestServer { url, requests }
+        }
+
+        fn requests(&self) -> Vec<Vec<String>> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    fn serve(mut stream: TcpStream, body: &[u8], etag: &str, truncate_at: Option<usize>) -> Vec<String> {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut headers = Vec::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 || line.trim().is_empty() {
+                break;
+            }
+            headers.push(line.trim().to_lowercase());
+        }
+
+        let header = |name: &str| headers.iter()
+            .find_map(|line| line.strip_prefix(&format!("{name}: ")).map(str::to_string));
+        let range_start = header("range")
+            .and_then(|range| range.strip_prefix("bytes=")?.strip_suffix('-')?.parse::<usize>().ok())
+            .filter(|_| header("if-range").is_none_or(|tag| tag == etag.to_lowercase()));
+
+        let (status, start) = match range_start {
+            Some(start) if start < body.len() => ("206 Partial Content", start),
+            Some(_) => ("416 Range Not Satisfiable", body.len()),
+            None => ("200 OK", 0),
+        };
+
+        let mut response = format!("HTTP/1.1 {status}\r\nContent-Length: {}\r\nETag: {etag}\r\nConnection: close\r\n", body.len() - start);
+        if range_start.is_some() {
+            response.push_str(&format!("Content-Range: bytes {start}-{}/{}\r\n", body.len() - 1, body.len()));
+        }
+        response.push_str("\r\n");
+
+        let end = truncate_at.map_or(body.len(), |at| at.min(body.len()));
+        let _ = stream.write_all(response.as_bytes());
+        let _ = stream.write_all(&body[start..end.max(start)]);
+        headers
+    }
+
+    fn test_body() -> Vec<u8> {
+        (0..200_000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn sha256_bytes(bytes: &[u8]) -> String {
+        Sha256::digest(bytes).iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    #[test]
+    fn test_interrupted_download_resumes_with_range() {
+        let body = test_body();
+        let server = TestServer::start(body.clone(), "\"v1\"", Some(70_000));
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("dataset.txt.gz");
+        let check = IntegrityCheck { size: Some(body.len() as u64), sha256: Some(sha256_bytes(&body)) };
+
+        let metadata = download_verified(&Client::new(), &server.url, &destination, &check, None).unwrap();
+
+        assert_eq!(fs::read(&destination).unwrap(), body);
+        assert!(!part_path(&destination).exists());
+        assert_eq!(metadata.etag.as_deref(), Some("\"v1\""));
+        assert!(verify_cached_download(&destination, &check));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].contains(&"range: bytes=70000-".to_string()));
+        assert!(requests[1].contains(&"if-range: \"v1\"".to_string()));
+    }
+
+    #[test]
+    fn test_changed_etag_restarts_download() {
+        let body = test_body();
+        let server = TestServer::start(body.clone(), "\"v2\"", None);
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("dataset.txt.gz");
+
+        // Leftover part-file from an older version of the remote file
+        fs::write(part_path(&destination), vec![0xff; 5000]).unwrap();
+        DownloadMetadata { etag: Some("\"v1\"".to_string()), size: Some(9999), sha256: None }
+            .write(&metadata_path(&destination)).unwrap();
+
+        download_verified(&Client::new(), &server.url, &destination, &IntegrityCheck::default(), None).unwrap();
+        assert_eq!(fs::read(&destination).unwrap(), body);
+    }
+
+    #[test]
+    fn test_checksum_mismatch_is_not_renamed_into_place() {
+        let server = TestServer::start(test_body(), "\"v1\"", None);
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("dataset.txt.gz");
+        let check = IntegrityCheck { size: None, sha256: Some("00".repeat(32)) };
+
+        let error = download_verified(&Client::new(), &server.url, &destination, &check, None).unwrap_err();
+        assert!(error.to_string().contains("SHA-256 mismatch"));
+        assert!(!destination.exists());
+        assert!(!part_path(&destination).exists());
+    }
+
+    #[test]
+    fn test_corrupt_cache_is_detected() {
+        let body = test_body();
+        let server = TestServer::start(body.clone(), "\"v1\"", None);
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("dataset.txt.gz");
+        let check = IntegrityCheck::default();
+
+        download_verified(&Client::new(), &server.url, &destination, &check, None).unwrap();
+        assert!(verify_cached_download(&destination, &check));
+
+        let mut corrupted = body.clone();
+        corrupted[1234] ^= 0xff;
+        fs::write(&destination, &corrupted).unwrap();
+        // Only hashing catches a flipped byte; the sidecar check does not
+        assert!(recorded_download(&destination, &check).is_some());
+        assert!(!verify_cached_download(&destination, &check));
+
+        fs::write(&destination, &body[..1000]).unwrap();
+        assert!(recorded_download(&destination, &check).is_none());
+        assert!(!verify_cached_download(&destination, &check));
+    }
+}
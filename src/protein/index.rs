@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, Write};
+use log::{debug, info};
+use crate::protein::SmallProtein;
+use crate::protein::snapshot::{SnapshotReader, SnapshotWriter};
+
+pub const DEFAULT_KMER_SIZE: usize = 8;
+pub const DEFAULT_PEPTIDE_KMER_SIZE: usize = 3;
+pub const DEFAULT_CANDIDATE_LIMIT: usize = 50;
+
+/// K-mer inverted index over `SmallProtein::rna_seq` (and a peptide k-mer index
+/// over `aa_seq`), used to pick a short list of candidates to align instead of
+/// scanning the whole dataset.
+#[derive(Debug, Clone)]
+pub struct ProteinIndex {
+    kmer_size: usize,
+    peptide_kmer_size: usize,
+    protein_count: usize,
+    postings: HashMap<u64, Vec<u32>>,
+    peptide_postings: HashMap<u64, Vec<u32>>,
+}
+
+impl ProteinIndex {
+    pub fn new(proteins: &[SmallProtein]) -> ProteinIndex {
+        Self::with_kmer_size(proteins, DEFAULT_KMER_SIZE)
+    }
+
+    pub fn with_kmer_size(proteins: &[SmallProtein], kmer_size: usize) -> ProteinIndex {
+        assert!((1..=32).contains(&kmer_size), "k-mer size must be between 1 and 32");
+
+        let peptide_kmer_size = DEFAULT_PEPTIDE_KMER_SIZE;
+        let mut postings: HashMap<u64, Vec<u32>> = HashMap::new();
+        let mut peptide_postings: HashMap<u64, Vec<u32>> = HashMap::new();
+
+        for (protein_index, protein) in proteins.iter().enumerate() {
+            let protein_index = protein_index as u32;
+            add_postings(&mut postings, encode_kmers(&protein.rna_seq, kmer_size), protein_index);
+            add_postings(&mut peptide_postings, encode_peptide_kmers(&protein.aa_seq, peptide_kmer_size), protein_index);
+        }
+
+        info!("Built protein k-mer index: {} proteins, {} distinct {}-mers, {} distinct peptide {}-mers",
+              proteins.len(), postings.len(), kmer_size, peptide_postings.len(), peptide_kmer_size);
+
+        ProteinIndex {
+            kmer_size,
+            peptide_kmer_size,
+            protein_count: proteins.len(),
+            postings,
+            peptide_postings,
+        }
+    }
+
+    pub fn kmer_size(&self) -> usize {
+        self.kmer_size
+    }
+
+    pub fn protein_count(&self) -> usize {
+        self.protein_count
+    }
+
+    /// Returns up to `limit` protein indices ranked by the number of k-mers they
+    /// share with `query`. Returns `None` when the query is too short to be
+    /// looked up, in which case callers should fall back to a full scan.
+    pub fn candidates(&self, query: &str, limit: usize) -> Option<Vec<usize>> {
+        if query.len() < self.kmer_size {
+            return None;
+        }
+
+        Some(rank_candidates(&self.postings, encode_kmers(query, self.kmer_size), limit))
+    }
+
+    /// Same as `candidates`, but looks up an amino-acid query in the `aa_seq` index.
+    pub fn peptide_candidates(&self, peptide: &str, limit: usize) -> Option<Vec<usize>> {
+        if peptide.len() < self.peptide_kmer_size {
+            return None;
+        }
+
+        Some(rank_candidates(&self.peptide_postings, encode_peptide_kmers(peptide, self.peptide_kmer_size), limit))
+    }
+
+    pub(crate) fn write_snapshot<W: Write>(&self, writer: &mut SnapshotWriter<W>) -> io::Result<()> {
+        writer.u32(self.kmer_size as u32)?;
+        writer.u32(self.peptide_kmer_size as u32)?;
+        writer.u64(self.protein_count as u64)?;
+        write_postings(writer, &self.postings)?;
+        write_postings(writer, &self.peptide_postings)
+    }
+
+    pub(crate) fn read_snapshot(reader: &mut SnapshotReader) -> Result<ProteinIndex, Box<dyn Error>> {
+        let kmer_size = reader.u32()? as usize;
+        let peptide_kmer_size = reader.u32()? as usize;
+        if kmer_size != DEFAULT_KMER_SIZE || peptide_kmer_size != DEFAULT_PEPTIDE_KMER_SIZE {
+            return Err(format!("snapshot index uses {kmer_size}/{peptide_kmer_size}-mers").into());
+        }
+
+        Ok(ProteinIndex {
+            kmer_size,
+            peptide_kmer_size,
+            protein_count: reader.u64()? as usize,
+            postings: read_postings(reader)?,
+            peptide_postings: read_postings(reader)?,
+        })
+    }
+}
+
+fn write_postings<W: Write>(writer: &mut SnapshotWriter<W>, postings: &HashMap<u64, Vec<u32>>) -> io::Result<()> {
+    writer.u64(postings.len() as u64)?;
+    for (kmer, list) in postings {
+        writer.u64(*kmer)?;
+        writer.u32(list.len() as u32)?;
+        for protein_index in list {
+            writer.u32(*protein_index)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_postings(reader: &mut SnapshotReader) -> Result<HashMap<u64, Vec<u32>>, Box<dyn Error>> {
+    let kmer_count = reader.u64()? as usize;
+    let mut postings = HashMap::with_capacity(kmer_count.min(reader.remaining() / 12));
+
+    for _ in 0..kmer_count {
+        let kmer = reader.u64()?;
+        let len = reader.u32()? as usize;
+        let list = reader.bytes(len * 4)?
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        postings.insert(kmer, list);
+    }
+
+    Ok(postings)
+}
+
+fn add_postings(postings: &mut HashMap<u64, Vec<u32>>, kmers: Vec<u64>, protein_index: u32) {
+    for kmer in kmers {
+        let list = postings.entry(kmer).or_default();
+        // Proteins are visited in order, so a repeated k-mer is always at the tail
+        if list.last() != Some(&protein_index) {
+            list.push(protein_index);
+        }
+    }
+}
+
+fn rank_candidates(postings: &HashMap<u64, Vec<u32>>, mut query_kmers: Vec<u64>, limit: usize) -> Vec<usize> {
+    query_kmers.sort_unstable();
+    query_kmers.dedup();
+
+    let mut shared_counts: HashMap<u32, usize> = HashMap::new();
+    for kmer in &query_kmers {
+        if let Some(list) = postings.get(kmer) {
+            for &protein_index in list {
+                *shared_counts.entry(protein_index).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut ranked: Vec<(u32, usize)> = shared_counts.into_iter().collect();
+    ranked.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    ranked.truncate(limit);
+
+    debug!("Index lookup: {} query k-mers, {} candidates", query_kmers.len(), ranked.len());
+
+    ranked.into_iter().map(|(protein_index, _)| protein_index as usize).collect()
+}
+
+fn encode_base(base: u8) -> Option<u64> {
+    match base {
+        b'A' | b'a' => Some(0),
+        b'C' | b'c' => Some(1),
+        b'G' | b'g' => Some(2),
+        b'T' | b't' | b'U' | b'u' => Some(3),
+        _ => None,
+    }
+}
+
+/// 2-bit encodes every k-mer of `sequence`, skipping windows that contain a
+/// non-ACGT character.
+fn encode_kmers(sequence: &str, kmer_size: usize) -> Vec<u64> {
+    let mask = if kmer_size == 32 { u64::MAX } else { (1u64 << (2 * kmer_size)) - 1 };
+    let mut kmers = Vec::with_capacity(sequence.len().saturating_sub(kmer_size) + 1);
+    let mut code = 0u64;
+    let mut valid_bases = 0;
+
+    for &base in sequence.as_bytes() {
+        match encode_base(base) {
+            Some(bits) => {
+                code = ((code << 2) | bits) & mask;
+                valid_bases += 1;
+                if valid_bases >= kmer_size {
+                    kmers.push(code);
+                }
+            },
+            None => {
+                code = 0;
+                valid_bases = 0;
+            }
+        }
+    }
+
+    kmers
+}
+
+/// 5-bit encodes every k-mer of an amino-acid sequence, skipping windows that
+/// contain a stop or non-letter character.
+fn encode_peptide_kmers(peptide: &str, kmer_size: usize) -> Vec<u64> {
+    let mask = (1u64 << (5 * kmer_size)) - 1;
+    let mut kmers = Vec::new();
+    let mut code = 0u64;
+    let mut valid_residues = 0;
+
+    for &residue in peptide.as_bytes() {
+        if residue.is_ascii_alphabetic() {
+            code = ((code << 5) | (residue.to_ascii_uppercase() - b'A') as u64) & mask;
+            valid_residues += 1;
+            if valid_residues >= kmer_size {
+                kmers.push(code);
+            }
+        } else {
+            code = 0;
+            valid_residues = 0;
+        }
+    }
+
+    kmers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::protein::{parse_small_protein_dataset, smith_waterman, AlignmentScoring};
+
+    fn load_fixture() -> Vec<SmallProtein> {
+        let data = include_str!("../../tests/fixtures/smprot_sample.txt");
+        parse_small_protein_dataset(Cursor::new(data), None).unwrap()
+    }
+
+    fn best_hit<'a>(query: &str, proteins: &'a [SmallProtein], indices: impl Iterator<Item = usize>) -> Option<&'a str> {
+        let scoring = AlignmentScoring::default();
+        let mut best: Option<(i32, &str)> = None;
+
+        for i in indices {
+            if let Some(alignment) = smith_waterman(query, &proteins[i].rna_seq, &scoring) {
+                if best.is_none_or(|(score, _)| alignment.score > score) {
+                    best = Some((alignment.score, &proteins[i].id));
+                }
+            }
+        }
+
+        best.map(|(_, id)| id)
+    }
+
+    #[test]
+    fn test_kmer_encoding_skips_ambiguous_bases() {
+        assert_eq!(encode_kmers("ACGT", 2), vec![0b0001, 0b0110, 0b1011]);
+        assert_eq!(encode_kmers("ACNGT", 2), vec![0b0001, 0b1011]);
+        assert!(encode_kmers("AC", 3).is_empty());
+    }
+
+    #[test]
+    fn test_peptide_candidates_find_source_protein() {
+        let proteins = load_fixture();
+        let index = ProteinIndex::new(&proteins);
+        let target = &proteins[7].aa_seq;
+
+        let candidates = index.peptide_candidates(&target[2..14], 5).unwrap();
+        assert_eq!(candidates[0], 7);
+        assert!(index.peptide_candidates("MA", 5).is_none());
+    }
+
+    #[test]
+    fn test_short_query_falls_back_to_scan() {
+        let proteins = load_fixture();
+        let index = ProteinIndex::new(&proteins);
+        assert!(index.candidates("ATGC", DEFAULT_CANDIDATE_LIMIT).is_none());
+    }
+
+    #[test]
+    fn test_index_top_hit_matches_brute_force() {
+        let proteins = load_fixture();
+        let index = ProteinIndex::new(&proteins);
+        assert_eq!(index.protein_count(), proteins.len());
+
+        for (i, protein) in proteins.iter().enumerate().step_by(3) {
+            let seq = &protein.rna_seq;
+            let fragment = &seq[seq.len() / 4..seq.len() * 3 / 4];
+
+            // Introduce a substitution and an inserted base to mimic sequencing noise
+            let mut query = fragment.to_string();
+            query.replace_range(5..6, if &query[5..6] == "A" { "C" } else { "A" });
+            query.insert(query.len() / 2, 'G');
+
+            let candidates = index.candidates(&query, DEFAULT_CANDIDATE_LIMIT).unwrap();
+            assert!(candidates.contains(&i));
+
+            let brute_force = best_hit(&query, &proteins, 0..proteins.len());
+            let indexed = best_hit(&query, &proteins, candidates.into_iter());
+            assert_eq!(indexed, brute_force);
+            assert_eq!(indexed, Some(protein.id.as_str()));
+        }
+    }
+}
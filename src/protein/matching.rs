@@ -1,4 +1,6 @@
 use log::trace;
+use crate::protein::substitution::SubstitutionMatrix;
+use crate::sequence::base_match_probability;
 
 pub fn calculate_dna_similarity(seq1: &str, seq2: &str) -> f64 {
     let seq1 = seq1.to_uppercase();
@@ -114,4 +116,683 @@ pub fn calculate_amino_acid_similarity(seq1: &str, seq2: &str) -> f64 {
         .count();
 
     (matches as f64 / min_len as f64) * 100.0
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlignmentScoring {
+    /// Added for identical bases; positive
+    pub match_score: i32,
+    /// Added for differing bases; negative
+    pub mismatch_score: i32,
+    /// Subtracted once per gap; positive
+    pub gap_open_penalty: i32,
+    /// Subtracted for every position of a gap; positive
+    pub gap_extend_penalty: i32,
+}
+
+impl Default for AlignmentScoring {
+    fn default() -> Self {
+        // Same reward/penalty and gap costs as NCBI blastn
+        AlignmentScoring {
+            match_score: 2,
+            mismatch_score: -3,
+            gap_open_penalty: 5,
+            gap_extend_penalty: 2,
+        }
+    }
+}
+
+impl AlignmentScoring {
+    pub fn max_score(&self, query_len: usize) -> i32 {
+        query_len as i32 * self.match_score
+    }
+
+    /// Score for aligning two nucleotides. IUPAC ambiguity codes get partial
+    /// credit: the expected score over their expansions, rounded.
+    pub fn substitution(&self, a: u8, b: u8) -> i32 {
+        let is_plain = |base: u8| matches!(base, b'A' | b'C' | b'G' | b'T');
+        if is_plain(a) && is_plain(b) {
+            return if a == b { self.match_score } else { self.mismatch_score };
+        }
+
+        let probability = base_match_probability(a, b);
+        (probability * self.match_score as f64 + (1.0 - probability) * self.mismatch_score as f64).round() as i32
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProteinScoring {
+    pub matrix: SubstitutionMatrix,
+    pub gap_open_penalty: i32,
+    pub gap_extend_penalty: i32,
+}
+
+impl Default for ProteinScoring {
+    fn default() -> Self {
+        // Same matrix and gap costs as NCBI blastp/tblastn
+        ProteinScoring {
+            matrix: SubstitutionMatrix::Blosum62,
+            gap_open_penalty: 11,
+            gap_extend_penalty: 1,
+        }
+    }
+}
+
+impl ProteinScoring {
+    /// Score of aligning `query` against itself.
+    pub fn max_score(&self, query: &str) -> i32 {
+        query.bytes().map(|residue| self.matrix.score(residue, residue)).sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalAlignment {
+    pub score: i32,
+    pub query_start: usize,
+    pub query_end: usize,
+    pub target_start: usize,
+    pub target_end: usize,
+    pub cigar: String,
+}
+
+impl LocalAlignment {
+    /// Expands the run-length CIGAR string into `(length, op)` pairs.
+    pub fn cigar_ops(&self) -> Vec<(usize, char)> {
+        let mut ops = Vec::new();
+        let mut length = 0;
+
+        for c in self.cigar.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                length = length * 10 + digit as usize;
+            } else {
+                ops.push((length, c));
+                length = 0;
+            }
+        }
+
+        ops
+    }
+
+    pub fn alignment_length(&self) -> usize {
+        self.cigar_ops().iter().map(|(length, _)| length).sum()
+    }
+
+    pub fn matches(&self) -> usize {
+        self.cigar_ops().iter()
+            .filter(|(_, op)| *op == '=')
+            .map(|(length, _)| length)
+            .sum()
+    }
+
+    /// Percentage of alignment columns that are identical residues.
+    pub fn identity(&self) -> f64 {
+        let length = self.alignment_length();
+        if length == 0 {
+            return 0.0;
+        }
+        (self.matches() as f64 / length as f64) * 100.0
+    }
+
+    /// Score as a percentage of a perfect full-length match of the query.
+    pub fn normalized_score(&self, max_score: i32) -> f64 {
+        if max_score <= 0 {
+            return 0.0;
+        }
+        (self.score as f64 / max_score as f64 * 100.0).min(100.0)
+    }
+
+    /// Marks the nucleotides of each codon whose residue is identical in a
+    /// protein-level alignment.
+    pub fn matching_codon_positions(&self, nucleotide_len: usize) -> Vec<bool> {
+        let residue_positions = self.matching_positions(nucleotide_len.div_ceil(3));
+        let mut positions = vec![false; nucleotide_len];
+
+        for (residue, is_match) in residue_positions.into_iter().enumerate() {
+            if is_match {
+                for position in positions.iter_mut().skip(residue * 3).take(3) {
+                    *position = true;
+                }
+            }
+        }
+
+        positions
+    }
+
+    /// Marks the target positions covered by an identical aligned residue.
+    pub fn matching_positions(&self, target_len: usize) -> Vec<bool> {
+        let mut positions = vec![false; target_len];
+        let mut target_pos = self.target_start;
+
+        for (length, op) in self.cigar_ops() {
+            match op {
+                '=' => {
+                    for position in positions.iter_mut().skip(target_pos).take(length) {
+                        *position = true;
+                    }
+                    target_pos += length;
+                },
+                'X' | 'D' => target_pos += length,
+                _ => {}
+            }
+        }
+
+        positions
+    }
+}
+
+/// Smith-Waterman local alignment with affine gaps (Gotoh). A gap of length k
+/// costs `gap_open_penalty + k * gap_extend_penalty`, as in BLAST.
+pub fn smith_waterman(query: &str, target: &str, scoring: &AlignmentScoring) -> Option<LocalAlignment> {
+    let query = query.to_uppercase();
+    let target = target.to_uppercase();
+
+    local_align(
+        query.as_bytes(),
+        target.as_bytes(),
+        |a, b| scoring.substitution(a, b),
+        scoring.gap_open_penalty,
+        scoring.gap_extend_penalty,
+    )
+}
+
+/// Karlin-Altschul parameters relating raw alignment scores to bit scores and
+/// E-values: `E = K * m * n * exp(-lambda * S)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KarlinAltschulParams {
+    pub lambda: f64,
+    pub k: f64,
+}
+
+impl KarlinAltschulParams {
+    // Gapped values published with NCBI BLAST for (match, mismatch, gap open, gap extend)
+    const BLASTN_GAPPED: [(i32, i32, i32, i32, f64, f64); 3] = [
+        (2, -3, 5, 2, 0.625, 0.41),
+        (2, -3, 4, 4, 0.63, 0.42),
+        (2, -3, 6, 2, 0.63, 0.42),
+    ];
+
+    // Gapped values published with NCBI BLAST for (matrix, gap open, gap extend)
+    const PROTEIN_GAPPED: [(SubstitutionMatrix, i32, i32, f64, f64); 1] = [
+        (SubstitutionMatrix::Blosum62, 11, 1, 0.267, 0.041),
+    ];
+
+    // Conservative K used when no published value exists for the scoring scheme
+    const FALLBACK_K: f64 = 0.1;
+
+    /// Uses published gapped parameters when the scoring scheme has them and
+    /// otherwise solves for the ungapped lambda over a uniform base composition.
+    pub fn for_scoring(scoring: &AlignmentScoring) -> KarlinAltschulParams {
+        let published = Self::BLASTN_GAPPED.iter().find(|(m, x, o, e, _, _)| {
+            *m == scoring.match_score
+                && *x == scoring.mismatch_score
+                && *o == scoring.gap_open_penalty
+                && *e == scoring.gap_extend_penalty
+        });
+
+        if let Some(&(_, _, _, _, lambda, k)) = published {
+            return KarlinAltschulParams { lambda, k };
+        }
+
+        let outcomes = [
+            (0.25, scoring.match_score as f64),
+            (0.75, scoring.mismatch_score as f64),
+        ];
+        let lambda = solve_ungapped_lambda(&outcomes);
+        trace!("No published Karlin-Altschul parameters for {scoring:?}, solved lambda = {lambda:.4}");
+
+        KarlinAltschulParams { lambda, k: Self::FALLBACK_K }
+    }
+
+    /// Protein counterpart of `for_scoring`, solving over a uniform amino-acid
+    /// composition when no published parameters exist.
+    pub fn for_protein_scoring(scoring: &ProteinScoring) -> KarlinAltschulParams {
+        let published = Self::PROTEIN_GAPPED.iter().find(|(matrix, o, e, _, _)| {
+            *matrix == scoring.matrix
+                && *o == scoring.gap_open_penalty
+                && *e == scoring.gap_extend_penalty
+        });
+
+        if let Some(&(_, _, _, lambda, k)) = published {
+            return KarlinAltschulParams { lambda, k };
+        }
+
+        let residues = b"ARNDCQEGHILKMFPSTWYV";
+        let pair_probability = 1.0 / (residues.len() * residues.len()) as f64;
+        let outcomes: Vec<(f64, f64)> = residues.iter()
+            .flat_map(|&a| residues.iter().map(move |&b| (a, b)))
+            .map(|(a, b)| (pair_probability, scoring.matrix.score(a, b) as f64))
+            .collect();
+        let lambda = solve_ungapped_lambda(&outcomes);
+        trace!("No published Karlin-Altschul parameters for {scoring:?}, solved lambda = {lambda:.4}");
+
+        KarlinAltschulParams { lambda, k: Self::FALLBACK_K }
+    }
+
+    pub fn bit_score(&self, raw_score: i32) -> f64 {
+        (self.lambda * raw_score as f64 - self.k.ln()) / std::f64::consts::LN_2
+    }
+
+    /// Expected number of chance hits scoring at least `raw_score` in a search of
+    /// a `query_len` query against `database_len` residues.
+    pub fn e_value(&self, raw_score: i32, query_len: usize, database_len: usize) -> f64 {
+        let search_space = query_len as f64 * database_len as f64;
+        search_space * (-self.bit_score(raw_score) * std::f64::consts::LN_2).exp()
+    }
+}
+
+/// Solves `sum(p * exp(lambda * s)) = 1` over `(p, s)` score outcomes by bisection.
+fn solve_ungapped_lambda(outcomes: &[(f64, f64)]) -> f64 {
+    let expected = |lambda: f64| outcomes.iter()
+        .map(|(probability, score)| probability * (lambda * score).exp())
+        .sum::<f64>() - 1.0;
+
+    let mean_score: f64 = outcomes.iter().map(|(probability, score)| probability * score).sum();
+    if mean_score >= 0.0 || outcomes.iter().all(|(_, score)| *score <= 0.0) {
+        // Without a negative expected score there is no positive root
+        return 0.0;
+    }
+
+    let mut low = 1e-6;
+    let mut high = 1.0;
+    while expected(high) < 0.0 {
+        high *= 2.0;
+    }
+
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if expected(mid) < 0.0 {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    (low + high) / 2.0
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProteinHit {
+    pub protein_index: usize,
+    pub alignment: LocalAlignment,
+    pub identity: f64,
+    pub coverage: f64,
+    pub bit_score: f64,
+    pub e_value: f64,
+    pub is_positive_strand: bool,
+    pub frame: Option<usize>,
+}
+
+impl ProteinHit {
+    pub fn new(
+        protein_index: usize,
+        alignment: LocalAlignment,
+        query_len: usize,
+        is_positive_strand: bool,
+        statistics: &KarlinAltschulParams,
+        database_len: usize,
+    ) -> ProteinHit {
+        let coverage = if query_len == 0 {
+            0.0
+        } else {
+            (alignment.query_end - alignment.query_start) as f64 / query_len as f64 * 100.0
+        };
+
+        ProteinHit {
+            protein_index,
+            identity: alignment.identity(),
+            coverage,
+            bit_score: statistics.bit_score(alignment.score),
+            e_value: statistics.e_value(alignment.score, query_len, database_len),
+            alignment,
+            is_positive_strand,
+            frame: None,
+        }
+    }
+
+    /// Hit from a translated search, where `frame` indexes the six reading frames.
+    pub fn translated(
+        protein_index: usize,
+        frame: usize,
+        alignment: LocalAlignment,
+        frame_len: usize,
+        statistics: &KarlinAltschulParams,
+        database_len: usize,
+    ) -> ProteinHit {
+        let mut hit = ProteinHit::new(protein_index, alignment, frame_len, frame < 3, statistics, database_len);
+        hit.frame = Some(frame);
+        hit
+    }
+
+    pub fn is_significant(&self, e_value_threshold: f64) -> bool {
+        self.e_value <= e_value_threshold
+    }
+
+    pub fn score(&self) -> i32 {
+        self.alignment.score
+    }
+}
+
+/// Sorts hits best-first and keeps the top `limit`.
+pub fn rank_protein_hits(mut hits: Vec<ProteinHit>, limit: usize) -> Vec<ProteinHit> {
+    hits.sort_by(|a, b| {
+        b.score().cmp(&a.score())
+            .then(b.identity.partial_cmp(&a.identity).unwrap_or(std::cmp::Ordering::Equal))
+            .then(a.protein_index.cmp(&b.protein_index))
+    });
+    hits.truncate(limit);
+    hits
+}
+
+/// Protein-level Smith-Waterman using a substitution matrix.
+pub fn smith_waterman_protein(query: &str, target: &str, scoring: &ProteinScoring) -> Option<LocalAlignment> {
+    let query = query.to_uppercase();
+    let target = target.to_uppercase();
+
+    local_align(
+        query.as_bytes(),
+        target.as_bytes(),
+        |a, b| scoring.matrix.score(a, b),
+        scoring.gap_open_penalty,
+        scoring.gap_extend_penalty,
+    )
+}
+
+/// Aligns each translated reading frame of a query against a protein and
+/// returns the best-scoring frame index with its alignment, tblastn-style.
+pub fn align_translated_frames(frames: &[String], target: &str, scoring: &ProteinScoring) -> Option<(usize, LocalAlignment)> {
+    let mut best: Option<(usize, LocalAlignment)> = None;
+
+    for (frame, translation) in frames.iter().enumerate() {
+        if let Some(alignment) = smith_waterman_protein(translation, target, scoring) {
+            if best.as_ref().is_none_or(|(_, current)| alignment.score > current.score) {
+                best = Some((frame, alignment));
+            }
+        }
+    }
+
+    best
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TraceState {
+    Diagonal,
+    Deletion,
+    Insertion,
+}
+
+fn local_align<F>(
+    query: &[u8],
+    target: &[u8],
+    substitution: F,
+    gap_open: i32,
+    gap_extend: i32,
+) -> Option<LocalAlignment>
+where
+    F: Fn(u8, u8) -> i32,
+{
+    let rows = query.len() + 1;
+    let cols = target.len() + 1;
+    if rows == 1 || cols == 1 {
+        return None;
+    }
+
+    let neg_inf = i32::MIN / 2;
+    let gap_first = gap_open + gap_extend;
+
+    let mut h = vec![0i32; rows * cols];
+    let mut e = vec![neg_inf; rows * cols];
+    let mut f = vec![neg_inf; rows * cols];
+
+    let mut best_score = 0;
+    let mut best_cell = (0, 0);
+
+    for i in 1..rows {
+        for j in 1..cols {
+            let idx = i * cols + j;
+
+            e[idx] = (h[idx - 1] - gap_first).max(e[idx - 1] - gap_extend);
+            f[idx] = (h[idx - cols] - gap_first).max(f[idx - cols] - gap_extend);
+
+            let diagonal = h[idx - cols - 1] + substitution(query[i - 1], target[j - 1]);
+            h[idx] = 0.max(diagonal).max(e[idx]).max(f[idx]);
+
+            if h[idx] > best_score {
+                best_score = h[idx];
+                best_cell = (i, j);
+            }
+        }
+    }
+
+    if best_score <= 0 {
+        trace!("No positive-scoring local alignment found");
+        return None;
+    }
+
+    let (mut i, mut j) = best_cell;
+    let mut state = TraceState::Diagonal;
+    let mut ops = Vec::new();
+
+    loop {
+        let idx = i * cols + j;
+        match state {
+            TraceState::Diagonal => {
+                if i == 0 || j == 0 || h[idx] == 0 {
+                    break;
+                }
+                let diagonal = h[idx - cols - 1] + substitution(query[i - 1], target[j - 1]);
+                if h[idx] == diagonal {
+                    ops.push(if query[i - 1] == target[j - 1] { '=' } else { 'X' });
+                    i -= 1;
+                    j -= 1;
+                } else if h[idx] == e[idx] {
+                    state = TraceState::Deletion;
+                } else {
+                    state = TraceState::Insertion;
+                }
+            },
+            TraceState::Deletion => {
+                ops.push('D');
+                if e[idx] == h[idx - 1] - gap_first {
+                    state = TraceState::Diagonal;
+                }
+                j -= 1;
+            },
+            TraceState::Insertion => {
+                ops.push('I');
+                if f[idx] == h[idx - cols] - gap_first {
+                    state = TraceState::Diagonal;
+                }
+                i -= 1;
+            },
+        }
+    }
+
+    ops.reverse();
+
+    let mut cigar = String::new();
+    let mut run_op = None;
+    let mut run_length = 0;
+    for op in ops {
+        if Some(op) == run_op {
+            run_length += 1;
+        } else {
+            if let Some(previous) = run_op {
+                cigar.push_str(&format!("{run_length}{previous}"));
+            }
+            run_op = Some(op);
+            run_length = 1;
+        }
+    }
+    if let Some(previous) = run_op {
+        cigar.push_str(&format!("{run_length}{previous}"));
+    }
+
+    let alignment = LocalAlignment {
+        score: best_score,
+        query_start: i,
+        query_end: best_cell.0,
+        target_start: j,
+        target_end: best_cell.1,
+        cigar,
+    };
+    trace!("Local alignment score {} with CIGAR {}", alignment.score, alignment.cigar);
+
+    Some(alignment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_sequences_align_fully() {
+        let scoring = AlignmentScoring::default();
+        let alignment = smith_waterman("ATGAAACCCGGG", "ATGAAACCCGGG", &scoring).unwrap();
+
+        assert_eq!(alignment.score, 24);
+        assert_eq!(alignment.cigar, "12=");
+        assert_eq!((alignment.query_start, alignment.query_end), (0, 12));
+        assert_eq!((alignment.target_start, alignment.target_end), (0, 12));
+        assert_eq!(alignment.identity(), 100.0);
+    }
+
+    #[test]
+    fn test_single_insertion_keeps_downstream_matches() {
+        let scoring = AlignmentScoring::default();
+        let target = "ATGGCTAGCAAGGAGGAACTGTTCACC";
+        let query = "ATGGCTAGCAAGTGAGGAACTGTTCACC";
+
+        // Position-wise comparison collapses after the inserted base
+        assert!(calculate_dna_similarity(query, target) < 60.0);
+
+        let alignment = smith_waterman(query, target, &scoring).unwrap();
+        assert_eq!(alignment.matches(), target.len());
+        assert_eq!(alignment.cigar_ops().iter().filter(|(_, op)| *op == 'I').count(), 1);
+        assert!(alignment.matching_positions(target.len()).iter().all(|&m| m));
+    }
+
+    #[test]
+    fn test_local_alignment_ignores_flanks() {
+        let scoring = AlignmentScoring::default();
+        let alignment = smith_waterman("CCCCCGATTACAGATTACA", "TTTTGATTACAGATTACATTTT", &scoring).unwrap();
+
+        assert_eq!(alignment.cigar, "14=");
+        assert_eq!((alignment.query_start, alignment.query_end), (5, 19));
+        assert_eq!((alignment.target_start, alignment.target_end), (4, 18));
+
+        let positions = alignment.matching_positions(22);
+        assert!(!positions[3] && positions[4] && positions[17] && !positions[18]);
+    }
+
+    #[test]
+    fn test_ambiguous_bases_get_partial_credit() {
+        let scoring = AlignmentScoring::default();
+        assert_eq!(scoring.substitution(b'A', b'A'), 2);
+        assert_eq!(scoring.substitution(b'A', b'G'), -3);
+        assert_eq!(scoring.substitution(b'R', b'A'), -1);
+        assert_eq!(scoring.substitution(b'N', b'C'), -2);
+        assert_eq!(scoring.substitution(b'U', b'T'), 2);
+
+        let target = "ATGGCTAGCAAGGAGGAACTG";
+        let exact = smith_waterman(target, target, &scoring).unwrap();
+        let ambiguous = smith_waterman("ATGGCTAGCARGGAGGAACTG", target, &scoring).unwrap();
+        let mismatched = smith_waterman("ATGGCTAGCATGGAGGAACTG", target, &scoring).unwrap();
+        assert!(mismatched.score < ambiguous.score && ambiguous.score < exact.score);
+    }
+
+    #[test]
+    fn test_hits_are_ranked_by_score() {
+        let scoring = AlignmentScoring::default();
+        let query = "ATGGCTAGCAAGGAGGAACTG";
+        let targets = ["ATGGCTAGCAAG", "ATGGCTAGCAAGGAGGAACTG", "CCCCATGGCTAGCAAGGAGG"];
+
+        let statistics = KarlinAltschulParams::for_scoring(&scoring);
+        let hits: Vec<ProteinHit> = targets.iter().enumerate()
+            .filter_map(|(i, target)| smith_waterman(query, target, &scoring)
+                .map(|alignment| ProteinHit::new(i, alignment, query.len(), true, &statistics, 1000)))
+            .collect();
+
+        let ranked = rank_protein_hits(hits, 2);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].protein_index, 1);
+        assert_eq!(ranked[0].coverage, 100.0);
+        assert_eq!(ranked[1].protein_index, 2);
+    }
+
+    #[test]
+    fn test_short_perfect_match_is_not_significant() {
+        let scoring = AlignmentScoring::default();
+        let statistics = KarlinAltschulParams::for_scoring(&scoring);
+        let database_len = 1_000_000;
+
+        let short = smith_waterman("ATGGCA", "ATGGCA", &scoring).unwrap();
+        assert!(statistics.e_value(short.score, 6, database_len) > 1.0);
+
+        let query = "ATGGCTAGCAAGGAGGAACTGTTCACCGGGGTGGTGCCCATCCTGGTC";
+        let long = smith_waterman(query, query, &scoring).unwrap();
+        let e_value = statistics.e_value(long.score, query.len(), database_len);
+        assert!(e_value < 1e-10);
+
+        // Bit scores are independent of the search space
+        let bits = statistics.bit_score(long.score);
+        assert!((bits - (0.625 * 96.0 - 0.41f64.ln()) / std::f64::consts::LN_2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ungapped_lambda_solves_expected_score_equation() {
+        let scoring = AlignmentScoring { match_score: 1, mismatch_score: -1, gap_open_penalty: 2, gap_extend_penalty: 1 };
+        let statistics = KarlinAltschulParams::for_scoring(&scoring);
+
+        // 0.25 e^λ + 0.75 e^-λ = 1  =>  λ = ln 3
+        assert!((statistics.lambda - 3f64.ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_translated_search_finds_frame_despite_synonymous_drift() {
+        use crate::sequence::{GeneticCode, translate_all_reading_frames};
+
+        let scoring = ProteinScoring::default();
+        // Synonymous codons throughout, offset by one base so the coding frame is +2
+        let target = "MASKGEELFTGVVPILV";
+        let query = "AATGGCGAGTAAGGGTGAAGAGTTATTCACGGGC";
+
+        let frames = translate_all_reading_frames(query, GeneticCode::standard()).unwrap();
+        let (frame, alignment) = align_translated_frames(&frames, target, &scoring).unwrap();
+
+        assert_eq!(frame, 1);
+        assert_eq!(alignment.cigar, "11=");
+        assert_eq!((alignment.target_start, alignment.target_end), (0, 11));
+
+        let codons = alignment.matching_codon_positions(target.len() * 3);
+        assert!(codons[..33].iter().all(|&m| m));
+        assert!(!codons[33]);
+    }
+
+    #[test]
+    fn test_protein_lambda_uses_published_blosum62_values() {
+        let statistics = KarlinAltschulParams::for_protein_scoring(&ProteinScoring::default());
+        assert_eq!(statistics, KarlinAltschulParams { lambda: 0.267, k: 0.041 });
+
+        let pam = ProteinScoring { matrix: SubstitutionMatrix::Pam250, ..ProteinScoring::default() };
+        let solved = KarlinAltschulParams::for_protein_scoring(&pam);
+        assert!(solved.lambda > 0.0 && solved.lambda < 1.0);
+    }
+
+    #[test]
+    fn test_no_alignment_for_unrelated_or_empty_sequences() {
+        let scoring = AlignmentScoring::default();
+        assert!(smith_waterman("", "ATG", &scoring).is_none());
+        assert!(smith_waterman("AAAA", "CCCC", &scoring).is_none());
+    }
+
+    #[test]
+    fn test_affine_gap_prefers_single_long_gap() {
+        let scoring = AlignmentScoring::default();
+        let target = "ACGTACGTACGTTTTTGCATGCATGCA";
+        let query = "ACGTACGTACGTGCATGCATGCA";
+
+        let alignment = smith_waterman(query, target, &scoring).unwrap();
+        assert_eq!(alignment.cigar, "11=4D12=");
+        assert_eq!(alignment.score, 23 * 2 - (5 + 4 * 2));
+    }
+}
@@ -1,11 +1,33 @@
 pub mod dataset;
+pub mod digestion;
+pub mod download;
+pub mod index;
 pub mod matching;
+pub mod model;
 pub mod molecular_weights;
+pub mod pmf;
+pub mod profiles;
+pub mod properties;
+pub mod snapshot;
+pub mod source;
+pub mod substitution;
+pub mod topology;
 
 pub use dataset::*;
 pub use dataset::DatasetProgress;
+pub use digestion::*;
+pub use download::*;
+pub use index::*;
 pub use matching::*;
+pub use model::*;
 pub use molecular_weights::*;
+pub use pmf::*;
+pub use profiles::*;
+pub use properties::*;
+pub use snapshot::*;
+pub use source::*;
+pub use substitution::*;
+pub use topology::*;
 
 #[cfg(test)]
 mod tests {
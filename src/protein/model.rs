@@ -0,0 +1,245 @@
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+use bio_seq::prelude::*;
+use crate::sequence::{is_ambiguous_nucleotide, is_iupac_nucleotide};
+
+#[derive(Debug, Clone)]
+pub struct SmallProtein {
+    pub species: String,
+    pub id: String,
+    pub rna_seq: NucleotideSequence,
+    pub aa_seq: ProteinSequence,
+    pub length: usize,
+    pub chromosome: String,
+    pub start: usize,
+    pub stop: usize,
+    pub strand: Strand,
+    pub blocks: Vec<GenomicInterval>,
+    pub start_codon: Option<NucleotideSequence>,
+    pub phylo_csf_mean: Option<f64>,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Strand {
+    Forward,
+    Reverse,
+}
+
+impl Strand {
+    pub fn symbol(self) -> char {
+        match self {
+            Strand::Forward => '+',
+            Strand::Reverse => '-',
+        }
+    }
+}
+
+impl FromStr for Strand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "+" => Ok(Strand::Forward),
+            "-" => Ok(Strand::Reverse),
+            other => Err(format!("invalid strand '{other}', expected '+' or '-'")),
+        }
+    }
+}
+
+impl fmt::Display for Strand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+/// One exon of a protein's genomic locus, in the dataset's 1-based inclusive coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenomicInterval {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl GenomicInterval {
+    pub fn len(&self) -> usize {
+        (self.end + 1).saturating_sub(self.start)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end < self.start
+    }
+}
+
+impl FromStr for GenomicInterval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Blocks are sometimes written with the chromosome in front, e.g. chr1:100-200
+        let range = s.trim().rsplit(':').next().unwrap_or("");
+        let (start, end) = range.split_once('-')
+            .ok_or_else(|| format!("invalid block '{s}', expected START-END"))?;
+        let start: usize = start.trim().parse().map_err(|_| format!("invalid block start in '{s}'"))?;
+        let end: usize = end.trim().parse().map_err(|_| format!("invalid block end in '{s}'"))?;
+
+        if start > end {
+            return Err(format!("block '{s}' ends before it starts"));
+        }
+
+        Ok(GenomicInterval { start, end })
+    }
+}
+
+impl fmt::Display for GenomicInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Parses a comma- or semicolon-separated block list.
+pub fn parse_blocks(s: &str) -> Result<Vec<GenomicInterval>, String> {
+    let blocks = s.split([',', ';'])
+        .map(str::trim)
+        .filter(|block| !block.is_empty())
+        .map(GenomicInterval::from_str)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if blocks.is_empty() {
+        return Err("no blocks listed".to_string());
+    }
+    if blocks.windows(2).any(|pair| pair[1].start <= pair[0].end) {
+        return Err(format!("blocks '{s}' overlap or are out of order"));
+    }
+
+    Ok(blocks)
+}
+
+pub fn format_blocks(blocks: &[GenomicInterval]) -> String {
+    blocks.iter().map(GenomicInterval::to_string).collect::<Vec<_>>().join(",")
+}
+
+/// Uppercase DNA text, with IUPAC ambiguity codes allowed and U read as T.
+/// Validated once at parse time and kept as a string, since alignment and the
+/// index work on bytes; `to_seq` converts unambiguous sequences to bio-seq.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct NucleotideSequence(String);
+
+impl NucleotideSequence {
+    pub fn new(sequence: &str) -> Result<NucleotideSequence, String> {
+        let normalized: String = sequence.trim().chars()
+            .map(|c| match c.to_ascii_uppercase() {
+                'U' => 'T',
+                upper => upper,
+            })
+            .collect();
+
+        if let Some((position, invalid)) = normalized.char_indices().find(|(_, c)| !is_iupac_nucleotide(*c)) {
+            return Err(format!("invalid nucleotide '{invalid}' at position {}", position + 1));
+        }
+
+        Ok(NucleotideSequence(normalized))
+    }
+
+    /// For text that was validated before being stored, e.g. in a snapshot.
+    pub(crate) fn from_validated(sequence: String) -> NucleotideSequence {
+        NucleotideSequence(sequence)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_ambiguous(&self) -> bool {
+        self.0.chars().any(is_ambiguous_nucleotide)
+    }
+
+    pub fn to_seq(&self) -> Result<Seq<Dna>, String> {
+        if let Some(position) = self.0.chars().position(is_ambiguous_nucleotide) {
+            return Err(format!("ambiguous nucleotide at position {} has no 2-bit encoding", position + 1));
+        }
+        Ok(self.0.parse().expect("unambiguous sequence contains only ACGT"))
+    }
+}
+
+/// Uppercase amino-acid letters, with `*` allowed for stop codons.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ProteinSequence(String);
+
+impl ProteinSequence {
+    pub fn new(sequence: &str) -> Result<ProteinSequence, String> {
+        let normalized = sequence.trim().to_ascii_uppercase();
+
+        if let Some((position, invalid)) = normalized.char_indices().find(|(_, c)| !(c.is_ascii_uppercase() || *c == '*')) {
+            return Err(format!("invalid amino acid '{invalid}' at position {}", position + 1));
+        }
+
+        Ok(ProteinSequence(normalized))
+    }
+
+    pub(crate) fn from_validated(sequence: String) -> ProteinSequence {
+        ProteinSequence(sequence)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+macro_rules! impl_sequence_str {
+    ($sequence:ty) => {
+        impl Deref for $sequence {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $sequence {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+    };
+}
+
+impl_sequence_str!(NucleotideSequence);
+impl_sequence_str!(ProteinSequence);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequence_validation() {
+        assert_eq!(NucleotideSequence::new("acgu").unwrap().as_str(), "ACGT");
+        assert_eq!(NucleotideSequence::new("ATG-NA").unwrap_err(), "invalid nucleotide '-' at position 4");
+        assert_eq!(NucleotideSequence::new("ATGCCC").unwrap().to_seq().unwrap().len(), 6);
+
+        let ambiguous = NucleotideSequence::new("atgryn").unwrap();
+        assert!(ambiguous.is_ambiguous());
+        assert!(ambiguous.to_seq().is_err());
+
+        assert_eq!(ProteinSequence::new("mkv*").unwrap().as_str(), "MKV*");
+        assert!(ProteinSequence::new("MK-V").is_err());
+    }
+
+    #[test]
+    fn test_blocks_parse() {
+        let blocks = parse_blocks("100-200,chr1:300-350").unwrap();
+        assert_eq!(blocks, vec![GenomicInterval { start: 100, end: 200 }, GenomicInterval { start: 300, end: 350 }]);
+        assert_eq!(blocks[1].len(), 51);
+        assert_eq!(format_blocks(&blocks), "100-200,300-350");
+
+        assert!(parse_blocks("").is_err());
+        assert!(parse_blocks("200-100").is_err());
+        assert!(parse_blocks("100-200,150-250").is_err());
+    }
+
+    #[test]
+    fn test_strand_parse() {
+        assert_eq!("+".parse::<Strand>().unwrap(), Strand::Forward);
+        assert_eq!(" - ".parse::<Strand>().unwrap().to_string(), "-");
+        assert!(".".parse::<Strand>().is_err());
+    }
+}
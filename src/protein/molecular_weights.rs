@@ -1,66 +1,152 @@
-use std::collections::HashMap;
-
-pub fn get_amino_acid_molecular_weight(amino_acid: char) -> f64 {
-    match amino_acid {
-        'A' => 71.04,
-        'R' => 156.10,
-        'N' => 114.04,
-        'D' => 115.03,
-        'C' => 103.01,
-        'E' => 129.04,
-        'Q' => 128.06,
-        'G' => 57.02,
-        'H' => 137.06,
-        'I' => 113.08,
-        'L' => 113.08,
-        'K' => 128.09,
-        'M' => 131.04,
-        'F' => 147.07,
-        'P' => 97.05,
-        'S' => 87.03,
-        'T' => 101.05,
-        'W' => 186.08,
-        'Y' => 163.06,
-        'V' => 99.07,
-        '*' => 0.0,
-        _ => 0.0,
+//! Residue masses and peptide mass calculations. Every mass goes through
+//! `residue_mass`, so monoisotopic and average values never get mixed.
+
+/// Mass of a proton, for m/z.
+pub const PROTON_MASS: f64 = 1.007_276_467;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum MassType {
+    /// Most abundant isotopes only, as measured for peptides in MS
+    #[value(name = "mono")]
+    Monoisotopic,
+    /// Natural isotope abundances, as for intact protein weights
+    #[default]
+    Average,
+}
+
+impl MassType {
+    /// The water a peptide gains over the sum of its residues.
+    pub fn water(self) -> f64 {
+        match self {
+            MassType::Monoisotopic => 18.010_565,
+            MassType::Average => 18.015_28,
+        }
+    }
+}
+
+/// Mass of an amino acid residue within a chain, i.e. without water. `None`
+/// for stops and letters that are not amino acids, including `X`.
+pub fn residue_mass(residue: char, mass_type: MassType) -> Option<f64> {
+    let (monoisotopic, average) = match residue.to_ascii_uppercase() {
+        'G' => (57.021_464, 57.0519),
+        'A' => (71.037_114, 71.0788),
+        'S' => (87.032_028, 87.0782),
+        'P' => (97.052_764, 97.1167),
+        'V' => (99.068_414, 99.1326),
+        'T' => (101.047_679, 101.1051),
+        'C' => (103.009_185, 103.1388),
+        'L' => (113.084_064, 113.1594),
+        'I' => (113.084_064, 113.1594),
+        'N' => (114.042_927, 114.1038),
+        'D' => (115.026_943, 115.0886),
+        'Q' => (128.058_578, 128.1307),
+        'K' => (128.094_963, 128.1741),
+        'E' => (129.042_593, 129.1155),
+        'M' => (131.040_485, 131.1926),
+        'H' => (137.058_912, 137.1411),
+        'F' => (147.068_414, 147.1766),
+        'U' => (150.953_636, 150.0388),
+        'R' => (156.101_111, 156.1875),
+        'Y' => (163.063_329, 163.1760),
+        'W' => (186.079_313, 186.2132),
+        'O' => (237.147_727, 237.3018),
+        _ => return None,
+    };
+    Some(match mass_type {
+        MassType::Monoisotopic => monoisotopic,
+        MassType::Average => average,
+    })
+}
+
+/// Terminal and side-chain modifications. Each one listed for a peptide
+/// modifies one site, so two oxidised methionines are listed twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Modification {
+    /// N-terminal acetylation
+    Acetylation,
+    /// C-terminal amidation
+    Amidation,
+    /// Methionine oxidation
+    Oxidation,
+    /// Phosphorylation of S, T or Y
+    Phosphorylation,
+}
+
+impl Modification {
+    pub fn delta(self, mass_type: MassType) -> f64 {
+        let (monoisotopic, average) = match self {
+            Modification::Acetylation => (42.010_565, 42.0367),
+            Modification::Amidation => (-0.984_016, -0.9848),
+            Modification::Oxidation => (15.994_915, 15.9994),
+            Modification::Phosphorylation => (79.966_331, 79.9799),
+        };
+        match mass_type {
+            MassType::Monoisotopic => monoisotopic,
+            MassType::Average => average,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Modification::Acetylation => "N-terminal acetylation",
+            Modification::Amidation => "C-terminal amidation",
+            Modification::Oxidation => "Met oxidation",
+            Modification::Phosphorylation => "phosphorylation",
+        }
+    }
+
+    /// How many times the modification fits on `residues`.
+    pub fn sites(self, residues: &str) -> usize {
+        match self {
+            Modification::Acetylation | Modification::Amidation => 1,
+            Modification::Oxidation => residues.matches('M').count(),
+            Modification::Phosphorylation => residues.matches(['S', 'T', 'Y']).count(),
+        }
     }
 }
 
-pub fn get_all_molecular_weights() -> HashMap<char, f64> {
-    let mut weights = HashMap::new();
-    weights.insert('A', 71.04);
-    weights.insert('R', 156.10);
-    weights.insert('N', 114.04);
-    weights.insert('D', 115.03);
-    weights.insert('C', 103.01);
-    weights.insert('E', 129.04);
-    weights.insert('Q', 128.06);
-    weights.insert('G', 57.02);
-    weights.insert('H', 137.06);
-    weights.insert('I', 113.08);
-    weights.insert('L', 113.08);
-    weights.insert('K', 128.09);
-    weights.insert('M', 131.04);
-    weights.insert('F', 147.07);
-    weights.insert('P', 97.05);
-    weights.insert('S', 87.03);
-    weights.insert('T', 101.05);
-    weights.insert('W', 186.08);
-    weights.insert('Y', 163.06);
-    weights.insert('V', 99.07);
-    weights.insert('*', 0.0);
-    weights
+impl std::str::FromStr for Modification {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "acetyl" | "acetylation" | "nterm-acetyl" => Ok(Modification::Acetylation),
+            "amide" | "amidation" | "cterm-amide" => Ok(Modification::Amidation),
+            "oxidation" | "met-oxidation" | "ox" => Ok(Modification::Oxidation),
+            "phospho" | "phosphorylation" => Ok(Modification::Phosphorylation),
+            _ => Err(format!("unknown modification '{s}', expected acetyl, amide, oxidation or phospho")),
+        }
+    }
 }
 
-pub fn calculate_protein_molecular_weight(amino_acid_sequence: &str) -> f64 {
-    let mut total_weight = 0.0;
+/// Neutral mass of a peptide or protein with the given modifications. A
+/// trailing stop is ignored; any other non-residue letter is an error, as is
+/// a modification listed more often than it has sites.
+pub fn peptide_mass(sequence: &str, mass_type: MassType, modifications: &[Modification]) -> Result<f64, String> {
+    let residues = sequence.trim().trim_end_matches('*').to_ascii_uppercase();
 
-    for amino_acid in amino_acid_sequence.chars() {
-        total_weight += get_amino_acid_molecular_weight(amino_acid);
+    let mut mass = mass_type.water();
+    for (position, residue) in residues.chars().enumerate() {
+        mass += residue_mass(residue, mass_type)
+            .ok_or_else(|| format!("no mass for residue '{residue}' at position {}", position + 1))?;
     }
 
-    total_weight + 18.015
+    for modification in modifications {
+        let applied = modifications.iter().filter(|other| *other == modification).count();
+        if applied > modification.sites(&residues) {
+            return Err(format!("{} applied {applied} times but '{residues}' has {} site(s)",
+                               modification.name(), modification.sites(&residues)));
+        }
+        mass += modification.delta(mass_type);
+    }
+
+    Ok(mass)
+}
+
+/// m/z of `neutral_mass` carrying `charge` protons.
+pub fn mass_to_charge(neutral_mass: f64, charge: u32) -> f64 {
+    let charge = charge.max(1) as f64;
+    (neutral_mass + charge * PROTON_MASS) / charge
 }
 
 #[cfg(test)]
@@ -69,21 +155,37 @@ mod tests {
 
     #[test]
     fn test_individual_amino_acid_weights() {
-        assert_eq!(get_amino_acid_molecular_weight('A'), 71.04);
-        assert_eq!(get_amino_acid_molecular_weight('G'), 57.02);
-        assert_eq!(get_amino_acid_molecular_weight('W'), 186.08);
-        assert_eq!(get_amino_acid_molecular_weight('*'), 0.0);
-        assert_eq!(get_amino_acid_molecular_weight('X'), 0.0);
+        assert_eq!(residue_mass('A', MassType::Monoisotopic), Some(71.037_114));
+        assert_eq!(residue_mass('g', MassType::Average), Some(57.0519));
+        assert_eq!(residue_mass('W', MassType::Average), Some(186.2132));
+        assert_eq!(residue_mass('*', MassType::Average), None);
+        assert_eq!(residue_mass('X', MassType::Monoisotopic), None);
     }
 
     #[test]
     fn test_protein_molecular_weight() {
-        let weight = calculate_protein_molecular_weight("AG");
-        assert!((weight - 146.075).abs() < 0.001);
+        assert!((peptide_mass("AG", MassType::Average, &[]).unwrap() - 146.1460).abs() < 0.001);
+        assert!((peptide_mass("AG*", MassType::Monoisotopic, &[]).unwrap() - 146.069_143).abs() < 1e-6);
     }
 
     #[test]
     fn test_empty_sequence() {
-        assert_eq!(calculate_protein_molecular_weight(""), 18.015);
+        assert_eq!(peptide_mass("", MassType::Average, &[]), Ok(MassType::Average.water()));
+    }
+
+    #[test]
+    fn test_modified_peptide_mass_and_mz() {
+        // Angiotensin II, [M+H]+ 1046.5418
+        let angiotensin = peptide_mass("DRVYIHPF", MassType::Monoisotopic, &[]).unwrap();
+        assert!((mass_to_charge(angiotensin, 1) - 1046.5418).abs() < 1e-3);
+        assert!((mass_to_charge(angiotensin, 2) - 523.7745).abs() < 1e-3);
+
+        let modified = [Modification::Acetylation, Modification::Phosphorylation];
+        let phospho = peptide_mass("DRVYIHPF", MassType::Monoisotopic, &modified).unwrap();
+        assert!((phospho - angiotensin - 42.010_565 - 79.966_331).abs() < 1e-9);
+
+        let error = peptide_mass("MAK", MassType::Average, &[Modification::Oxidation, Modification::Oxidation]).unwrap_err();
+        assert_eq!(error, "Met oxidation applied 2 times but 'MAK' has 1 site(s)");
+        assert!(peptide_mass("MXK", MassType::Average, &[]).is_err());
     }
 }
@@ -0,0 +1,248 @@
+//! Peptide mass fingerprinting: observed peptide masses are looked up in an
+//! index of in-silico digests of the dataset and proteins ranked by how many
+//! of their peptides were seen.
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use log::{info, debug};
+use crate::protein::digestion::{DigestOptions, digest};
+use crate::protein::model::SmallProtein;
+use crate::protein::molecular_weights::{MassType, Modification, PROTON_MASS, peptide_mass};
+
+/// How peptides are made and peaks compared.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PmfOptions {
+    pub digest: DigestOptions,
+    pub mass_type: MassType,
+    /// Applied to every peptide with a site for them
+    pub modifications: Vec<Modification>,
+    pub tolerance_ppm: f64,
+    /// Charge of the peak list's ions; 0 when it holds neutral masses
+    pub peak_charge: u32,
+}
+
+impl Default for PmfOptions {
+    fn default() -> Self {
+        PmfOptions {
+            digest: DigestOptions::default(),
+            mass_type: MassType::Monoisotopic,
+            modifications: Vec::new(),
+            tolerance_ppm: 10.0,
+            peak_charge: 1,
+        }
+    }
+}
+
+impl PmfOptions {
+    /// The neutral peptide mass behind an observed peak.
+    pub fn neutral_mass(&self, peak: f64) -> f64 {
+        match self.peak_charge {
+            0 => peak,
+            charge => peak * charge as f64 - charge as f64 * PROTON_MASS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IndexedPeptide {
+    /// Neutral mass
+    pub mass: f64,
+    pub protein_index: u32,
+    pub start: u32,
+    pub end: u32,
+    pub missed_cleavages: u8,
+}
+
+/// Every digest peptide of a dataset, sorted by mass.
+#[derive(Debug, Clone)]
+pub struct PeptideMassIndex {
+    pub digest: DigestOptions,
+    pub mass_type: MassType,
+    pub modifications: Vec<Modification>,
+    peptides: Vec<IndexedPeptide>,
+    /// Digest peptides per protein, matched or not
+    peptide_counts: Vec<u32>,
+}
+
+impl PeptideMassIndex {
+    pub fn new(proteins: &[SmallProtein], options: &PmfOptions) -> Self {
+        let (digest_options, mass_type) = (&options.digest, options.mass_type);
+        let mut peptides = Vec::new();
+        let mut peptide_counts = Vec::with_capacity(proteins.len());
+
+        for (protein_index, protein) in proteins.iter().enumerate() {
+            let before = peptides.len();
+            let length = protein.aa_seq.trim_end_matches('*').len();
+            // Peptides with residues that have no mass (X) cannot be matched
+            peptides.extend(digest(&protein.aa_seq, digest_options).into_iter().filter_map(|peptide| Some(IndexedPeptide {
+                mass: peptide_mass(&peptide.sequence, mass_type, &peptide.modifications(&options.modifications, length)).ok()?,
+                protein_index: protein_index as u32,
+                start: peptide.start as u32,
+                end: peptide.end as u32,
+                missed_cleavages: peptide.missed_cleavages as u8,
+            })));
+            peptide_counts.push((peptides.len() - before) as u32);
+        }
+
+        peptides.sort_by(|a, b| a.mass.total_cmp(&b.mass));
+        info!("Indexed {} {} peptides from {} proteins", peptides.len(), digest_options.protease.name(), proteins.len());
+        PeptideMassIndex { digest: *digest_options, mass_type, modifications: options.modifications.clone(), peptides, peptide_counts }
+    }
+
+    /// Whether the index was built the way `options` asks for.
+    pub fn built_for(&self, options: &PmfOptions) -> bool {
+        self.digest == options.digest && self.mass_type == options.mass_type && self.modifications == options.modifications
+    }
+
+    pub fn len(&self) -> usize {
+        self.peptides.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peptides.is_empty()
+    }
+
+    /// Peptides within `tolerance_ppm` of `mass`.
+    pub fn lookup(&self, mass: f64, tolerance_ppm: f64) -> &[IndexedPeptide] {
+        let tolerance = mass * tolerance_ppm * 1e-6;
+        let low = self.peptides.partition_point(|peptide| peptide.mass < mass - tolerance);
+        let high = self.peptides.partition_point(|peptide| peptide.mass <= mass + tolerance);
+        &self.peptides[low..high]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeptideMatch {
+    pub peak: f64,
+    pub peptide: IndexedPeptide,
+    pub error_ppm: f64,
+}
+
+/// A protein with peptides seen in the peak list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PmfHit {
+    pub protein_index: usize,
+    /// One per distinct peptide, in sequence order
+    pub matches: Vec<PeptideMatch>,
+    /// Digest peptides of the protein in total
+    pub candidate_peptides: usize,
+    /// Fraction of residues covered by matched peptides
+    pub coverage: f64,
+}
+
+impl PmfHit {
+    pub fn matched_peptides(&self) -> usize {
+        self.matches.len()
+    }
+}
+
+/// Ranks proteins by matched peptide count, then sequence coverage.
+pub fn search_peak_list(
+    index: &PeptideMassIndex,
+    proteins: &[SmallProtein],
+    peaks: &[f64],
+    options: &PmfOptions,
+) -> Vec<PmfHit> {
+    let mut by_protein: HashMap<usize, Vec<PeptideMatch>> = HashMap::new();
+
+    for &peak in peaks {
+        let mass = options.neutral_mass(peak);
+        for peptide in index.lookup(mass, options.tolerance_ppm) {
+            by_protein.entry(peptide.protein_index as usize).or_default().push(PeptideMatch {
+                peak,
+                peptide: *peptide,
+                error_ppm: (mass - peptide.mass) / peptide.mass * 1e6,
+            });
+        }
+    }
+
+    let mut hits: Vec<PmfHit> = by_protein.into_iter().map(|(protein_index, mut matches)| {
+        // A peptide hit by several close peaks counts once, with its closest peak
+        matches.sort_by(|a, b| (a.peptide.start, a.peptide.end).cmp(&(b.peptide.start, b.peptide.end))
+            .then(a.error_ppm.abs().total_cmp(&b.error_ppm.abs())));
+        matches.dedup_by_key(|found| (found.peptide.start, found.peptide.end));
+
+        let length = proteins[protein_index].aa_seq.trim_end_matches('*').len().max(1);
+        let mut covered = vec![false; length];
+        for found in &matches {
+            covered[found.peptide.start as usize..found.peptide.end as usize].fill(true);
+        }
+
+        PmfHit {
+            protein_index,
+            candidate_peptides: index.peptide_counts[protein_index] as usize,
+            coverage: covered.iter().filter(|&&residue| residue).count() as f64 / length as f64,
+            matches,
+        }
+    }).collect();
+
+    hits.sort_by(|a, b| b.matched_peptides().cmp(&a.matched_peptides())
+        .then(b.coverage.total_cmp(&a.coverage))
+        .then(a.protein_index.cmp(&b.protein_index)));
+    debug!("{} peaks matched peptides of {} proteins", peaks.len(), hits.len());
+    hits
+}
+
+/// Reads one mass per line; further columns such as intensities, blank lines
+/// and `#` comments are ignored.
+pub fn parse_peak_list<R: BufRead>(reader: R) -> Result<Vec<f64>, String> {
+    let mut peaks = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("line {}: {e}", i + 1))?;
+        let Some(mass) = line.split('#').next().and_then(|data| data.split_whitespace().next()) else {
+            continue;
+        };
+        let mass: f64 = mass.parse().map_err(|_| format!("line {}: '{mass}' is not a mass", i + 1))?;
+        if !(mass > 0.0 && mass.is_finite()) {
+            return Err(format!("line {}: mass must be positive", i + 1));
+        }
+        peaks.push(mass);
+    }
+    Ok(peaks)
+}
+
+pub fn read_peak_list(path: &Path) -> Result<Vec<f64>, Box<dyn Error>> {
+    let file = File::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let peaks = parse_peak_list(BufReader::new(file)).map_err(|e| format!("{}: {e}", path.display()))?;
+    if peaks.is_empty() {
+        return Err(format!("{}: no peaks", path.display()).into());
+    }
+    info!("Read {} peaks from {path:?}", peaks.len());
+    Ok(peaks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protein::parse_small_protein_dataset;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/smprot_sample.txt");
+
+    #[test]
+    fn test_peak_list_finds_digested_protein() {
+        let proteins = parse_small_protein_dataset(FIXTURE.as_bytes(), None).unwrap();
+        let options = PmfOptions::default();
+        let index = PeptideMassIndex::new(&proteins, &options);
+        assert!(index.built_for(&options) && !index.is_empty());
+
+        let target = 5;
+        let peptides = digest(&proteins[target].aa_seq, &DigestOptions { missed_cleavages: 0, ..options.digest });
+        assert!(peptides.len() >= 2, "fixture protein should digest into several peptides");
+        // [M+H]+ peaks a few ppm off, plus noise
+        let mut peak_list = String::from("# mass intensity\n");
+        for peptide in &peptides {
+            let mz = peptide.mass(MassType::Monoisotopic).unwrap() + PROTON_MASS;
+            peak_list.push_str(&format!("{:.5} 1200\n", mz * (1.0 + 3e-6)));
+        }
+        peak_list.push_str("\n4321.0\n");
+        let peaks = parse_peak_list(peak_list.as_bytes()).unwrap();
+
+        let hits = search_peak_list(&index, &proteins, &peaks, &options);
+        assert_eq!(hits[0].protein_index, target);
+        assert_eq!(hits[0].matched_peptides(), peptides.len());
+        assert!(hits[0].matches.iter().all(|found| found.error_ppm.abs() < 5.0));
+        assert!(parse_peak_list("12.5\nabc\n".as_bytes()).unwrap_err().starts_with("line 2"));
+    }
+}
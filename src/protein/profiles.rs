@@ -0,0 +1,165 @@
+//! Sliding-window profiles along a sequence, for plotting hydropathy, charge
+//! and GC content per position.
+use std::ops::Range;
+use crate::protein::properties::{PkaSet, kyte_doolittle, residue_charge};
+
+pub const DEFAULT_PROFILE_WINDOW: usize = 11;
+pub const MIN_PROFILE_WINDOW: usize = 3;
+pub const MAX_PROFILE_WINDOW: usize = 31;
+/// Kyte & Doolittle's mean hydropathy above which a 19-residue window
+/// suggests a membrane-spanning segment.
+pub const TM_HYDROPATHY_THRESHOLD: f64 = 1.6;
+/// pH of the charge profile
+pub const PROFILE_PH: f64 = 7.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProfileKind {
+    /// Kyte-Doolittle hydropathy of the protein
+    #[default]
+    Hydropathy,
+    /// Side-chain charge of the protein at `PROFILE_PH`
+    Charge,
+    /// GC content of the nucleotides, in percent
+    GcContent,
+}
+
+impl ProfileKind {
+    pub const ALL: [ProfileKind; 3] = [ProfileKind::Hydropathy, ProfileKind::Charge, ProfileKind::GcContent];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProfileKind::Hydropathy => "Hydropathy",
+            ProfileKind::Charge => "Charge",
+            ProfileKind::GcContent => "GC content",
+        }
+    }
+
+    pub fn next(self) -> ProfileKind {
+        let position = ProfileKind::ALL.iter().position(|kind| *kind == self).unwrap_or(0);
+        ProfileKind::ALL[(position + 1) % ProfileKind::ALL.len()]
+    }
+
+    /// Fixed plot range, so profiles of different sequences compare.
+    pub fn range(self) -> (f64, f64) {
+        match self {
+            ProfileKind::Hydropathy => (-4.5, 4.5),
+            ProfileKind::Charge => (-1.0, 1.0),
+            ProfileKind::GcContent => (0.0, 100.0),
+        }
+    }
+}
+
+/// Window means along a sequence. `values[i]` averages positions
+/// `i..i + window`, so it is centred on position `i + window / 2`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub kind: ProfileKind,
+    pub window: usize,
+    pub values: Vec<f64>,
+    /// Sequence ranges marked on the plot, such as predicted TM helices;
+    /// independent of `window`.
+    pub highlights: Vec<Range<usize>>,
+}
+
+impl Profile {
+    /// Residues without a hydropathy value, such as `X`, count as 0.
+    pub fn hydropathy(protein: &str, window: usize) -> Profile {
+        let values: Vec<f64> = residues(protein).map(|residue| kyte_doolittle(residue).unwrap_or(0.0)).collect();
+        Profile { kind: ProfileKind::Hydropathy, window, values: sliding_window_mean(&values, window), highlights: Vec::new() }
+    }
+
+    pub fn charge(protein: &str, window: usize, ph: f64, pka: &PkaSet) -> Profile {
+        let values: Vec<f64> = residues(protein).map(|residue| residue_charge(residue, ph, pka)).collect();
+        Profile { kind: ProfileKind::Charge, window, values: sliding_window_mean(&values, window), highlights: Vec::new() }
+    }
+
+    /// S (G or C) counts as GC; other ambiguity codes do not.
+    pub fn gc_content(nucleotides: &str, window: usize) -> Profile {
+        let values: Vec<f64> = nucleotides.trim().chars()
+            .map(|base| if matches!(base.to_ascii_uppercase(), 'G' | 'C' | 'S') { 100.0 } else { 0.0 })
+            .collect();
+        Profile { kind: ProfileKind::GcContent, window, values: sliding_window_mean(&values, window), highlights: Vec::new() }
+    }
+
+    pub fn with_highlights(mut self, highlights: Vec<Range<usize>>) -> Profile {
+        self.highlights = highlights;
+        self
+    }
+
+    /// Sequence coordinates, 0-based and end-exclusive, of the residues
+    /// covered by windows above `threshold`; overlapping windows merge.
+    pub fn regions_above(&self, threshold: f64) -> Vec<Range<usize>> {
+        let mut regions: Vec<Range<usize>> = Vec::new();
+        for (start, value) in self.values.iter().enumerate() {
+            if *value < threshold {
+                continue;
+            }
+            match regions.last_mut() {
+                Some(region) if start <= region.end => region.end = start + self.window,
+                _ => regions.push(start..start + self.window),
+            }
+        }
+        regions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+fn residues(protein: &str) -> impl Iterator<Item = char> + '_ {
+    protein.trim().trim_end_matches('*').chars()
+}
+
+/// Means of every `window` consecutive values; empty when there are fewer
+/// values than that.
+pub fn sliding_window_mean(values: &[f64], window: usize) -> Vec<f64> {
+    if window == 0 || values.len() < window {
+        return Vec::new();
+    }
+
+    let mut sum: f64 = values[..window].iter().sum();
+    let mut means = Vec::with_capacity(values.len() - window + 1);
+    means.push(sum / window as f64);
+    for i in window..values.len() {
+        sum += values[i] - values[i - window];
+        means.push(sum / window as f64);
+    }
+    means
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protein::PKA_BJELLQVIST;
+
+    #[test]
+    fn test_profiles_and_hydrophobic_regions() {
+        assert_eq!(sliding_window_mean(&[1.0, 2.0, 3.0, 6.0], 2), [1.5, 2.5, 4.5]);
+        assert!(sliding_window_mean(&[1.0], 3).is_empty());
+
+        // A leucine stretch between charged ends
+        let protein = format!("MKDE{}RKDE*", "L".repeat(12));
+        let hydropathy = Profile::hydropathy(&protein, 5);
+        assert_eq!(hydropathy.values.len(), protein.len() - 1 - 4);
+        // Windows reaching one charged residue into the stretch still average above the threshold
+        let regions = hydropathy.regions_above(TM_HYDROPATHY_THRESHOLD);
+        assert_eq!((regions.len(), regions[0].clone()), (1, 3..17));
+
+        let charge = Profile::charge("KKKDDD", 3, 7.0, &PKA_BJELLQVIST);
+        assert!(charge.values[0] > 0.9 && charge.values[3] < -0.9);
+        assert_eq!(Profile::gc_content("GCAT", 2).values, [100.0, 50.0, 0.0]);
+    }
+
+    #[test]
+    fn test_regions_merge_across_dips_shorter_than_the_window() {
+        let profile = Profile {
+            kind: ProfileKind::Hydropathy,
+            window: 3,
+            values: vec![2.0, 2.0, 0.0, 2.0, 0.0, 0.0, 0.0, 2.0],
+            highlights: Vec::new(),
+        };
+        // The window at 3 overlaps the run ending at 4; the one at 7 starts past 6
+        assert_eq!(profile.regions_above(1.0), [0..6, 7..10]);
+    }
+}
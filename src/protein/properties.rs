@@ -0,0 +1,329 @@
+//! ProtParam-style physicochemical properties of a protein sequence. Only the
+//! twenty standard residues count; stops, `X` and other letters are skipped.
+
+const STANDARD_RESIDUES: &str = "ACDEFGHIKLMNPQRSTVWY";
+
+/// Molar extinction at 280 nm in water, from Pace et al. (1995).
+const TRP_EXTINCTION: u32 = 5500;
+const TYR_EXTINCTION: u32 = 1490;
+const CYSTINE_EXTINCTION: u32 = 125;
+
+/// pKa values of the ionisable groups used for charge and pI.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PkaSet {
+    pub name: &'static str,
+    pub n_term: f64,
+    pub c_term: f64,
+    pub lys: f64,
+    pub arg: f64,
+    pub his: f64,
+    pub asp: f64,
+    pub glu: f64,
+    pub cys: f64,
+    pub tyr: f64,
+    /// Terminal pKa that depend on the terminal residue
+    pub n_term_by_residue: &'static [(char, f64)],
+    pub c_term_by_residue: &'static [(char, f64)],
+}
+
+/// Bjellqvist et al. (1993) values, as used by ExPASy ProtParam.
+pub const PKA_BJELLQVIST: PkaSet = PkaSet {
+    name: "Bjellqvist",
+    n_term: 7.5,
+    c_term: 3.55,
+    lys: 10.0,
+    arg: 12.0,
+    his: 5.98,
+    asp: 4.05,
+    glu: 4.45,
+    cys: 9.0,
+    tyr: 10.0,
+    n_term_by_residue: &[('A', 7.59), ('M', 7.0), ('S', 6.93), ('P', 8.36), ('T', 6.82), ('V', 7.44), ('E', 7.7)],
+    c_term_by_residue: &[('D', 4.55), ('E', 4.75)],
+};
+
+pub const PKA_EMBOSS: PkaSet = PkaSet {
+    name: "EMBOSS",
+    n_term: 8.6,
+    c_term: 3.6,
+    lys: 10.8,
+    arg: 12.5,
+    his: 6.5,
+    asp: 3.9,
+    glu: 4.1,
+    cys: 8.5,
+    tyr: 10.1,
+    n_term_by_residue: &[],
+    c_term_by_residue: &[],
+};
+
+pub const PKA_LEHNINGER: PkaSet = PkaSet {
+    name: "Lehninger",
+    n_term: 9.69,
+    c_term: 2.34,
+    lys: 10.5,
+    arg: 12.4,
+    his: 6.0,
+    asp: 3.86,
+    glu: 4.25,
+    cys: 8.33,
+    tyr: 10.0,
+    n_term_by_residue: &[],
+    c_term_by_residue: &[],
+};
+
+/// Kyte-Doolittle hydropathy of a residue.
+pub fn kyte_doolittle(residue: char) -> Option<f64> {
+    Some(match residue.to_ascii_uppercase() {
+        'A' => 1.8,
+        'R' => -4.5,
+        'N' => -3.5,
+        'D' => -3.5,
+        'C' => 2.5,
+        'Q' => -3.5,
+        'E' => -3.5,
+        'G' => -0.4,
+        'H' => -3.2,
+        'I' => 4.5,
+        'L' => 3.8,
+        'K' => -3.9,
+        'M' => 1.9,
+        'F' => 2.8,
+        'P' => -1.6,
+        'S' => -0.8,
+        'T' => -0.7,
+        'W' => -0.9,
+        'Y' => -1.3,
+        'V' => 4.2,
+        _ => return None,
+    })
+}
+
+/// The standard residues of `sequence`, uppercased.
+fn standard_residues(sequence: &str) -> Vec<char> {
+    sequence.chars()
+        .map(|c| c.to_ascii_uppercase())
+        .filter(|c| STANDARD_RESIDUES.contains(*c))
+        .collect()
+}
+
+fn count(residues: &[char], residue: char) -> usize {
+    residues.iter().filter(|&&c| c == residue).count()
+}
+
+fn positive_fraction(pka: f64, ph: f64) -> f64 {
+    1.0 / (10f64.powf(ph - pka) + 1.0)
+}
+
+fn negative_fraction(pka: f64, ph: f64) -> f64 {
+    1.0 / (10f64.powf(pka - ph) + 1.0)
+}
+
+fn net_charge_of(residues: &[char], ph: f64, pka: &PkaSet) -> f64 {
+    let (Some(&first), Some(&last)) = (residues.first(), residues.last()) else {
+        return 0.0;
+    };
+    let terminal = |overrides: &[(char, f64)], residue: char, default: f64| {
+        overrides.iter().find(|(c, _)| *c == residue).map_or(default, |(_, value)| *value)
+    };
+    let n_term = terminal(pka.n_term_by_residue, first, pka.n_term);
+    let c_term = terminal(pka.c_term_by_residue, last, pka.c_term);
+
+    let positive = positive_fraction(n_term, ph)
+        + [('K', pka.lys), ('R', pka.arg), ('H', pka.his)].iter()
+            .map(|&(residue, value)| count(residues, residue) as f64 * positive_fraction(value, ph))
+            .sum::<f64>();
+    let negative = negative_fraction(c_term, ph)
+        + [('D', pka.asp), ('E', pka.glu), ('C', pka.cys), ('Y', pka.tyr)].iter()
+            .map(|&(residue, value)| count(residues, residue) as f64 * negative_fraction(value, ph))
+            .sum::<f64>();
+    positive - negative
+}
+
+/// Henderson-Hasselbalch net charge of the protein at `ph`.
+pub fn net_charge_at_ph(sequence: &str, ph: f64, pka: &PkaSet) -> f64 {
+    net_charge_of(&standard_residues(sequence), ph, pka)
+}
+
+/// Side-chain charge of a single residue at `ph`; termini are not included.
+pub fn residue_charge(residue: char, ph: f64, pka: &PkaSet) -> f64 {
+    match residue.to_ascii_uppercase() {
+        'K' => positive_fraction(pka.lys, ph),
+        'R' => positive_fraction(pka.arg, ph),
+        'H' => positive_fraction(pka.his, ph),
+        'D' => -negative_fraction(pka.asp, ph),
+        'E' => -negative_fraction(pka.glu, ph),
+        'C' => -negative_fraction(pka.cys, ph),
+        'Y' => -negative_fraction(pka.tyr, ph),
+        _ => 0.0,
+    }
+}
+
+/// The pH where the net charge is zero, found by bisection to within 0.0001.
+pub fn isoelectric_point(sequence: &str, pka: &PkaSet) -> Option<f64> {
+    let residues = standard_residues(sequence);
+    if residues.is_empty() {
+        return None;
+    }
+
+    let (mut low, mut high) = (0.0, 14.0);
+    while high - low > 1e-4 {
+        let ph = (low + high) / 2.0;
+        if net_charge_of(&residues, ph, pka) > 0.0 {
+            low = ph;
+        } else {
+            high = ph;
+        }
+    }
+    Some((low + high) / 2.0)
+}
+
+/// Grand average of hydropathy: the mean Kyte-Doolittle value.
+pub fn gravy(sequence: &str) -> Option<f64> {
+    let residues = standard_residues(sequence);
+    if residues.is_empty() {
+        return None;
+    }
+    Some(residues.iter().filter_map(|&c| kyte_doolittle(c)).sum::<f64>() / residues.len() as f64)
+}
+
+/// Guruprasad et al. (1990) instability index; above 40 the protein is
+/// predicted to be unstable in vitro.
+pub fn instability_index(sequence: &str) -> Option<f64> {
+    let residues = standard_residues(sequence);
+    if residues.len() < 2 {
+        return None;
+    }
+    let total: f64 = residues.windows(2).map(|pair| dipeptide_instability_weight(pair[0], pair[1])).sum();
+    Some(10.0 / residues.len() as f64 * total)
+}
+
+/// Ikai (1980) aliphatic index, the relative volume of A, V, I and L side chains.
+pub fn aliphatic_index(sequence: &str) -> Option<f64> {
+    let residues = standard_residues(sequence);
+    if residues.is_empty() {
+        return None;
+    }
+    let mole_percent = |residue| count(&residues, residue) as f64 / residues.len() as f64 * 100.0;
+    Some(mole_percent('A') + 2.9 * mole_percent('V') + 3.9 * (mole_percent('I') + mole_percent('L')))
+}
+
+/// Molar extinction coefficients at 280 nm, in M⁻¹ cm⁻¹.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtinctionCoefficient {
+    /// All cysteines reduced
+    pub reduced: u32,
+    /// All cysteine pairs forming cystines
+    pub cystines: u32,
+}
+
+pub fn extinction_coefficient(sequence: &str) -> ExtinctionCoefficient {
+    let residues = standard_residues(sequence);
+    let reduced = count(&residues, 'W') as u32 * TRP_EXTINCTION + count(&residues, 'Y') as u32 * TYR_EXTINCTION;
+    ExtinctionCoefficient { reduced, cystines: reduced + (count(&residues, 'C') / 2) as u32 * CYSTINE_EXTINCTION }
+}
+
+/// Everything ProtParam reports besides the mass, for one sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProteinProperties {
+    /// Standard residues counted
+    pub length: usize,
+    pub isoelectric_point: Option<f64>,
+    pub net_charge: f64,
+    /// pH `net_charge` was computed at
+    pub ph: f64,
+    pub gravy: Option<f64>,
+    pub instability_index: Option<f64>,
+    pub aliphatic_index: Option<f64>,
+    pub extinction: ExtinctionCoefficient,
+}
+
+impl ProteinProperties {
+    pub fn new(sequence: &str, ph: f64, pka: &PkaSet) -> Self {
+        ProteinProperties {
+            length: standard_residues(sequence).len(),
+            isoelectric_point: isoelectric_point(sequence, pka),
+            net_charge: net_charge_at_ph(sequence, ph, pka),
+            ph,
+            gravy: gravy(sequence),
+            instability_index: instability_index(sequence),
+            aliphatic_index: aliphatic_index(sequence),
+            extinction: extinction_coefficient(sequence),
+        }
+    }
+
+    pub fn is_unstable(&self) -> bool {
+        self.instability_index.is_some_and(|index| index > 40.0)
+    }
+}
+
+/// Dipeptide instability weight values (DIWV) of Guruprasad et al. (1990),
+/// for the residue `first` followed by `second`.
+fn dipeptide_instability_weight(first: char, second: char) -> f64 {
+    const ORDER: &str = "ACDEFGHIKLMNPQRSTVWY";
+    #[rustfmt::skip]
+    const DIWV: [[f64; 20]; 20] = [
+        // A      C      D      E      F      G      H      I      K      L      M      N      P      Q      R      S      T      V      W      Y
+        [  1.0,  44.94, -7.49,  1.0,   1.0,   1.0,  -7.49,  1.0,   1.0,   1.0,   1.0,   1.0,  20.26,  1.0,   1.0,   1.0,   1.0,   1.0,   1.0,   1.0 ], // A
+        [  1.0,   1.0,  20.26,  1.0,   1.0,   1.0,  33.60,  1.0,   1.0,  20.26, 33.60,  1.0,  20.26, -6.54,  1.0,   1.0,  33.60, -6.54, 24.68,  1.0 ], // C
+        [  1.0,   1.0,   1.0,   1.0,  -6.54,  1.0,   1.0,   1.0,  -7.49,  1.0,   1.0,   1.0,   1.0,   1.0,  -6.54, 20.26,-14.03,  1.0,   1.0,   1.0 ], // D
+        [  1.0,  44.94, 20.26, 33.60,  1.0,   1.0,  -6.54, 20.26,  1.0,   1.0,   1.0,   1.0,  20.26, 20.26,  1.0,  20.26,  1.0,   1.0, -14.03,  1.0 ], // E
+        [  1.0,   1.0,  13.34,  1.0,   1.0,   1.0,   1.0,   1.0, -14.03,  1.0,   1.0,   1.0,  20.26,  1.0,   1.0,   1.0,   1.0,   1.0,   1.0,  33.601], // F
+        [ -7.49,  1.0,   1.0,  -6.54,  1.0,  13.34,  1.0,  -7.49, -7.49,  1.0,   1.0,  -7.49,  1.0,   1.0,   1.0,   1.0,  -7.49,  1.0,  13.34, -7.49], // G
+        [  1.0,   1.0,   1.0,   1.0,  -9.37, -9.37,  1.0,  44.94, 24.68,  1.0,   1.0,  24.68, -1.88,  1.0,   1.0,   1.0,  -6.54,  1.0,  -1.88, 44.94], // H
+        [  1.0,   1.0,   1.0,  44.94,  1.0,   1.0,  13.34,  1.0,  -7.49, 20.26,  1.0,   1.0,  -1.88,  1.0,   1.0,   1.0,   1.0,  -7.49,  1.0,   1.0 ], // I
+        [  1.0,   1.0,   1.0,   1.0,   1.0,  -7.49,  1.0,  -7.49,  1.0,  -7.49, 33.60,  1.0,  -6.54, 24.64, 33.60,  1.0,   1.0,  -7.49,  1.0,   1.0 ], // K
+        [  1.0,   1.0,   1.0,   1.0,   1.0,   1.0,   1.0,   1.0,  -7.49,  1.0,   1.0,   1.0,  20.26, 33.60, 20.26,  1.0,   1.0,   1.0,  24.68,  1.0 ], // L
+        [ 13.34,  1.0,   1.0,   1.0,   1.0,   1.0,  58.28,  1.0,   1.0,   1.0,  -1.88,  1.0,  44.94, -6.54, -6.54, 44.94, -1.88,  1.0,   1.0,  24.68], // M
+        [  1.0,  -1.88,  1.0,   1.0, -14.03,-14.03,  1.0,  44.94, 24.68,  1.0,   1.0,   1.0,  -1.88, -6.54,  1.0,   1.0,  -7.49,  1.0,  -9.37,  1.0 ], // N
+        [ 20.26, -6.54, -6.54, 18.38, 20.26,  1.0,   1.0,   1.0,   1.0,   1.0,  -6.54,  1.0,  20.26, 20.26, -6.54, 20.26,  1.0,  20.26, -1.88,  1.0 ], // P
+        [  1.0,  -6.54, 20.26, 20.26, -6.54,  1.0,   1.0,   1.0,   1.0,   1.0,   1.0,   1.0,  20.26, 20.26,  1.0,  44.94,  1.0,  -6.54,  1.0,  -6.54], // Q
+        [  1.0,   1.0,   1.0,   1.0,   1.0,  -7.49, 20.26,  1.0,   1.0,   1.0,   1.0,  13.34, 20.26, 20.26, 58.28, 44.94,  1.0,   1.0,  58.28, -6.54], // R
+        [  1.0,  33.60,  1.0,  20.26,  1.0,   1.0,   1.0,   1.0,   1.0,   1.0,   1.0,   1.0,  44.94, 20.26, 20.26, 20.26,  1.0,   1.0,   1.0,   1.0 ], // S
+        [  1.0,   1.0,   1.0,  20.26, 13.34, -7.49,  1.0,   1.0,   1.0,   1.0,   1.0, -14.03,  1.0,  -6.54,  1.0,   1.0,   1.0,   1.0, -14.03,  1.0 ], // T
+        [  1.0,   1.0, -14.03,  1.0,   1.0,  -7.49,  1.0,   1.0,  -1.88,  1.0,   1.0,   1.0,  20.26,  1.0,   1.0,   1.0,  -7.49,  1.0,   1.0,  -6.54], // V
+        [-14.03,  1.0,   1.0,   1.0,   1.0,  -9.37, 24.68,  1.0,   1.0,  13.34, 24.68, 13.34,  1.0,   1.0,   1.0,   1.0, -14.03, -7.49,  1.0,   1.0 ], // W
+        [ 24.68,  1.0,  24.68, -6.54,  1.0,  -7.49, 13.34,  1.0,   1.0,   1.0,  44.94,  1.0,  13.34,  1.0, -15.91,  1.0,  -7.49,  1.0,  -9.37, 13.34], // Y
+    ];
+
+    match (ORDER.find(first), ORDER.find(second)) {
+        (Some(row), Some(column)) => DIWV[row][column],
+        _ => 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Human ubiquitin, with the values ExPASy ProtParam reports for it
+    const UBIQUITIN: &str = "MQIFVKTLTGKTITLEVEPSDTIENVKAKIQDKEGIPPDQQRLIFAGKQLEDGRTLSDYNIQKESTLHLVLRLRGG";
+
+    #[test]
+    fn test_properties_match_protparam_reference() {
+        let properties = ProteinProperties::new(UBIQUITIN, 7.0, &PKA_BJELLQVIST);
+        assert_eq!(properties.length, 76);
+        assert!((properties.isoelectric_point.unwrap() - 6.56).abs() < 0.005);
+        assert!((properties.gravy.unwrap() - -0.489).abs() < 0.0005);
+        assert!((properties.instability_index.unwrap() - 36.06).abs() < 0.005);
+        assert!((properties.aliphatic_index.unwrap() - 100.0).abs() < 0.005);
+        assert!(!properties.is_unstable());
+        assert_eq!(properties.extinction, ExtinctionCoefficient { reduced: 1490, cystines: 1490 });
+
+        let extinction = extinction_coefficient("WYYCCC");
+        assert_eq!(extinction, ExtinctionCoefficient { reduced: 5500 + 2 * 1490, cystines: 5500 + 2 * 1490 + 125 });
+        // Two groups only: the pI sits halfway between the terminal pKa values
+        assert!((isoelectric_point("G", &PKA_BJELLQVIST).unwrap() - (7.5 + 3.55) / 2.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_net_charge_goes_negative_for_acidic_peptides() {
+        let charge = net_charge_at_ph("DDEEK*", 7.0, &PKA_EMBOSS);
+        assert!(charge < -2.5 && charge > -3.5, "charge {charge}");
+        assert!(net_charge_at_ph("KKRR", 7.0, &PKA_LEHNINGER) > 3.5);
+
+        let pi = isoelectric_point("DDEEK", &PKA_EMBOSS).unwrap();
+        assert!(net_charge_at_ph("DDEEK", pi, &PKA_EMBOSS).abs() < 1e-3);
+        assert_eq!(isoelectric_point("**", &PKA_EMBOSS), None);
+    }
+}
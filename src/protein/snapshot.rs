@@ -0,0 +1,458 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, UNIX_EPOCH};
+use memmap2::Mmap;
+use sha2::{Digest, Sha256};
+use log::{info, warn, debug};
+use crate::protein::{
+    DatasetProgress, DatasetSource, ProteinIndex, SmallProtein, SourceLocation, ParseReport, RejectedRow,
+    GenomicInterval, NucleotideSequence, ProteinSequence, Strand,
+    load_dataset_sources, recorded_download, part_path,
+};
+use crate::protein::dataset::get_data_dir;
+
+const SNAPSHOT_MAGIC: &[u8; 8] = b"RZSNAPSH";
+/// Bump whenever the layout written by `write_snapshot` (or `SmallProtein`) changes.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 2;
+
+/// Proteins and their search index, either parsed from the sources or read
+/// back from a snapshot.
+#[derive(Debug, Clone)]
+pub struct LoadedDataset {
+    pub proteins: Vec<SmallProtein>,
+    pub index: ProteinIndex,
+    pub report: ParseReport,
+    pub from_snapshot: bool,
+}
+
+/// Identifies the content a snapshot was built from without reading it: one
+/// entry per source, holding its name and a stamp of its raw data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFingerprint {
+    pub name: String,
+    pub stamp: String,
+}
+
+pub fn snapshot_path(sources: &[DatasetSource]) -> Result<PathBuf, Box<dyn Error>> {
+    let key = sources.iter().map(|source| source.cache_key.as_str()).collect::<Vec<_>>().join("+");
+    Ok(get_data_dir()?.join(format!("{key}.snapshot")))
+}
+
+/// Loads the sources through the snapshot in the data directory.
+pub fn load_dataset(
+    sources: &[DatasetSource],
+    progress_callback: Option<&dyn Fn(DatasetProgress)>
+) -> Result<LoadedDataset, Box<dyn Error>> {
+    load_dataset_with_snapshot(sources, &snapshot_path(sources)?, progress_callback)
+}
+
+/// Reads `snapshot` when it matches the current format and every source's
+/// fingerprint; otherwise parses the sources and rewrites the snapshot.
+pub fn load_dataset_with_snapshot(
+    sources: &[DatasetSource],
+    snapshot: &Path,
+    progress_callback: Option<&dyn Fn(DatasetProgress)>
+) -> Result<LoadedDataset, Box<dyn Error>> {
+    let fingerprints = fingerprint_sources(sources);
+    if let Some(fingerprints) = &fingerprints {
+        if snapshot.exists() {
+            if let Some(callback) = progress_callback {
+                callback(DatasetProgress::ReadingSnapshot);
+            }
+
+            let started = Instant::now();
+            match read_snapshot(snapshot, fingerprints) {
+                Ok(Some(dataset)) => {
+                    info!("Loaded {} proteins from snapshot {snapshot:?} in {:?}", dataset.proteins.len(), started.elapsed());
+                    if let Some(callback) = progress_callback {
+                        callback(DatasetProgress::Complete);
+                    }
+                    return Ok(dataset);
+                },
+                Ok(None) => info!("Snapshot {snapshot:?} is stale, rebuilding"),
+                Err(e) => warn!("Failed to read snapshot {snapshot:?} ({e}), rebuilding"),
+            }
+        }
+    }
+
+    let parsed = load_dataset_sources(sources, progress_callback)?;
+    let (proteins, report) = (parsed.proteins, parsed.report);
+    let index = ProteinIndex::new(&proteins);
+
+    // Remote sources only have a fingerprint once their download is on disk
+    match fingerprints.or_else(|| fingerprint_sources(sources)) {
+        Some(fingerprints) => match write_snapshot(snapshot, &fingerprints, &proteins, &report, &index) {
+            Ok(()) => info!("Wrote snapshot {snapshot:?}"),
+            Err(e) => warn!("Failed to write snapshot {snapshot:?}: {e}"),
+        },
+        None => debug!("Not all sources could be fingerprinted, skipping snapshot"),
+    }
+
+    Ok(LoadedDataset { proteins, index, report, from_snapshot: false })
+}
+
+pub fn fingerprint_sources(sources: &[DatasetSource]) -> Option<Vec<SourceFingerprint>> {
+    sources.iter()
+        .map(|source| Some(SourceFingerprint { name: source.name.clone(), stamp: source_stamp(source)? }))
+        .collect()
+}
+
+/// Files are stamped by size and modification time, and verified downloads
+/// also by the SHA-256 recorded in their sidecar, so nothing is hashed here.
+fn source_stamp(source: &DatasetSource) -> Option<String> {
+    match &source.location {
+        SourceLocation::Remote(_) => {
+            let archive = source.cache_file("txt.gz").ok()?;
+            let sha256 = recorded_download(&archive, &source.integrity)?.sha256?;
+            Some(format!("sha256={sha256};{}", file_stamp(&archive)?))
+        },
+        SourceLocation::LocalFile(path) => file_stamp(path),
+        SourceLocation::InMemory(data) => {
+            Some(Sha256::digest(data).iter().map(|byte| format!("{byte:02x}")).collect())
+        },
+    }
+}
+
+fn file_stamp(path: &Path) -> Option<String> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(format!("size={};mtime={}", metadata.len(), modified.as_nanos()))
+}
+
+pub fn write_snapshot(
+    path: &Path,
+    fingerprints: &[SourceFingerprint],
+    proteins: &[SmallProtein],
+    report: &ParseReport,
+    index: &ProteinIndex
+) -> Result<(), Box<dyn Error>> {
+    let part_file = part_path(path);
+    let mut writer = SnapshotWriter::new(BufWriter::new(File::create(&part_file)?));
+
+    writer.bytes(SNAPSHOT_MAGIC)?;
+    writer.u32(SNAPSHOT_FORMAT_VERSION)?;
+
+    writer.u32(fingerprints.len() as u32)?;
+    for fingerprint in fingerprints {
+        writer.str(&fingerprint.name)?;
+        writer.str(&fingerprint.stamp)?;
+    }
+
+    writer.u64(proteins.len() as u64)?;
+    for protein in proteins {
+        writer.str(&protein.species)?;
+        writer.str(&protein.id)?;
+        writer.str(&protein.rna_seq)?;
+        writer.str(&protein.aa_seq)?;
+        writer.u64(protein.length as u64)?;
+        writer.str(&protein.chromosome)?;
+        writer.u64(protein.start as u64)?;
+        writer.u64(protein.stop as u64)?;
+        writer.u8(protein.strand as u8)?;
+        writer.u32(protein.blocks.len() as u32)?;
+        for block in &protein.blocks {
+            writer.u64(block.start as u64)?;
+            writer.u64(block.end as u64)?;
+        }
+        // Start codons are always three bases, so empty stands for missing
+        writer.str(protein.start_codon.as_deref().unwrap_or(""))?;
+        writer.u8(protein.phylo_csf_mean.is_some() as u8)?;
+        writer.f64(protein.phylo_csf_mean.unwrap_or_default())?;
+        writer.str(&protein.source)?;
+    }
+
+    writer.u64(report.rows_read as u64)?;
+    writer.u32(report.rejected.len() as u32)?;
+    for row in &report.rejected {
+        writer.str(&row.source)?;
+        writer.u64(row.line as u64)?;
+        writer.str(&row.field)?;
+        writer.str(&row.reason)?;
+    }
+
+    index.write_snapshot(&mut writer)?;
+    writer.finish()?;
+
+    fs::rename(&part_file, path)?;
+    Ok(())
+}
+
+/// Returns `Ok(None)` when the snapshot was written by another format version
+/// or from different source data.
+pub fn read_snapshot(
+    path: &Path,
+    fingerprints: &[SourceFingerprint]
+) -> Result<Option<LoadedDataset>, Box<dyn Error>> {
+    let file = File::open(path)?;
+    // SAFETY: the snapshot is only ever replaced by renaming a new file over it,
+    // never modified in place, so the mapped pages stay valid while we decode.
+    let mmap = unsafe { Mmap::map(&file)? };
+    let mut reader = SnapshotReader::new(&mmap);
+
+    if reader.bytes(SNAPSHOT_MAGIC.len())? != SNAPSHOT_MAGIC {
+        return Err("not a ribozap snapshot".into());
+    }
+
+    let version = reader.u32()?;
+    if version != SNAPSHOT_FORMAT_VERSION {
+        debug!("Snapshot format {version} does not match current {SNAPSHOT_FORMAT_VERSION}");
+        return Ok(None);
+    }
+
+    let source_count = reader.u32()? as usize;
+    let mut stored = Vec::with_capacity(source_count);
+    for _ in 0..source_count {
+        stored.push(SourceFingerprint { name: reader.string()?, stamp: reader.string()? });
+    }
+    if stored != fingerprints {
+        debug!("Snapshot sources {stored:?} do not match {fingerprints:?}");
+        return Ok(None);
+    }
+
+    let protein_count = reader.u64()? as usize;
+    let mut proteins = Vec::with_capacity(protein_count.min(reader.remaining()));
+    for _ in 0..protein_count {
+        proteins.push(read_protein(&mut reader)?);
+    }
+
+    let mut report = ParseReport { rows_read: reader.u64()? as usize, rejected: Vec::new() };
+    for _ in 0..reader.u32()? {
+        report.rejected.push(RejectedRow {
+            source: reader.string()?,
+            line: reader.u64()? as usize,
+            field: reader.string()?,
+            reason: reader.string()?,
+        });
+    }
+
+    let index = ProteinIndex::read_snapshot(&mut reader)?;
+    if index.protein_count() != proteins.len() {
+        return Err("snapshot index does not match its proteins".into());
+    }
+
+    Ok(Some(LoadedDataset { proteins, index, report, from_snapshot: true }))
+}
+
+fn read_protein(reader: &mut SnapshotReader) -> Result<SmallProtein, Box<dyn Error>> {
+    let species = reader.string()?;
+    let id = reader.string()?;
+    let rna_seq = NucleotideSequence::from_validated(reader.string()?);
+    let aa_seq = ProteinSequence::from_validated(reader.string()?);
+    let length = reader.u64()? as usize;
+    let chromosome = reader.string()?;
+    let start = reader.u64()? as usize;
+    let stop = reader.u64()? as usize;
+    let strand = match reader.u8()? {
+        0 => Strand::Forward,
+        1 => Strand::Reverse,
+        other => return Err(format!("invalid strand tag {other} in snapshot").into()),
+    };
+
+    let block_count = reader.u32()? as usize;
+    let mut blocks = Vec::with_capacity(block_count.min(reader.remaining()));
+    for _ in 0..block_count {
+        blocks.push(GenomicInterval { start: reader.u64()? as usize, end: reader.u64()? as usize });
+    }
+
+    let start_codon = Some(reader.string()?)
+        .filter(|codon| !codon.is_empty())
+        .map(NucleotideSequence::from_validated);
+    let has_phylo_csf = reader.u8()? != 0;
+    let phylo_csf_value = reader.f64()?;
+    let phylo_csf_mean = has_phylo_csf.then_some(phylo_csf_value);
+
+    Ok(SmallProtein {
+        species,
+        id,
+        rna_seq,
+        aa_seq,
+        length,
+        chromosome,
+        start,
+        stop,
+        strand,
+        blocks,
+        start_codon,
+        phylo_csf_mean,
+        source: reader.string()?,
+    })
+}
+
+/// Little-endian encoder for snapshot files.
+pub struct SnapshotWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    pub fn new(inner: W) -> SnapshotWriter<W> {
+        SnapshotWriter { inner }
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.inner.write_all(bytes)
+    }
+
+    pub fn u8(&mut self, value: u8) -> std::io::Result<()> {
+        self.bytes(&[value])
+    }
+
+    pub fn u32(&mut self, value: u32) -> std::io::Result<()> {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn u64(&mut self, value: u64) -> std::io::Result<()> {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn f64(&mut self, value: f64) -> std::io::Result<()> {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn str(&mut self, value: &str) -> std::io::Result<()> {
+        self.u32(value.len() as u32)?;
+        self.bytes(value.as_bytes())
+    }
+
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Bounds-checked decoder over a snapshot mapped into memory.
+pub struct SnapshotReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> SnapshotReader<'a> {
+    pub fn new(data: &'a [u8]) -> SnapshotReader<'a> {
+        SnapshotReader { data, position: 0 }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.position
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        if len > self.remaining() {
+            return Err(format!("snapshot truncated at byte {}", self.position).into());
+        }
+        let bytes = &self.data[self.position..self.position + len];
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Box<dyn Error>> {
+        Ok(self.bytes(N)?.try_into()?)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, Box<dyn Error>> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, Box<dyn Error>> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    pub fn f64(&mut self) -> Result<f64, Box<dyn Error>> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    pub fn string(&mut self) -> Result<String, Box<dyn Error>> {
+        let len = self.u32()? as usize;
+        Ok(std::str::from_utf8(self.bytes(len)?)?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/smprot_sample.txt");
+
+    fn fixture_source(data: &str) -> Vec<DatasetSource> {
+        vec![DatasetSource::in_memory("fixture", data.as_bytes().to_vec())]
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = dir.path().join("fixture.snapshot");
+        let sources = fixture_source(&format!("{FIXTURE}truncated\trow\n"));
+
+        let parsed = load_dataset_with_snapshot(&sources, &snapshot, None).unwrap();
+        assert_eq!(parsed.report.rejected.len(), 1);
+        assert!(!parsed.from_snapshot);
+        assert!(snapshot.exists());
+
+        let cached = load_dataset_with_snapshot(&sources, &snapshot, None).unwrap();
+        assert!(cached.from_snapshot);
+        assert_eq!(cached.proteins.len(), parsed.proteins.len());
+        for (a, b) in cached.proteins.iter().zip(&parsed.proteins) {
+            assert_eq!((&a.id, &a.rna_seq, &a.aa_seq, &a.source), (&b.id, &b.rna_seq, &b.aa_seq, &b.source));
+            assert_eq!((a.strand, &a.blocks, &a.start_codon), (b.strand, &b.blocks, &b.start_codon));
+            assert_eq!(a.phylo_csf_mean.map(f64::to_bits), b.phylo_csf_mean.map(f64::to_bits));
+        }
+        assert_eq!(cached.report, parsed.report);
+
+        let query = &parsed.proteins[5].rna_seq[10..40];
+        assert_eq!(cached.index.candidates(query, 5), parsed.index.candidates(query, 5));
+    }
+
+    #[test]
+    fn test_snapshot_rebuilt_when_source_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = dir.path().join("fixture.snapshot");
+
+        load_dataset_with_snapshot(&fixture_source(FIXTURE), &snapshot, None).unwrap();
+
+        let fewer_rows: String = FIXTURE.lines().take(11).map(|line| format!("{line}\n")).collect();
+        let reloaded = load_dataset_with_snapshot(&fixture_source(&fewer_rows), &snapshot, None).unwrap();
+        assert!(!reloaded.from_snapshot);
+        assert_eq!(reloaded.proteins.len(), 10);
+    }
+
+    #[test]
+    fn test_local_file_snapshot_follows_size_and_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = dir.path().join("local.snapshot");
+        let path = dir.path().join("local.txt");
+        fs::write(&path, FIXTURE).unwrap();
+        let sources = vec![DatasetSource::local_file("local", &path)];
+
+        assert!(!load_dataset_with_snapshot(&sources, &snapshot, None).unwrap().from_snapshot);
+        assert!(load_dataset_with_snapshot(&sources, &snapshot, None).unwrap().from_snapshot);
+
+        let fewer_rows: String = FIXTURE.lines().take(11).map(|line| format!("{line}\n")).collect();
+        fs::write(&path, fewer_rows).unwrap();
+        let reloaded = load_dataset_with_snapshot(&sources, &snapshot, None).unwrap();
+        assert!(!reloaded.from_snapshot);
+        assert_eq!(reloaded.proteins.len(), 10);
+    }
+
+    #[test]
+    fn test_snapshot_rebuilt_when_format_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = dir.path().join("fixture.snapshot");
+        let sources = fixture_source(FIXTURE);
+
+        load_dataset_with_snapshot(&sources, &snapshot, None).unwrap();
+
+        let mut bytes = fs::read(&snapshot).unwrap();
+        bytes[SNAPSHOT_MAGIC.len()..SNAPSHOT_MAGIC.len() + 4].copy_from_slice(&(SNAPSHOT_FORMAT_VERSION + 1).to_le_bytes());
+        fs::write(&snapshot, &bytes).unwrap();
+        assert!(!load_dataset_with_snapshot(&sources, &snapshot, None).unwrap().from_snapshot);
+
+        // Truncated snapshots are rebuilt rather than failing the load
+        fs::write(&snapshot, &bytes[..bytes.len() / 2]).unwrap();
+        assert!(!load_dataset_with_snapshot(&sources, &snapshot, None).unwrap().from_snapshot);
+        assert!(load_dataset_with_snapshot(&sources, &snapshot, None).unwrap().from_snapshot);
+    }
+}
@@ -0,0 +1,289 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use log::{info, debug};
+use sha2::{Digest, Sha256};
+use crate::protein::dataset::get_data_dir;
+use crate::protein::download::{IntegrityCheck, recorded_download};
+
+//noinspection HttpUrlsUsage
+pub const SMPROT_LITERATURE_URL: &str = "http://bigdata.ibp.ac.cn/SmProt/datadownload/SmProt2_LiteratureMining.txt.gz";
+pub const DATASETS_CONFIG_FILE: &str = "datasets.conf";
+
+#[derive(Debug, Clone)]
+pub enum SourceLocation {
+    Remote(String),
+    LocalFile(PathBuf),
+    InMemory(Arc<[u8]>),
+}
+
+/// Where a SmProt-format dataset comes from. `name` tags every protein loaded
+/// from the source, `cache_key` names its files in the data directory and
+/// `integrity` is checked against remote downloads.
+#[derive(Debug, Clone)]
+pub struct DatasetSource {
+    pub name: String,
+    pub cache_key: String,
+    pub location: SourceLocation,
+    pub integrity: IntegrityCheck,
+}
+
+impl DatasetSource {
+    pub fn remote(name: &str, url: &str) -> DatasetSource {
+        Self::with_location(name, SourceLocation::Remote(url.to_string()))
+    }
+
+    /// A gzip or plain-text file on disk; compression is detected from the content.
+    pub fn local_file(name: &str, path: impl Into<PathBuf>) -> DatasetSource {
+        Self::with_location(name, SourceLocation::LocalFile(path.into()))
+    }
+
+    pub fn in_memory(name: &str, data: impl Into<Arc<[u8]>>) -> DatasetSource {
+        Self::with_location(name, SourceLocation::InMemory(data.into()))
+    }
+
+    /// The cache key also hashes the URL or path, so sources whose names
+    /// sanitize alike do not share cache files.
+    fn with_location(name: &str, location: SourceLocation) -> DatasetSource {
+        let cache_key = match &location {
+            SourceLocation::Remote(url) => format!("{}-{}", sanitize_cache_key(name), short_hash(url)),
+            SourceLocation::LocalFile(path) => format!("{}-{}", sanitize_cache_key(name), short_hash(&path.to_string_lossy())),
+            SourceLocation::InMemory(_) => sanitize_cache_key(name),
+        };
+        DatasetSource {
+            name: name.to_string(),
+            cache_key,
+            location,
+            integrity: IntegrityCheck::default(),
+        }
+    }
+
+    pub fn with_cache_key(mut self, cache_key: &str) -> DatasetSource {
+        self.cache_key = sanitize_cache_key(cache_key);
+        self
+    }
+
+    pub fn with_sha256(mut self, sha256: &str) -> DatasetSource {
+        self.integrity.sha256 = Some(sha256.to_lowercase());
+        self
+    }
+
+    pub fn with_expected_size(mut self, size: u64) -> DatasetSource {
+        self.integrity.size = Some(size);
+        self
+    }
+
+    pub fn cache_file(&self, extension: &str) -> Result<PathBuf, Box<dyn Error>> {
+        Ok(get_data_dir()?.join(format!("{}.{extension}", self.cache_key)))
+    }
+
+    /// True when the source's data is on disk, so it can be loaded without
+    /// touching the network. Downloads are checked against their sidecar only;
+    /// the loader re-fetches one that then fails to hash or parse.
+    pub fn is_available_offline(&self) -> bool {
+        match &self.location {
+            SourceLocation::Remote(_) => self.cache_file("txt.gz").is_ok_and(|path| recorded_download(&path, &self.integrity).is_some()),
+            SourceLocation::LocalFile(path) => path.exists(),
+            SourceLocation::InMemory(_) => true,
+        }
+    }
+}
+
+impl Default for DatasetSource {
+    fn default() -> Self {
+        // Keeps the cache file name used before sources were configurable
+        DatasetSource::remote("SmProt2 Literature", SMPROT_LITERATURE_URL).with_cache_key("small_protein_dataset")
+    }
+}
+
+impl fmt::Display for DatasetSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            SourceLocation::Remote(url) => write!(f, "{}={url}", self.name),
+            SourceLocation::LocalFile(path) => write!(f, "{}={}", self.name, path.display()),
+            SourceLocation::InMemory(data) => write!(f, "{} ({} bytes in memory)", self.name, data.len()),
+        }
+    }
+}
+
+/// Parses `[NAME=]LOCATION[;sha256=HEX][;size=BYTES]`, where LOCATION is an
+/// http(s) URL or a file path. Without a name, the file name minus its
+/// extensions is used.
+impl FromStr for DatasetSource {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut parts = spec.trim().split(';');
+        let spec = parts.next().unwrap_or("").trim();
+        let (name, location) = match spec.split_once('=') {
+            Some((name, location)) if !name.contains(['/', ':', '\\']) => (Some(name.trim()), location.trim()),
+            _ => (None, spec),
+        };
+
+        if location.is_empty() {
+            return Err(format!("dataset source '{spec}' has no location"));
+        }
+
+        let name = match name {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => default_source_name(location),
+        };
+
+        let mut source = if location.starts_with("http://") || location.starts_with("https://") {
+            DatasetSource::remote(&name, location)
+        } else {
+            DatasetSource::local_file(&name, location)
+        };
+
+        for option in parts.map(str::trim).filter(|option| !option.is_empty()) {
+            source = match option.split_once('=') {
+                Some(("sha256", hex)) if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) => source.with_sha256(hex),
+                Some(("size", bytes)) => source.with_expected_size(
+                    bytes.parse().map_err(|_| format!("invalid size '{bytes}' in dataset source '{spec}'"))?,
+                ),
+                _ => return Err(format!("unknown option '{option}' in dataset source '{spec}'")),
+            };
+        }
+
+        Ok(source)
+    }
+}
+
+pub fn get_config_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let config_dir = dirs::config_dir()
+        .ok_or("Could not determine config directory")?
+        .join("ribozap");
+
+    debug!("Config directory path: {config_dir:?}");
+    Ok(config_dir)
+}
+
+/// Reads dataset sources from `datasets.conf` in the config directory, one
+/// source spec per line, `#` starting a comment. Returns `None` when the file
+/// does not exist.
+pub fn load_configured_sources() -> Result<Option<Vec<DatasetSource>>, Box<dyn Error>> {
+    let config_file = get_config_dir()?.join(DATASETS_CONFIG_FILE);
+    if !config_file.exists() {
+        debug!("No dataset config at {config_file:?}");
+        return Ok(None);
+    }
+
+    info!("Reading dataset sources from {config_file:?}");
+    let sources = parse_source_list(&fs::read_to_string(&config_file)?)
+        .map_err(|e| format!("{}: {e}", config_file.display()))?;
+
+    Ok(Some(sources))
+}
+
+pub fn parse_source_list(text: &str) -> Result<Vec<DatasetSource>, String> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .map(DatasetSource::from_str)
+        .collect()
+}
+
+fn default_source_name(location: &str) -> String {
+    let file_name = location.rsplit(['/', '\\']).next().unwrap_or(location);
+    let stem = file_name.split('?').next().unwrap_or(file_name);
+    let stem = stem.strip_suffix(".gz").unwrap_or(stem);
+    let stem = stem.strip_suffix(".txt").or_else(|| stem.strip_suffix(".tsv")).unwrap_or(stem);
+
+    if stem.is_empty() { location.to_string() } else { stem.to_string() }
+}
+
+fn short_hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes()).iter().take(4).map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn sanitize_cache_key(key: &str) -> String {
+    key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use flate2::{write::GzEncoder, Compression};
+    use crate::protein::load_dataset_sources;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/smprot_sample.txt");
+
+    #[test]
+    fn test_parse_source_specs() {
+        let remote: DatasetSource = "https://example.org/SmProt2_RiboSeq.txt.gz".parse().unwrap();
+        assert_eq!(remote.name, "SmProt2_RiboSeq");
+        assert!(remote.cache_key.starts_with("smprot2_riboseq-") && remote.cache_key.len() == "smprot2_riboseq-".len() + 8);
+        assert!(matches!(remote.location, SourceLocation::Remote(ref url) if url.ends_with("RiboSeq.txt.gz")));
+
+        let named: DatasetSource = "Mirror Copy=http://mirror.local/data.txt.gz?token=a=b".parse().unwrap();
+        assert_eq!(named.name, "Mirror Copy");
+        assert!(named.cache_key.starts_with("mirror_copy-"));
+        // Names that sanitize alike keep apart by location
+        let other: DatasetSource = "mirror copy=http://other.local/data.txt.gz".parse().unwrap();
+        assert_ne!(other.cache_key, named.cache_key);
+        assert!(matches!(named.location, SourceLocation::Remote(ref url) if url.ends_with("token=a=b")));
+
+        let local: DatasetSource = "/data/smprot/ms.tsv".parse().unwrap();
+        assert_eq!(local.name, "ms");
+        assert!(matches!(local.location, SourceLocation::LocalFile(_)));
+
+        let checked: DatasetSource = format!("ms=https://example.org/ms.txt.gz;sha256={};size=42", "AB".repeat(32)).parse().unwrap();
+        assert_eq!(checked.integrity.sha256, Some("ab".repeat(32)));
+        assert_eq!(checked.integrity.size, Some(42));
+
+        assert!("riboseq=".parse::<DatasetSource>().is_err());
+        assert!("ms=https://example.org/ms.txt.gz;md5=00".parse::<DatasetSource>().is_err());
+    }
+
+    #[test]
+    fn test_parse_source_list_skips_comments() {
+        let sources = parse_source_list("# SmProt mirrors\n\nliterature=/srv/lit.txt.gz  # local copy\nhttps://example.org/ms.txt\n").unwrap();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].name, "literature");
+        assert_eq!(sources[1].name, "ms");
+    }
+
+    #[test]
+    fn test_default_source_keeps_legacy_cache_key() {
+        let source = DatasetSource::default();
+        assert_eq!(source.cache_key, "small_protein_dataset");
+        assert!(matches!(source.location, SourceLocation::Remote(ref url) if url == SMPROT_LITERATURE_URL));
+    }
+
+    #[test]
+    fn test_sources_merge_and_tag_proteins() {
+        let lines: Vec<&str> = FIXTURE.lines().collect();
+        let first_half = lines[..21].join("\n");
+        let second_half = [lines[0]].iter().chain(&lines[21..]).copied().collect::<Vec<_>>().join("\n");
+
+        let dir = tempfile::tempdir().unwrap();
+        let gzip_path = dir.path().join("second.txt.gz");
+        let mut encoder = GzEncoder::new(fs::File::create(&gzip_path).unwrap(), Compression::default());
+        encoder.write_all(second_half.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let sources = [
+            DatasetSource::in_memory("memory", first_half.into_bytes()),
+            DatasetSource::local_file("gzip", &gzip_path),
+        ];
+        let proteins = load_dataset_sources(&sources, None).unwrap().proteins;
+
+        assert_eq!(proteins.len(), lines.len() - 1);
+        assert!(proteins[..20].iter().all(|protein| protein.source == "memory"));
+        assert!(proteins[20..].iter().all(|protein| protein.source == "gzip"));
+        assert_eq!(proteins[20].id, lines[21].split('\t').nth(1).unwrap());
+    }
+
+    #[test]
+    fn test_missing_local_file_names_source() {
+        let error = load_dataset_sources(&[DatasetSource::local_file("absent", "/nonexistent/smprot.txt")], None)
+            .unwrap_err();
+        assert!(error.to_string().starts_with("absent: "));
+    }
+}
@@ -0,0 +1,227 @@
+//! Local heuristics for membrane topology: transmembrane helices from
+//! windowed hydropathy, and N-terminal signal peptides from von Heijne's
+//! n/h/c-region rules.
+use std::ops::Range;
+use crate::protein::SmallProtein;
+use crate::protein::profiles::{Profile, TM_HYDROPATHY_THRESHOLD};
+use crate::protein::properties::kyte_doolittle;
+
+/// Kyte & Doolittle's window for membrane-spanning segments
+pub const TM_WINDOW: usize = 19;
+pub const MIN_TM_LENGTH: usize = 16;
+pub const MAX_TM_LENGTH: usize = 25;
+
+/// n-regions are usually 1-5 residues; a few more are allowed
+const MAX_N_REGION: usize = 8;
+const H_REGION_LENGTHS: Range<usize> = 7..16;
+const C_REGION_LENGTHS: Range<usize> = 3..8;
+/// Mean Kyte-Doolittle hydropathy an h-region needs
+const MIN_H_REGION_HYDROPATHY: f64 = 1.8;
+/// c-regions are polar; a hydrophobic one means the h-region runs on into
+/// a membrane anchor
+const MAX_C_REGION_HYDROPATHY: f64 = 1.0;
+const MIN_MATURE_LENGTH: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransmembraneHelix {
+    /// 0-based, end-exclusive
+    pub range: Range<usize>,
+    /// Mean Kyte-Doolittle hydropathy
+    pub hydropathy: f64,
+}
+
+/// Regions are 0-based and end-exclusive; the c-region ends at the cleavage site.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalPeptide {
+    pub n_region: Range<usize>,
+    pub h_region: Range<usize>,
+    pub c_region: Range<usize>,
+    /// Mean hydropathy of the h-region
+    pub hydropathy: f64,
+    /// -3/-1 rule score of the cleavage site
+    pub site_score: u32,
+}
+
+impl SignalPeptide {
+    /// Residues cleaved off; the mature protein starts at this index.
+    pub fn cleavage_site(&self) -> usize {
+        self.c_region.end
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TopologyPrediction {
+    pub signal_peptide: Option<SignalPeptide>,
+    /// Helices outside the signal peptide, in sequence order
+    pub tm_helices: Vec<TransmembraneHelix>,
+}
+
+impl TopologyPrediction {
+    /// A hydrophobic segment mostly inside a predicted signal peptide is its
+    /// h-region, not a helix of the mature protein.
+    pub fn predict(protein: &str) -> TopologyPrediction {
+        let signal_peptide = predict_signal_peptide(protein);
+        let cleaved = signal_peptide.as_ref().map_or(0, SignalPeptide::cleavage_site);
+        let tm_helices = predict_tm_helices(protein).into_iter()
+            .filter(|helix| cleaved.saturating_sub(helix.range.start) * 2 < helix.range.len())
+            .collect();
+        TopologyPrediction { signal_peptide, tm_helices }
+    }
+
+    pub fn summary(&self) -> &'static str {
+        match (&self.signal_peptide, self.tm_helices.len()) {
+            (Some(_), 0) => "secreted",
+            (None, 0) => "soluble",
+            (_, 1) => "single-pass membrane",
+            (_, _) => "multi-pass membrane",
+        }
+    }
+}
+
+/// Predictions for every protein of a dataset, in the same order.
+pub fn predict_dataset_topology(proteins: &[SmallProtein]) -> Vec<TopologyPrediction> {
+    proteins.iter().map(|protein| TopologyPrediction::predict(&protein.aa_seq)).collect()
+}
+
+fn residues(protein: &str) -> Vec<char> {
+    protein.trim().trim_end_matches('*').chars().map(|c| c.to_ascii_uppercase()).collect()
+}
+
+fn mean_hydropathy(hydropathy: &[f64], range: &Range<usize>) -> f64 {
+    hydropathy[range.clone()].iter().sum::<f64>() / range.len() as f64
+}
+
+/// Runs of 19-residue windows above the Kyte-Doolittle cutoff, trimmed of
+/// polar ends. Runs too long for one helix are split evenly into as few
+/// helices as fit.
+pub fn predict_tm_helices(protein: &str) -> Vec<TransmembraneHelix> {
+    let hydropathy: Vec<f64> = residues(protein).into_iter().map(|residue| kyte_doolittle(residue).unwrap_or(0.0)).collect();
+    let mut helices = Vec::new();
+
+    for mut region in Profile::hydropathy(protein, TM_WINDOW).regions_above(TM_HYDROPATHY_THRESHOLD) {
+        while region.len() > MIN_TM_LENGTH && hydropathy[region.start] < 0.0 {
+            region.start += 1;
+        }
+        while region.len() > MIN_TM_LENGTH && hydropathy[region.end - 1] < 0.0 {
+            region.end -= 1;
+        }
+
+        let count = region.len().div_ceil(MAX_TM_LENGTH);
+        for part in 0..count {
+            let helix = region.start + part * region.len() / count..region.start + (part + 1) * region.len() / count;
+            let mean = mean_hydropathy(&hydropathy, &helix);
+            if helix.len() >= MIN_TM_LENGTH && mean >= TM_HYDROPATHY_THRESHOLD {
+                helices.push(TransmembraneHelix { range: helix, hydropathy: mean });
+            }
+        }
+    }
+
+    helices
+}
+
+/// von Heijne's -3/-1 rule: small, neutral residues at both positions.
+fn cleavage_site_score(minus_three: char, minus_one: char) -> Option<u32> {
+    let minus_one = match minus_one {
+        'A' => 3,
+        'G' | 'S' | 'C' => 2,
+        'T' => 1,
+        _ => return None,
+    };
+    let minus_three = match minus_three {
+        'A' => 3,
+        'V' => 2,
+        'S' | 'G' | 'T' | 'C' | 'I' | 'L' => 1,
+        _ => return None,
+    };
+    Some(minus_one + minus_three)
+}
+
+/// Looks for a positive or neutral n-region, an uncharged hydrophobic
+/// h-region of 7-15 residues and a polar 3-7 residue c-region ending in a
+/// -3/-1 cleavage site not followed by proline. Candidates are ranked by the
+/// h-region's summed hydropathy plus the site score.
+pub fn predict_signal_peptide(protein: &str) -> Option<SignalPeptide> {
+    let residues = residues(protein);
+    let hydropathy: Vec<f64> = residues.iter().map(|&residue| kyte_doolittle(residue).unwrap_or(0.0)).collect();
+    let charge = |range: Range<usize>| residues[range].iter()
+        .map(|residue| match residue {
+            'K' | 'R' => 1,
+            'D' | 'E' => -1,
+            _ => 0,
+        })
+        .sum::<i32>();
+
+    let mut best: Option<(f64, SignalPeptide)> = None;
+    for h_start in 1..=MAX_N_REGION.min(residues.len()) {
+        if charge(0..h_start) < 0 {
+            continue;
+        }
+        for h_length in H_REGION_LENGTHS {
+            let h_region = h_start..h_start + h_length;
+            if h_region.end + C_REGION_LENGTHS.start + MIN_MATURE_LENGTH > residues.len() {
+                break;
+            }
+            // One charge ends the h-region
+            if residues[h_region.clone()].iter().any(|residue| matches!(residue, 'K' | 'R' | 'D' | 'E')) {
+                break;
+            }
+            let h_hydropathy = mean_hydropathy(&hydropathy, &h_region);
+            if h_hydropathy < MIN_H_REGION_HYDROPATHY {
+                continue;
+            }
+
+            for cleavage in C_REGION_LENGTHS.map(|c_length| h_region.end + c_length) {
+                if cleavage + MIN_MATURE_LENGTH > residues.len() || residues[cleavage] == 'P' {
+                    continue;
+                }
+                if mean_hydropathy(&hydropathy, &(h_region.end..cleavage)) > MAX_C_REGION_HYDROPATHY {
+                    continue;
+                }
+                let Some(site_score) = cleavage_site_score(residues[cleavage - 3], residues[cleavage - 1]) else {
+                    continue;
+                };
+                let score = h_hydropathy * h_length as f64 + site_score as f64;
+                if best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+                    best = Some((score, SignalPeptide {
+                        n_region: 0..h_start,
+                        h_region: h_region.clone(),
+                        c_region: h_region.end..cleavage,
+                        hydropathy: h_hydropathy,
+                        site_score,
+                    }));
+                }
+            }
+        }
+    }
+
+    best.map(|(_, signal_peptide)| signal_peptide)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signal_peptide_cleavage_and_tm_helices() {
+        // Serum albumin, signal peptide 1-18 (..SAYS|RG..)
+        let albumin = "MKWVTFISLLFLFSSAYSRGVFRRDAHKSEVAHRFKDLGEENFKALVLIAFAQYLQQCPFEDHVKLVNEVTEFAKTCVADESAENCDKS";
+        let prediction = TopologyPrediction::predict(albumin);
+        let signal = prediction.signal_peptide.as_ref().expect("albumin has a signal peptide");
+        assert_eq!(signal.cleavage_site(), 18);
+        assert!(signal.n_region.end <= MAX_N_REGION && signal.h_region.len() >= 7);
+        assert_eq!(prediction.summary(), "secreted");
+
+        // Ubiquitin is cytosolic
+        let ubiquitin = "MQIFVKTLTGKTITLEVEPSDTIENVKAKIQDKEGIPPDQQRLIFAGKQLEDGRTLSDYNIQKESTLHLVLRLRGG";
+        assert_eq!(TopologyPrediction::predict(ubiquitin), TopologyPrediction::default());
+
+        // Two hydrophobic spans between charged loops, the second too long for one helix
+        let membrane = format!("MSKEDRPQSGNT{}KRRDEGSQNTPESKDG{}RKDESQ*", "LLVAILLGVFIALLVLAAIFW", "LIVALLFGAVLILAVIGLLFWGLAVILLAFVVLLAGLIFA");
+        let prediction = TopologyPrediction::predict(&membrane);
+        assert!(prediction.signal_peptide.is_none());
+        let helices: Vec<Range<usize>> = prediction.tm_helices.iter().map(|helix| helix.range.clone()).collect();
+        // Kyte-Doolittle scores the first span's closing tryptophan as polar, so it is trimmed
+        assert_eq!(helices, [12..32, 49..69, 69..89]);
+        assert_eq!(prediction.summary(), "multi-pass membrane");
+    }
+}
@@ -1,7 +1,6 @@
-use bio_seq::prelude::*;
-use bio_seq::translation::{TranslationTable, STANDARD};
-use crate::sequence::conversion::dna_sequence_to_mrna;
-use crate::protein::molecular_weights::get_amino_acid_molecular_weight;
+use crate::sequence::genetic_code::GeneticCode;
+use crate::sequence::translation::{OrfOptions, find_open_reading_frames};
+use crate::protein::molecular_weights::{MassType, residue_mass};
 
 pub fn calculate_gc_content(dna: &str) -> f64 {
     if dna.is_empty() {
@@ -58,52 +57,27 @@ pub fn calculate_amino_acid_length(dna: &str) -> usize {
     dna.len() / 3
 }
 
-pub fn estimate_molecular_weight(dna: &str) -> f64 {
+pub fn estimate_molecular_weight(dna: &str, code: &GeneticCode) -> f64 {
     if dna.len() < 3 {
         return 0.0;
     }
 
-    if let Ok(translation) = crate::sequence::translation::translate_dna_to_amino(dna) {
-        let mut total_weight = 0.0;
+    // Average masses; incomplete trailing codons, stops and ambiguous (X) codons are skipped
+    let total_weight: f64 = dna.as_bytes()
+        .chunks_exact(3)
+        .filter_map(|codon| code.translate_codon(codon))
+        .filter_map(|amino_char| residue_mass(amino_char, MassType::Average))
+        .sum();
 
-        for amino_char in translation.chars() {
-            if amino_char != '*' {
-                total_weight += get_amino_acid_molecular_weight(amino_char);
-            }
-        }
-
-        total_weight + 18.015
-    } else {
-        let mrna = dna_sequence_to_mrna(dna);
-        let mut total_weight = 0.0;
-
-        for i in (0..mrna.len()).step_by(3) {
-            if i + 2 < mrna.len() {
-                let codon = &mrna[i..i+3];
-                if let Ok(codon_seq) = codon.parse::<Seq<Dna>>() {
-                    if codon_seq.len() == 3 {
-                        let amino = STANDARD.to_amino(&codon_seq);
-                        let amino_str = amino.to_string();
-                        if let Some(amino_char) = amino_str.chars().next() {
-                            if amino_char != '*' {
-                                total_weight += get_amino_acid_molecular_weight(amino_char);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        total_weight + 18.015
-    }
+    total_weight + MassType::Average.water()
 }
 
-pub fn calculate_hydrophobicity_index(dna: &str) -> f64 {
+pub fn calculate_hydrophobicity_index(dna: &str, code: &GeneticCode) -> f64 {
     if dna.len() < 3 {
         return 0.0;
     }
 
-    if let Ok(translation) = crate::sequence::translation::translate_dna_to_amino(dna) {
+    if let Ok(translation) = crate::sequence::translation::translate_dna_to_amino(dna, code) {
         let mut hydrophobic_count = 0;
         let total_amino_acids = translation.len();
 
@@ -123,12 +97,12 @@ pub fn calculate_hydrophobicity_index(dna: &str) -> f64 {
     }
 }
 
-pub fn count_charged_residues(dna: &str) -> (usize, usize) {
+pub fn count_charged_residues(dna: &str, code: &GeneticCode) -> (usize, usize) {
     if dna.len() < 3 {
         return (0, 0);
     }
 
-    if let Ok(translation) = crate::sequence::translation::translate_dna_to_amino(dna) {
+    if let Ok(translation) = crate::sequence::translation::translate_dna_to_amino(dna, code) {
         let mut positive_count = 0;
         let mut negative_count = 0;
 
@@ -146,26 +120,7 @@ pub fn count_charged_residues(dna: &str) -> (usize, usize) {
     }
 }
 
-pub fn count_orfs(dna: &str) -> usize {
-    if dna.len() < 3 {
-        return 0;
-    }
-
-    let mut orf_count = 0;
-    let mut in_orf = false;
-
-    for i in (0..dna.len()).step_by(3) {
-        if i + 2 < dna.len() {
-            let codon = &dna[i..i+3].to_uppercase();
-
-            if codon == "ATG" && !in_orf {
-                in_orf = true;
-            } else if (codon == "TAA" || codon == "TAG" || codon == "TGA") && in_orf {
-                orf_count += 1;
-                in_orf = false;
-            }
-        }
-    }
-
-    orf_count
-}
\ No newline at end of file
+/// Counts ORFs in all six frames, nested ones included.
+pub fn count_orfs(dna: &str, code: &GeneticCode, options: &OrfOptions) -> usize {
+    find_open_reading_frames(dna, code, options).map_or(0, |orfs| orfs.len())
+}
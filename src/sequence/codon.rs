@@ -1,16 +1,8 @@
-use bio_seq::prelude::*;
-use bio_seq::translation::{TranslationTable, STANDARD};
+use crate::sequence::genetic_code::GeneticCode;
 
-pub fn dna_codon_to_amino_acid(codon: &str) -> String {
-    if let Ok(codon_seq) = codon.parse::<Seq<Dna>>() {
-        if codon_seq.len() == 3 {
-            STANDARD.to_amino(&codon_seq).to_string()
-        } else {
-            "?".to_string()
-        }
-    } else {
-        "?".to_string()
-    }
+pub fn dna_codon_to_amino_acid(codon: &str, code: &GeneticCode) -> String {
+    code.translate_codon(codon.as_bytes())
+        .map_or_else(|| "?".to_string(), |amino| amino.to_string())
 }
 
 pub fn count_total_codons(dna: &str) -> usize {
@@ -19,38 +11,18 @@ pub fn count_total_codons(dna: &str) -> usize {
 
 pub fn count_complete_incomplete_codons(dna: &str) -> (usize, usize) {
     let complete = dna.len() / 3;
-    let incomplete = if dna.len() % 3 > 0 { 1 } else { 0 };
+    let incomplete = if dna.len().is_multiple_of(3) { 0 } else { 1 };
     (complete, incomplete)
 }
 
-pub fn count_start_codons(dna: &str) -> usize {
-    if dna.len() < 3 {
-        return 0;
-    }
-
-    let mut count = 0;
-    for i in 0..=(dna.len() - 3) {
-        let codon = &dna[i..i+3].to_uppercase();
-        if codon == "ATG" || codon == "AUG" {
-            count += 1;
-        }
-    }
-    count
+/// Counts start codons of `code` at every position, in any frame.
+pub fn count_start_codons(dna: &str, code: &GeneticCode) -> usize {
+    dna.as_bytes().windows(3).filter(|codon| code.is_start(codon)).count()
 }
 
-pub fn count_stop_codons(dna: &str) -> usize {
-    if dna.len() < 3 {
-        return 0;
-    }
-
-    let mut count = 0;
-    for i in 0..=(dna.len() - 3) {
-        let codon = &dna[i..i+3].to_uppercase();
-        if codon == "TAA" || codon == "TAG" || codon == "TGA" {
-            count += 1;
-        }
-    }
-    count
+/// Counts stop codons of `code` at every position, in any frame.
+pub fn count_stop_codons(dna: &str, code: &GeneticCode) -> usize {
+    dna.as_bytes().windows(3).filter(|codon| code.is_stop(codon)).count()
 }
 
 pub fn find_reading_frames(dna: &str) -> Vec<String> {
@@ -1,20 +1,14 @@
+use crate::sequence::iupac::complement_iupac;
+
+/// Complements DNA, RNA and IUPAC ambiguity codes; anything else becomes '?'.
 pub fn get_complementary_base(base: char) -> char {
-    match base.to_uppercase().next().unwrap_or(' ') {
-        'A' => 'T',
-        'T' => 'A',
-        'G' => 'C',
-        'C' => 'G',
-        _ => '?',
-    }
+    complement_iupac(base).unwrap_or('?')
 }
 
 pub fn dna_to_mrna(base: char) -> char {
-    match base.to_uppercase().next().unwrap_or(' ') {
-        'A' => 'U',
-        'T' => 'A',
-        'G' => 'C',
-        'C' => 'G',
-        _ => '?',
+    match get_complementary_base(base) {
+        'T' => 'U',
+        other => other,
     }
 }
 
@@ -0,0 +1,246 @@
+use std::fmt;
+use crate::sequence::iupac::iupac_bases;
+
+/// An NCBI translation table. `amino_acids` and `starts` are the table's
+/// 64-character rows in NCBI order (first base TCAG, then second, then third),
+/// with `*` for stops and `M` marking alternative initiation codons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeneticCode {
+    pub id: u8,
+    pub name: &'static str,
+    amino_acids: &'static [u8; 64],
+    starts: &'static [u8; 64],
+}
+
+macro_rules! genetic_code {
+    ($id:expr, $name:expr, $amino_acids:expr, $starts:expr) => {
+        GeneticCode { id: $id, name: $name, amino_acids: $amino_acids, starts: $starts }
+    };
+}
+
+/// Every table in NCBI's gc.prt; IDs 7, 8 and 17-20 are unassigned.
+pub static GENETIC_CODES: [GeneticCode; 27] = [
+    genetic_code!(1, "Standard",
+        b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"---M------**--*----M---------------M----------------------------"),
+    genetic_code!(2, "Vertebrate Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG",
+        b"----------**--------------------MMMM----------**---M------------"),
+    genetic_code!(3, "Yeast Mitochondrial",
+        b"FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"----------**----------------------MM---------------M------------"),
+    genetic_code!(4, "Mold, Protozoan, and Coelenterate Mitochondrial; Mycoplasma/Spiroplasma",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"--MM------**-------M------------MMMM---------------M------------"),
+    genetic_code!(5, "Invertebrate Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG",
+        b"---M------**--------------------MMMM---------------M------------"),
+    genetic_code!(6, "Ciliate, Dasycladacean and Hexamita Nuclear",
+        b"FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"--------------*--------------------M----------------------------"),
+    genetic_code!(9, "Echinoderm and Flatworm Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        b"----------**-----------------------M---------------M------------"),
+    genetic_code!(10, "Euplotid Nuclear",
+        b"FFLLSSSSYY**CCCWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"----------**-----------------------M----------------------------"),
+    genetic_code!(11, "Bacterial, Archaeal and Plant Plastid",
+        b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"---M------**--*----M------------MMMM---------------M------------"),
+    genetic_code!(12, "Alternative Yeast Nuclear",
+        b"FFLLSSSSYY**CC*WLLLSPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"----------**--*----M---------------M----------------------------"),
+    genetic_code!(13, "Ascidian Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSGGVVVVAAAADDEEGGGG",
+        b"---M------**----------------------MM---------------M------------"),
+    genetic_code!(14, "Alternative Flatworm Mitochondrial",
+        b"FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        b"-----------*-----------------------M----------------------------"),
+    genetic_code!(15, "Blepharisma Macronuclear",
+        b"FFLLSSSSYY*QCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"----------*---*--------------------M----------------------------"),
+    genetic_code!(16, "Chlorophycean Mitochondrial",
+        b"FFLLSSSSYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"----------*---*--------------------M----------------------------"),
+    genetic_code!(21, "Trematode Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        b"----------**-----------------------M---------------M------------"),
+    genetic_code!(22, "Scenedesmus obliquus Mitochondrial",
+        b"FFLLSS*SYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"------*---*---*--------------------M----------------------------"),
+    genetic_code!(23, "Thraustochytrium Mitochondrial",
+        b"FF*LSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"--*-------**--*-----------------M--M---------------M------------"),
+    genetic_code!(24, "Rhabdopleuridae Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        b"---M------**-------M---------------M---------------M------------"),
+    genetic_code!(25, "Candidate Division SR1 and Gracilibacteria",
+        b"FFLLSSSSYY**CCGWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"---M------**-----------------------M---------------M------------"),
+    genetic_code!(26, "Pachysolen tannophilus Nuclear",
+        b"FFLLSSSSYY**CC*WLLLAPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"----------**--*----M---------------M----------------------------"),
+    genetic_code!(27, "Karyorelict Nuclear",
+        b"FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"--------------*--------------------M----------------------------"),
+    genetic_code!(28, "Condylostoma Nuclear",
+        b"FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"----------**--*--------------------M----------------------------"),
+    genetic_code!(29, "Mesodinium Nuclear",
+        b"FFLLSSSSYYYYCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"--------------*--------------------M----------------------------"),
+    genetic_code!(30, "Peritrich Nuclear",
+        b"FFLLSSSSYYEECC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"--------------*--------------------M----------------------------"),
+    genetic_code!(31, "Blastocrithidia Nuclear",
+        b"FFLLSSSSYYEECCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"----------**-----------------------M----------------------------"),
+    genetic_code!(32, "Balanophoraceae Plastid",
+        b"FFLLSSSSYY*WCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"---M------*---*----M------------MMMM---------------M------------"),
+    genetic_code!(33, "Cephalodiscidae Mitochondrial",
+        b"FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        b"---M-------*-------M---------------M---------------M------------"),
+];
+
+const BASES: [char; 4] = ['T', 'C', 'A', 'G'];
+
+fn base_index(base: u8) -> Option<usize> {
+    match base.to_ascii_uppercase() {
+        b'T' | b'U' => Some(0),
+        b'C' => Some(1),
+        b'A' => Some(2),
+        b'G' => Some(3),
+        _ => None,
+    }
+}
+
+/// Position of a DNA or RNA codon in the NCBI table rows.
+fn codon_index(codon: &[u8]) -> Option<usize> {
+    match codon {
+        [first, second, third] => Some(base_index(*first)? * 16 + base_index(*second)? * 4 + base_index(*third)?),
+        _ => None,
+    }
+}
+
+fn codon_at(index: usize) -> String {
+    [BASES[index / 16], BASES[index / 4 % 4], BASES[index % 4]].iter().collect()
+}
+
+impl GeneticCode {
+    pub fn by_id(id: u8) -> Option<&'static GeneticCode> {
+        GENETIC_CODES.iter().find(|code| code.id == id)
+    }
+
+    pub fn standard() -> &'static GeneticCode {
+        &GENETIC_CODES[0]
+    }
+
+    /// The amino acid for a three-base DNA or RNA codon, `*` for stops, or
+    /// `None` if the codon has anything but nucleotide codes. Codons with
+    /// IUPAC ambiguity codes give the residue every expansion agrees on, or X.
+    pub fn translate_codon(&self, codon: &[u8]) -> Option<char> {
+        if let Some(index) = codon_index(codon) {
+            return Some(self.amino_acids[index] as char);
+        }
+
+        let [first, second, third] = codon else {
+            return None;
+        };
+        let (first, second, third) = (iupac_bases(*first)?, iupac_bases(*second)?, iupac_bases(*third)?);
+        let mut residues = first.iter().flat_map(|&a| {
+            second.iter().flat_map(move |&b| third.iter().map(move |&c| [a, b, c]))
+        }).map(|expansion| codon_index(&expansion).map(|index| self.amino_acids[index]));
+
+        let residue = residues.next()??;
+        Some(if residues.all(|other| other == Some(residue)) { residue as char } else { 'X' })
+    }
+
+    /// Whether the codon can initiate translation, ATG or an alternative start.
+    pub fn is_start(&self, codon: &[u8]) -> bool {
+        codon_index(codon).is_some_and(|index| self.starts[index] == b'M')
+    }
+
+    pub fn is_stop(&self, codon: &[u8]) -> bool {
+        self.translate_codon(codon) == Some('*')
+    }
+
+    pub fn start_codons(&self) -> Vec<String> {
+        (0..64).filter(|&index| self.starts[index] == b'M').map(codon_at).collect()
+    }
+
+    pub fn stop_codons(&self) -> Vec<String> {
+        (0..64).filter(|&index| self.amino_acids[index] == b'*').map(codon_at).collect()
+    }
+
+    /// The next table in ID order, wrapping around, for cycling in the TUI.
+    pub fn next(&self) -> &'static GeneticCode {
+        let position = GENETIC_CODES.iter().position(|code| code.id == self.id).unwrap_or(0);
+        &GENETIC_CODES[(position + 1) % GENETIC_CODES.len()]
+    }
+
+    pub fn previous(&self) -> &'static GeneticCode {
+        let position = GENETIC_CODES.iter().position(|code| code.id == self.id).unwrap_or(0);
+        &GENETIC_CODES[(position + GENETIC_CODES.len() - 1) % GENETIC_CODES.len()]
+    }
+}
+
+impl fmt::Display for GeneticCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}. {}", self.id, self.name)
+    }
+}
+
+/// Parses a table ID for `--genetic-code`.
+pub fn parse_genetic_code(s: &str) -> Result<&'static GeneticCode, String> {
+    let id: u8 = s.trim().parse().map_err(|_| format!("genetic code '{s}' is not a table number"))?;
+    GeneticCode::by_id(id).ok_or_else(|| format!(
+        "no NCBI genetic code {id}, expected one of: {}",
+        GENETIC_CODES.iter().map(|code| code.id.to_string()).collect::<Vec<_>>().join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables_are_well_formed() {
+        for code in &GENETIC_CODES {
+            assert!(code.amino_acids.iter().all(|aa| aa.is_ascii_uppercase() || *aa == b'*'), "table {}", code.id);
+            assert!(code.starts.iter().all(|start| matches!(start, b'-' | b'M' | b'*')), "table {}", code.id);
+            assert!(code.is_start(b"ATG"), "table {} lacks ATG start", code.id);
+        }
+        assert!(GENETIC_CODES.windows(2).all(|pair| pair[0].id < pair[1].id));
+    }
+
+    #[test]
+    fn test_mitochondrial_and_bacterial_codes() {
+        let standard = GeneticCode::standard();
+        let vertebrate_mito = GeneticCode::by_id(2).unwrap();
+        let yeast_mito = GeneticCode::by_id(3).unwrap();
+        let bacterial = GeneticCode::by_id(11).unwrap();
+
+        assert_eq!(standard.translate_codon(b"TGA"), Some('*'));
+        assert_eq!(vertebrate_mito.translate_codon(b"UGA"), Some('W'));
+        assert_eq!(vertebrate_mito.stop_codons(), ["TAA", "TAG", "AGA", "AGG"]);
+        assert_eq!(yeast_mito.translate_codon(b"CTG"), Some('T'));
+        assert_eq!(bacterial.start_codons(), ["TTG", "CTG", "ATT", "ATC", "ATA", "ATG", "GTG"]);
+        assert_eq!(standard.translate_codon(b"A-G"), None);
+
+        assert!(parse_genetic_code("7").is_err());
+        assert_eq!(parse_genetic_code("33").unwrap().next().id, 1);
+    }
+
+    #[test]
+    fn test_ambiguous_codons() {
+        let standard = GeneticCode::standard();
+
+        assert_eq!(standard.translate_codon(b"GCN"), Some('A'));
+        assert_eq!(standard.translate_codon(b"TAR"), Some('*'));
+        assert_eq!(standard.translate_codon(b"ATN"), Some('X'));
+        assert_eq!(standard.translate_codon(b"nnn"), Some('X'));
+        assert!(standard.is_stop(b"TRA"));
+        assert!(!standard.is_start(b"ATN"));
+    }
+}
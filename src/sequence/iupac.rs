@@ -0,0 +1,64 @@
+/// The unambiguous bases an IUPAC nucleotide code stands for, with U read as
+/// T. `None` for anything that is not a nucleotide code.
+pub fn iupac_bases(code: u8) -> Option<&'static [u8]> {
+    Some(match code.to_ascii_uppercase() {
+        b'A' => b"A",
+        b'C' => b"C",
+        b'G' => b"G",
+        b'T' | b'U' => b"T",
+        b'R' => b"AG",
+        b'Y' => b"CT",
+        b'S' => b"CG",
+        b'W' => b"AT",
+        b'K' => b"GT",
+        b'M' => b"AC",
+        b'B' => b"CGT",
+        b'D' => b"AGT",
+        b'H' => b"ACT",
+        b'V' => b"ACG",
+        b'N' => b"ACGT",
+        _ => return None,
+    })
+}
+
+pub fn is_iupac_nucleotide(c: char) -> bool {
+    u8::try_from(c).ok().and_then(iupac_bases).is_some()
+}
+
+pub fn is_ambiguous_nucleotide(c: char) -> bool {
+    u8::try_from(c).ok().and_then(iupac_bases).is_some_and(|bases| bases.len() > 1)
+}
+
+/// The IUPAC code for the complements of `code`'s bases, e.g. R (A/G) to Y (C/T).
+pub fn complement_iupac(code: char) -> Option<char> {
+    Some(match code.to_ascii_uppercase() {
+        'A' => 'T',
+        'T' | 'U' => 'A',
+        'G' => 'C',
+        'C' => 'G',
+        'R' => 'Y',
+        'Y' => 'R',
+        'K' => 'M',
+        'M' => 'K',
+        'B' => 'V',
+        'V' => 'B',
+        'D' => 'H',
+        'H' => 'D',
+        'S' => 'S',
+        'W' => 'W',
+        'N' => 'N',
+        _ => return None,
+    })
+}
+
+/// Chance that two IUPAC codes denote the same base when each expansion is
+/// equally likely: 1 for A/A, 0.5 for A/R, 0.25 for A/N.
+pub fn base_match_probability(a: u8, b: u8) -> f64 {
+    match (iupac_bases(a), iupac_bases(b)) {
+        (Some(a_bases), Some(b_bases)) => {
+            let shared = a_bases.iter().filter(|base| b_bases.contains(base)).count();
+            shared as f64 / (a_bases.len() * b_bases.len()) as f64
+        },
+        _ => 0.0,
+    }
+}
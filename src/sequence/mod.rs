@@ -1,9 +1,15 @@
 pub mod analysis;
 pub mod codon;
 pub mod conversion;
+pub mod genetic_code;
+pub mod iupac;
+pub mod records;
 pub mod translation;
 
 pub use analysis::*;
 pub use codon::*;
 pub use conversion::*;
+pub use genetic_code::*;
+pub use iupac::*;
+pub use records::*;
 pub use translation::*;
@@ -0,0 +1,215 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use flate2::read::MultiGzDecoder;
+use log::{info, debug};
+use crate::protein::NucleotideSequence;
+use crate::sequence::iupac::is_iupac_nucleotide;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// One entry of a sequence file. `quality` is only set for FASTQ input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceRecord {
+    pub id: String,
+    pub description: String,
+    pub sequence: String,
+    pub quality: Option<String>,
+}
+
+impl SequenceRecord {
+    /// The validated nucleotide sequence, with the record id in any error.
+    pub fn nucleotides(&self) -> Result<NucleotideSequence, String> {
+        NucleotideSequence::new(&self.sequence).map_err(|e| format!("{}: {e}", self.id))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    Fasta,
+    Fastq,
+    /// One bare sequence per line
+    Plain,
+}
+
+pub fn read_sequence_file(path: &Path) -> Result<Vec<SequenceRecord>, Box<dyn Error>> {
+    info!("Reading sequence records from {path:?}");
+    let file = File::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let records = read_sequence_records(file).map_err(|e| format!("{}: {e}", path.display()))?;
+    info!("Read {} records from {path:?}", records.len());
+    Ok(records)
+}
+
+/// Reads FASTA, FASTQ or plain sequences, gzip-compressed or not; both the
+/// compression and the format are detected from the content.
+pub fn read_sequence_records<R: Read>(reader: R) -> Result<Vec<SequenceRecord>, Box<dyn Error>> {
+    let mut reader = BufReader::new(reader);
+    if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        debug!("Sequence input is gzip-compressed");
+        Ok(parse_sequence_records(BufReader::new(MultiGzDecoder::new(reader)))?)
+    } else {
+        Ok(parse_sequence_records(reader)?)
+    }
+}
+
+pub fn parse_sequence_records<R: BufRead>(reader: R) -> Result<Vec<SequenceRecord>, String> {
+    let mut lines = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("line {}: {e}", i + 1))?;
+        lines.push((i + 1, line.trim_end().to_string()));
+    }
+    lines.retain(|(_, line)| !line.trim().is_empty());
+
+    let format = match lines.first().map(|(_, line)| line.as_bytes()[0]) {
+        Some(b'>') => RecordFormat::Fasta,
+        Some(b'@') => RecordFormat::Fastq,
+        _ => RecordFormat::Plain,
+    };
+    debug!("Sequence input looks like {format:?}");
+
+    match format {
+        RecordFormat::Fasta => parse_fasta(&lines),
+        RecordFormat::Fastq => parse_fastq(&lines),
+        RecordFormat::Plain => Ok(parse_plain(&lines)),
+    }
+}
+
+/// Pasted text reduced to nucleotide codes.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CleanedSequence {
+    /// Uppercase, with U read as T
+    pub sequence: String,
+    /// Characters dropped that were not whitespace, digits or header text
+    pub rejected: usize,
+}
+
+/// Cleans text copied from papers or genome browsers: FASTA headers and
+/// comment lines, whitespace and digits (line numbering) are dropped, and
+/// so is anything else that is not an IUPAC nucleotide code.
+pub fn clean_sequence_text(text: &str) -> CleanedSequence {
+    let mut cleaned = CleanedSequence::default();
+
+    for line in text.lines().filter(|line| !line.trim_start().starts_with(['>', ';'])) {
+        for c in line.chars().filter(|c| !c.is_whitespace() && !c.is_ascii_digit()) {
+            match c.to_ascii_uppercase() {
+                'U' => cleaned.sequence.push('T'),
+                upper if is_iupac_nucleotide(upper) => cleaned.sequence.push(upper),
+                _ => cleaned.rejected += 1,
+            }
+        }
+    }
+
+    cleaned
+}
+
+fn split_header(header: &str) -> (String, String) {
+    let header = header.trim();
+    match header.split_once(char::is_whitespace) {
+        Some((id, description)) => (id.to_string(), description.trim().to_string()),
+        None => (header.to_string(), String::new()),
+    }
+}
+
+fn parse_fasta(lines: &[(usize, String)]) -> Result<Vec<SequenceRecord>, String> {
+    let mut records: Vec<SequenceRecord> = Vec::new();
+
+    for (line_num, line) in lines {
+        if let Some(header) = line.strip_prefix('>') {
+            let (id, description) = split_header(header);
+            if id.is_empty() {
+                return Err(format!("line {line_num}: FASTA header has no id"));
+            }
+            records.push(SequenceRecord { id, description, sequence: String::new(), quality: None });
+        } else if !line.starts_with(';') {
+            let record = records.last_mut()
+                .ok_or_else(|| format!("line {line_num}: sequence before the first FASTA header"))?;
+            record.sequence.extend(line.chars().filter(|c| !c.is_whitespace()));
+        }
+    }
+
+    Ok(records)
+}
+
+fn parse_fastq(lines: &[(usize, String)]) -> Result<Vec<SequenceRecord>, String> {
+    lines.chunks(4)
+        .map(|chunk| {
+            let (line_num, header) = &chunk[0];
+            let [_, (_, sequence), (separator_line, separator), (quality_line, quality)] = chunk else {
+                return Err(format!("line {line_num}: incomplete FASTQ record"));
+            };
+
+            let header = header.strip_prefix('@')
+                .ok_or_else(|| format!("line {line_num}: FASTQ header must start with '@'"))?;
+            if !separator.starts_with('+') {
+                return Err(format!("line {separator_line}: expected '+' separator"));
+            }
+            if quality.len() != sequence.len() {
+                return Err(format!("line {quality_line}: quality length {} does not match sequence length {}",
+                                   quality.len(), sequence.len()));
+            }
+
+            let (id, description) = split_header(header);
+            Ok(SequenceRecord { id, description, sequence: sequence.clone(), quality: Some(quality.clone()) })
+        })
+        .collect()
+}
+
+fn parse_plain(lines: &[(usize, String)]) -> Vec<SequenceRecord> {
+    lines.iter()
+        .map(|(_, line)| line.trim())
+        .filter(|line| !line.starts_with('#'))
+        .enumerate()
+        .map(|(i, line)| SequenceRecord {
+            id: format!("seq{}", i + 1),
+            description: String::new(),
+            sequence: line.to_string(),
+            quality: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use flate2::{write::GzEncoder, Compression};
+
+    #[test]
+    fn test_fasta_records_span_lines() {
+        let fasta = ">read1 first read\nATGAAA\nTAG\n; comment\n\n>read2\nccc\n";
+        let records = parse_sequence_records(fasta.as_bytes()).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].id.as_str(), records[0].description.as_str()), ("read1", "first read"));
+        assert_eq!(records[0].sequence, "ATGAAATAG");
+        assert_eq!(records[1].nucleotides().unwrap().as_str(), "CCC");
+
+        let plain = parse_sequence_records("# query\nATG\n\nccc\n".as_bytes()).unwrap();
+        assert_eq!(plain.iter().map(|record| record.id.as_str()).collect::<Vec<_>>(), ["seq1", "seq2"]);
+    }
+
+    #[test]
+    fn test_clean_pasted_sequence() {
+        let pasted = ">NM_001 some gene\n  1 atgaaa cccggg\r\n 13 uuu-tag\n";
+        let cleaned = clean_sequence_text(pasted);
+        assert_eq!(cleaned.sequence, "ATGAAACCCGGGTTTTAG");
+        assert_eq!(cleaned.rejected, 1);
+    }
+
+    #[test]
+    fn test_gzipped_fastq_records() {
+        let fastq = "@r1 lane=1\nATGN.N\n+\nIIIIII\n@r2\nGGG\n+r2\n###\n";
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(fastq.as_bytes()).unwrap();
+        let records = read_sequence_records(&encoder.finish().unwrap()[..]).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].quality.as_deref(), Some("IIIIII"));
+        assert_eq!(records[0].nucleotides().unwrap_err(), "r1: invalid nucleotide '.' at position 5");
+        assert_eq!(records[1].sequence, "GGG");
+
+        let truncated = parse_sequence_records("@r1\nATG\n+\nII\n".as_bytes()).unwrap_err();
+        assert!(truncated.starts_with("line 4: quality length 2"));
+    }
+}
@@ -1,95 +1,192 @@
-use bio_seq::prelude::*;
-use bio_seq::translation::{TranslationTable, STANDARD};
+use crate::sequence::conversion::get_reverse_complement;
+use crate::sequence::genetic_code::GeneticCode;
 
-pub fn translate_dna_to_amino(dna: &str) -> Result<String, String> {
-    if dna.len() % 3 != 0 {
+pub fn translate_dna_to_amino(dna: &str, code: &GeneticCode) -> Result<String, String> {
+    if !dna.len().is_multiple_of(3) {
         return Err("DNA sequence length must be divisible by 3".to_string());
     }
 
-    if dna.chars().all(|c| matches!(c.to_ascii_uppercase(), 'A' | 'T' | 'G' | 'C')) {
-        if let Ok(seq) = dna.parse::<Seq<Dna>>() {
-            let mut amino_acids = Vec::new();
+    dna.as_bytes()
+        .chunks(3)
+        .map(|codon| code.translate_codon(codon).ok_or_else(|| "Invalid DNA sequence".to_string()))
+        .collect()
+}
 
-            for codon_chunk in seq.chunks(3) {
-                if codon_chunk.len() == 3 {
-                    let amino = STANDARD.to_amino(codon_chunk);
-                    amino_acids.push(amino.to_string());
-                }
-            }
+pub const READING_FRAME_LABELS: [&str; 6] = ["+1", "+2", "+3", "-1", "-2", "-3"];
 
-            return Ok(amino_acids.join(""));
-        }
+/// The in-frame part of `dna` for a forward frame offset, without the trailing partial codon.
+fn frame_slice(dna: &str, offset: usize) -> &str {
+    let frame_dna = dna.get(offset..).unwrap_or("");
+    &frame_dna[..frame_dna.len() - frame_dna.len() % 3]
+}
+
+/// Translates the three forward and three reverse-complement frames, in the
+/// order given by `READING_FRAME_LABELS`. Trailing partial codons are dropped
+/// so every frame translates.
+pub fn translate_all_reading_frames(dna: &str, code: &GeneticCode) -> Result<Vec<String>, String> {
+    let revcomp = get_reverse_complement(dna);
+
+    [dna, revcomp.as_str()].iter()
+        .flat_map(|strand| (0..3).map(move |offset| frame_slice(strand, offset)))
+        .map(|frame_dna| translate_dna_to_amino(frame_dna, code))
+        .collect()
+}
+
+/// Which codons may open an ORF and the shortest ORF worth reporting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrfOptions {
+    /// Uppercase DNA codons, e.g. `ATG`
+    pub start_codons: Vec<String>,
+    /// In amino acids, not counting the stop
+    pub min_length: usize,
+}
+
+impl Default for OrfOptions {
+    fn default() -> Self {
+        OrfOptions { start_codons: vec!["ATG".to_string()], min_length: 1 }
     }
+}
 
-    Err("Invalid DNA sequence".to_string())
+impl OrfOptions {
+    pub const NEAR_COGNATE_STARTS: [&'static str; 5] = ["ATG", "CTG", "GTG", "TTG", "ACG"];
+
+    /// ATG plus the near-cognate starts common in small protein annotations.
+    pub fn near_cognate() -> Self {
+        OrfOptions { start_codons: Self::NEAR_COGNATE_STARTS.map(String::from).to_vec(), ..Self::default() }
+    }
+
+    /// Every start codon `code` allows.
+    pub fn for_genetic_code(code: &GeneticCode) -> Self {
+        OrfOptions { start_codons: code.start_codons(), ..Self::default() }
+    }
+
+    pub fn with_min_length(self, min_length: usize) -> Self {
+        OrfOptions { min_length, ..self }
+    }
+
+    pub fn is_start(&self, codon: &[u8]) -> bool {
+        self.start_codons.iter().any(|start| start.as_bytes() == codon)
+    }
+
+    /// Start codons in RNA notation, for display.
+    pub fn start_label(&self) -> String {
+        self.start_codons.iter().map(|codon| codon.replace('T', "U")).collect::<Vec<_>>().join("/")
+    }
 }
 
-fn translate_frames_for_sequence(dna: &str, translations: &mut Vec<String>) {
-    for offset in 0..3 {
-        if offset < dna.len() {
-            let frame_dna = &dna[offset..];
-            if let Ok(translation) = translate_dna_to_amino(frame_dna) {
-                translations.push(translation);
-            }
-        }
+/// Parses one start codon for `--start-codons`, accepting RNA or lowercase.
+pub fn parse_start_codon(s: &str) -> Result<String, String> {
+    let dna = s.trim().to_ascii_uppercase().replace('U', "T");
+    if dna.len() != 3 || !dna.chars().all(|c| matches!(c, 'A' | 'C' | 'G' | 'T')) {
+        return Err(format!("invalid start codon '{s}'"));
     }
+    Ok(dna)
 }
 
-pub fn translate_all_reading_frames(dna: &str) -> Result<Vec<String>, String> {
-    let mut translations = Vec::new();
+/// An open reading frame from a start codon up to and including the first
+/// in-frame stop. `start` and `end` are 0-based, end-exclusive positions on
+/// the forward strand, so reverse-frame ORFs have `start` at their 3' end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenReadingFrame {
+    pub frame: usize,
+    pub start: usize,
+    pub end: usize,
+    /// As read on the ORF's own strand
+    pub start_codon: String,
+    /// Without the stop; the first residue is always the initiator M
+    pub protein: String,
+}
 
-    translate_frames_for_sequence(dna, &mut translations);
+impl OpenReadingFrame {
+    pub fn frame_label(&self) -> &'static str {
+        READING_FRAME_LABELS[self.frame]
+    }
 
-    let revcomp = crate::sequence::conversion::get_reverse_complement(dna);
-    translate_frames_for_sequence(&revcomp, &mut translations);
+    pub fn is_reverse(&self) -> bool {
+        self.frame >= 3
+    }
 
-    Ok(translations)
+    pub fn nucleotide_len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn contains(&self, other: &OpenReadingFrame) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
 }
 
-pub fn find_longest_orf(dna: &str) -> Result<(String, usize, usize), String> {
-    let mut longest_orf = String::new();
-    let mut longest_start = 0;
-    let mut longest_end = 0;
-
-    let frames = find_reading_frames_simple(dna);
-
-    for frame_dna in &frames {
-        let mut current_orf = String::new();
-        let mut in_orf = false;
-        let mut orf_start = 0;
-
-        for i in (0..frame_dna.len()).step_by(3) {
-            if i + 2 < frame_dna.len() {
-                let codon = &frame_dna[i..i+3].to_uppercase();
-
-                if codon == "ATG" && !in_orf {
-                    in_orf = true;
-                    orf_start = i;
-                    current_orf = "M".to_string();
-                } else if matches!(codon.as_str(), "TAA" | "TAG" | "TGA") && in_orf {
-                    if current_orf.len() > longest_orf.len() {
-                        longest_orf = current_orf.clone();
-                        longest_start = orf_start;
-                        longest_end = i + 3;
-                    }
-                    current_orf.clear();
-                    in_orf = false;
-                } else if in_orf {
-                    if let Ok(codon_seq) = codon.parse::<Seq<Dna>>() {
-                        if codon_seq.len() == 3 {
-                            let amino = STANDARD.to_amino(&codon_seq);
-                            let amino_str = amino.to_string();
-                            if amino_str != "*" {
-                                current_orf.push_str(&amino_str);
-                            }
-                        }
-                    }
-                }
+/// Finds every ORF that opens with one of `options`' start codons and ends in
+/// one of `code`'s stops, in all six frames and `READING_FRAME_LABELS` order.
+/// Each start gives its own ORF, so nested starts sharing a stop are all
+/// reported; ORFs that run off the end of the sequence are not.
+pub fn find_open_reading_frames(dna: &str, code: &GeneticCode, options: &OrfOptions) -> Result<Vec<OpenReadingFrame>, String> {
+    let dna = dna.to_uppercase();
+    let revcomp = get_reverse_complement(&dna);
+    let translations = translate_all_reading_frames(&dna, code)?;
+    let mut orfs = Vec::new();
+
+    for (frame, translation) in translations.iter().enumerate() {
+        let offset = frame % 3;
+        let strand = if frame < 3 { &dna } else { &revcomp };
+        let codons: Vec<&[u8]> = frame_slice(strand, offset).as_bytes().chunks(3).collect();
+
+        // Index of the first stop at or after each codon, filled from the 3' end
+        let mut next_stop = vec![None; codons.len()];
+        let mut stop = None;
+        for (codon_index, amino) in translation.bytes().enumerate().rev() {
+            if amino == b'*' {
+                stop = Some(codon_index);
             }
+            next_stop[codon_index] = stop;
+        }
+
+        for (first_codon, codon) in codons.iter().enumerate() {
+            let Some(stop_codon) = next_stop[first_codon].filter(|_| options.is_start(codon)) else {
+                continue;
+            };
+            if stop_codon - first_codon < options.min_length.max(1) {
+                continue;
+            }
+
+            let frame_start = offset + first_codon * 3;
+            let frame_end = offset + (stop_codon + 1) * 3;
+            let (start, end) = if frame < 3 {
+                (frame_start, frame_end)
+            } else {
+                (dna.len() - frame_end, dna.len() - frame_start)
+            };
+            orfs.push(OpenReadingFrame {
+                frame,
+                start,
+                end,
+                start_codon: String::from_utf8_lossy(codon).into_owned(),
+                // Alternative starts are still decoded as methionine
+                protein: format!("M{}", &translation[first_codon + 1..stop_codon]),
+            });
         }
     }
 
-    Ok((longest_orf, longest_start, longest_end))
+    Ok(orfs)
+}
+
+/// The longest ORF's protein with its start and end offsets within its frame.
+pub fn find_longest_orf(dna: &str, code: &GeneticCode, options: &OrfOptions) -> Result<(String, usize, usize), String> {
+    let longest = find_open_reading_frames(dna, code, options)?
+        .into_iter()
+        .fold(None, |longest: Option<OpenReadingFrame>, orf| match longest {
+            Some(current) if current.protein.len() >= orf.protein.len() => Some(current),
+            _ => Some(orf),
+        });
+
+    Ok(match longest {
+        Some(orf) if orf.frame < 3 => (orf.protein, orf.start - orf.frame, orf.end - orf.frame),
+        Some(orf) => {
+            let offset = orf.frame % 3;
+            let (start, end) = (dna.len() - orf.end, dna.len() - orf.start);
+            (orf.protein, start - offset, end - offset)
+        },
+        None => (String::new(), 0, 0),
+    })
 }
 
 pub fn calculate_codon_usage(dna: &str) -> Result<std::collections::HashMap<String, usize>, String> {
@@ -105,21 +202,29 @@ pub fn calculate_codon_usage(dna: &str) -> Result<std::collections::HashMap<Stri
     Ok(codon_counts)
 }
 
-fn find_reading_frames_simple(dna: &str) -> Vec<String> {
-    let mut frames = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for offset in 0..3 {
-        if offset < dna.len() {
-            frames.push(dna[offset..].to_string());
-        }
-    }
+    #[test]
+    fn test_nested_near_cognate_orfs() {
+        let code = GeneticCode::standard();
+        let dna = "CTGAAAATGCCCTAA";
 
-    let revcomp = crate::sequence::conversion::get_reverse_complement(dna);
-    for offset in 0..3 {
-        if offset < revcomp.len() {
-            frames.push(revcomp[offset..].to_string());
-        }
-    }
+        let atg_only = find_open_reading_frames(dna, code, &OrfOptions::default()).unwrap();
+        assert_eq!(atg_only.len(), 1);
+        assert_eq!((atg_only[0].start, atg_only[0].end, atg_only[0].protein.as_str()), (6, 15, "MP"));
+
+        let orfs = find_open_reading_frames(dna, code, &OrfOptions::near_cognate()).unwrap();
+        assert_eq!(orfs.len(), 2);
+        assert_eq!((orfs[0].start_codon.as_str(), orfs[0].protein.as_str()), ("CTG", "MKMP"));
+        assert!(orfs[0].contains(&orfs[1]));
 
-    frames
+        let reverse = find_open_reading_frames(&get_reverse_complement(dna), code, &OrfOptions::near_cognate().with_min_length(3)).unwrap();
+        assert_eq!(reverse.len(), 1);
+        assert_eq!((reverse[0].frame_label(), reverse[0].start, reverse[0].end), ("-1", 0, 15));
+
+        assert_eq!(parse_start_codon("cug").unwrap(), "CTG");
+        assert!(parse_start_codon("CTGA").is_err());
+    }
 }
@@ -0,0 +1,334 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::Utc;
+use clap::ValueEnum;
+use log::{info, debug};
+use serde_json::{json, Map, Value};
+use crate::app::{App, MatchMode, SearchField};
+use crate::history::EditState;
+use crate::protein::get_data_dir;
+use crate::sequence::{GeneticCode, OrfOptions, is_iupac_nucleotide, parse_start_codon};
+
+/// Bumped whenever the layout of the session file changes.
+pub const SESSION_VERSION: u64 = 1;
+pub const SESSION_FILE: &str = "session.json";
+
+/// Where the TUI saves its session when no `--session` file is given.
+pub fn default_session_path() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(get_data_dir()?.join(SESSION_FILE))
+}
+
+/// Which panels were open when the session was saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionLayout {
+    pub show_orf_panel: bool,
+    pub show_protein_searcher: bool,
+    pub show_protein_detail: bool,
+    pub multi_search_mode: bool,
+    pub search_field: SearchField,
+}
+
+/// Proteins a session points at, by id. They can only be looked up once the
+/// dataset has loaded, so `App` holds on to them until then.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RestoredProteins {
+    /// The pinned match, `closest_protein`
+    pub pinned: Option<String>,
+    /// The protein highlighted in the searcher or shown in its detail view
+    pub selected: Option<String>,
+    pub show_detail: bool,
+}
+
+/// Everything needed to pick up the TUI where it was left.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub editor: EditState,
+    pub overwrite_mode: bool,
+    pub undo: Vec<EditState>,
+    pub redo: Vec<EditState>,
+    pub genetic_code: &'static GeneticCode,
+    pub orf_options: OrfOptions,
+    pub match_mode: MatchMode,
+    pub search_filters: Vec<(SearchField, String)>,
+    pub proteins: RestoredProteins,
+    pub layout: SessionLayout,
+}
+
+impl Session {
+    pub fn capture(app: &App) -> Session {
+        let mut search_filters: Vec<(SearchField, String)> = app.search_filters.iter()
+            .map(|(field, value)| (*field, value.clone()))
+            .collect();
+        search_filters.sort_by_key(|(field, _)| field.key());
+
+        let selected = if app.show_protein_detail {
+            app.detailed_protein.as_ref()
+        } else if app.show_protein_searcher {
+            app.filtered_proteins.get(app.selected_protein_index)
+        } else {
+            None
+        };
+
+        Session {
+            editor: EditState {
+                input: app.input.clone(),
+                complementary: app.complementary.clone(),
+                is_positive_strand: app.is_positive_strand,
+                cursor: app.cursor,
+            },
+            overwrite_mode: app.overwrite_mode,
+            undo: app.history.undo.clone(),
+            redo: app.history.redo.clone(),
+            genetic_code: app.genetic_code,
+            orf_options: app.orf_options.clone(),
+            match_mode: app.match_mode,
+            search_filters,
+            proteins: RestoredProteins {
+                pinned: app.closest_protein.as_ref().map(|protein| protein.id.clone()),
+                selected: selected.map(|protein| protein.id.clone()),
+                show_detail: app.show_protein_detail && selected.is_some(),
+            },
+            layout: SessionLayout {
+                show_orf_panel: app.show_orf_panel,
+                show_protein_searcher: app.show_protein_searcher,
+                show_protein_detail: app.show_protein_detail,
+                multi_search_mode: app.multi_search_mode,
+                search_field: app.searcher_field,
+            },
+        }
+    }
+
+    /// Puts the session into `app`. Proteins are re-selected by
+    /// `App::resolve_restored_proteins` once the dataset is available.
+    pub fn restore(self, app: &mut App) {
+        info!("Restoring session: {} nt, {} undo step(s)", self.editor.input.len(), self.undo.len());
+
+        app.genetic_code = self.genetic_code;
+        app.orf_options = self.orf_options;
+        app.match_mode = self.match_mode;
+        app.set_input(&self.editor.input);
+        app.complementary = self.editor.complementary;
+        app.is_positive_strand = self.editor.is_positive_strand;
+        app.cursor = self.editor.cursor;
+        app.overwrite_mode = self.overwrite_mode;
+        app.update_sequences();
+        app.protein_match_needed = true;
+        app.history.undo = self.undo;
+        app.history.redo = self.redo;
+
+        app.search_filters = self.search_filters.into_iter().collect();
+        app.multi_search_mode = self.layout.multi_search_mode;
+        app.searcher_field = self.layout.search_field;
+        app.selected_search_field = SearchField::ALL.iter().position(|field| *field == self.layout.search_field).unwrap_or(0);
+        app.show_orf_panel = self.layout.show_orf_panel;
+        app.show_protein_searcher = self.layout.show_protein_searcher;
+        app.restored_proteins = Some(self.proteins);
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "version": SESSION_VERSION,
+            "saved_at": Utc::now().to_rfc3339(),
+            "editor": {
+                "state": edit_state_to_json(&self.editor),
+                "overwrite_mode": self.overwrite_mode,
+            },
+            "history": {
+                "undo": self.undo.iter().map(edit_state_to_json).collect::<Vec<_>>(),
+                "redo": self.redo.iter().map(edit_state_to_json).collect::<Vec<_>>(),
+            },
+            "genetic_code": self.genetic_code.id,
+            "orf": {
+                "start_codons": self.orf_options.start_codons,
+                "min_length": self.orf_options.min_length,
+            },
+            "match_mode": self.match_mode.to_possible_value().map(|value| value.get_name().to_string()),
+            "search_filters": self.search_filters.iter()
+                .map(|(field, value)| (field.key().to_string(), json!(value)))
+                .collect::<Map<_, _>>(),
+            "proteins": {
+                "pinned": self.proteins.pinned,
+                "selected": self.proteins.selected,
+                "show_detail": self.proteins.show_detail,
+            },
+            "layout": {
+                "show_orf_panel": self.layout.show_orf_panel,
+                "show_protein_searcher": self.layout.show_protein_searcher,
+                "show_protein_detail": self.layout.show_protein_detail,
+                "multi_search_mode": self.layout.multi_search_mode,
+                "search_field": self.layout.search_field.key(),
+            },
+        })
+    }
+
+    pub fn from_json(value: &Value) -> Result<Session, String> {
+        let version = field(value, "version")?.as_u64().ok_or("session version is not a number")?;
+        if version != SESSION_VERSION {
+            return Err(format!("unsupported session version {version} (expected {SESSION_VERSION})"));
+        }
+
+        let editor = field(value, "editor")?;
+        let history = field(value, "history")?;
+        let orf = field(value, "orf")?;
+        let proteins = field(value, "proteins")?;
+        let layout = field(value, "layout")?;
+
+        let code_id = field(value, "genetic_code")?.as_u64().ok_or("genetic_code is not a number")?;
+        let match_mode = string(value, "match_mode")?;
+
+        Ok(Session {
+            editor: edit_state_from_json(field(editor, "state")?)?,
+            overwrite_mode: boolean(editor, "overwrite_mode")?,
+            undo: edit_states_from_json(history, "undo")?,
+            redo: edit_states_from_json(history, "redo")?,
+            genetic_code: u8::try_from(code_id).ok().and_then(GeneticCode::by_id)
+                .ok_or_else(|| format!("unknown genetic code {code_id}"))?,
+            orf_options: OrfOptions {
+                start_codons: field(orf, "start_codons")?.as_array().ok_or("start_codons is not a list")?
+                    .iter()
+                    .map(|codon| codon.as_str().ok_or_else(|| "start codon is not a string".to_string()).and_then(parse_start_codon))
+                    .collect::<Result<_, _>>()?,
+                min_length: field(orf, "min_length")?.as_u64().ok_or("min_length is not a number")? as usize,
+            },
+            match_mode: MatchMode::from_str(match_mode, true)?,
+            search_filters: field(value, "search_filters")?.as_object().ok_or("search_filters is not an object")?
+                .iter()
+                .map(|(key, value)| Ok((key.parse()?, value.as_str().ok_or("filter value is not a string")?.to_string())))
+                .collect::<Result<_, String>>()?,
+            proteins: RestoredProteins {
+                pinned: optional_string(proteins, "pinned")?,
+                selected: optional_string(proteins, "selected")?,
+                show_detail: boolean(proteins, "show_detail")?,
+            },
+            layout: SessionLayout {
+                show_orf_panel: boolean(layout, "show_orf_panel")?,
+                show_protein_searcher: boolean(layout, "show_protein_searcher")?,
+                show_protein_detail: boolean(layout, "show_protein_detail")?,
+                multi_search_mode: boolean(layout, "multi_search_mode")?,
+                search_field: string(layout, "search_field")?.parse()?,
+            },
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Session, Box<dyn Error>> {
+        debug!("Loading session from {path:?}");
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let value: Value = serde_json::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok(Session::from_json(&value).map_err(|e| format!("{}: {e}", path.display()))?)
+    }
+
+    /// Writes next to `path` first and renames, so a crash never leaves half a session.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let part_file = path.with_extension("part");
+        fs::write(&part_file, serde_json::to_string_pretty(&self.to_json())?)?;
+        fs::rename(&part_file, path)?;
+        info!("Saved session to {path:?}");
+        Ok(())
+    }
+}
+
+fn edit_state_to_json(state: &EditState) -> Value {
+    json!({
+        "input": state.input,
+        "complementary": state.complementary,
+        "is_positive_strand": state.is_positive_strand,
+        "cursor": state.cursor,
+    })
+}
+
+fn edit_state_from_json(value: &Value) -> Result<EditState, String> {
+    let state = EditState {
+        input: string(value, "input")?.to_string(),
+        complementary: string(value, "complementary")?.to_string(),
+        is_positive_strand: boolean(value, "is_positive_strand")?,
+        cursor: field(value, "cursor")?.as_u64().ok_or("cursor is not a number")? as usize,
+    };
+
+    let strands = [&state.input, &state.complementary];
+    if strands.iter().any(|strand| !strand.chars().all(is_iupac_nucleotide)) {
+        return Err("editor contents are not nucleotides".to_string());
+    }
+    if state.input.len() != state.complementary.len() || state.cursor > state.input.len() {
+        return Err("editor strands and cursor do not line up".to_string());
+    }
+    Ok(state)
+}
+
+fn edit_states_from_json(value: &Value, key: &str) -> Result<Vec<EditState>, String> {
+    field(value, key)?.as_array().ok_or_else(|| format!("{key} is not a list"))?
+        .iter()
+        .map(edit_state_from_json)
+        .collect()
+}
+
+fn field<'a>(value: &'a Value, key: &str) -> Result<&'a Value, String> {
+    value.get(key).ok_or_else(|| format!("missing '{key}'"))
+}
+
+fn string<'a>(value: &'a Value, key: &str) -> Result<&'a str, String> {
+    field(value, key)?.as_str().ok_or_else(|| format!("{key} is not a string"))
+}
+
+fn optional_string(value: &Value, key: &str) -> Result<Option<String>, String> {
+    match field(value, key)? {
+        Value::Null => Ok(None),
+        other => other.as_str().map(|text| Some(text.to_string())).ok_or_else(|| format!("{key} is not a string")),
+    }
+}
+
+fn boolean(value: &Value, key: &str) -> Result<bool, String> {
+    field(value, key)?.as_bool().ok_or_else(|| format!("{key} is not true or false"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protein::{DatasetSource, load_dataset_sources};
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/smprot_sample.txt");
+
+    #[test]
+    fn test_session_round_trip_restores_editor_and_pinned_match() {
+        let dataset = load_dataset_sources(&[DatasetSource::in_memory("fixture", FIXTURE.as_bytes().to_vec())], None).unwrap();
+        let pinned = dataset.proteins[3].clone();
+
+        let mut app = App::new();
+        app.set_input("ATGAAA");
+        app.on_key('C');
+        app.toggle_strand_mode();
+        app.toggle_orf_panel();
+        app.search_filters.insert(SearchField::Species, "Homo".to_string());
+        app.multi_search_mode = true;
+        app.closest_protein = Some(pinned.clone());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join(SESSION_FILE);
+        Session::capture(&app).save(&path).unwrap();
+
+        let mut restored = App::new();
+        Session::load(&path).unwrap().restore(&mut restored);
+        assert_eq!((&restored.input, &restored.complementary), (&app.input, &app.complementary));
+        assert!(!restored.is_positive_strand && restored.show_orf_panel && restored.multi_search_mode);
+        assert_eq!(restored.search_filters, app.search_filters);
+
+        restored.set_small_proteins(dataset.proteins);
+        restored.is_loading_proteins = false;
+        restored.perform_protein_matching_if_needed();
+        assert_eq!(restored.closest_protein.as_ref().map(|protein| &protein.id), Some(&pinned.id));
+
+        restored.undo();
+        restored.undo();
+        assert_eq!((restored.input.as_str(), restored.is_positive_strand), ("ATGAAA", true));
+    }
+
+    #[test]
+    fn test_other_session_versions_are_rejected() {
+        let mut value = Session::capture(&App::new()).to_json();
+        value["version"] = json!(SESSION_VERSION + 1);
+        assert_eq!(Session::from_json(&value).unwrap_err(), format!("unsupported session version 2 (expected {SESSION_VERSION})"));
+    }
+}
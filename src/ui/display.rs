@@ -1,8 +1,9 @@
+use std::ops::Range;
 use ratatui::{
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
 };
-use crate::sequence::dna_codon_to_amino_acid;
+use crate::sequence::{GeneticCode, dna_codon_to_amino_acid};
 
 pub fn format_triplets(sequence: &str) -> String {
     let mut result = String::new();
@@ -19,8 +20,62 @@ pub fn format_triplets(sequence: &str) -> String {
     result
 }
 
-pub fn create_codon_completion_display(partial_codon: &str) -> Vec<Line<'static>> {
-    let mut lines = Vec::new();
+/// What the sequence tracks highlight: the cursor, the codon it is in and the selection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditHighlight {
+    pub cursor: usize,
+    pub selection: Option<Range<usize>>,
+    pub show_cursor: bool,
+}
+
+impl EditHighlight {
+    fn codon(&self) -> Range<usize> {
+        let start = self.cursor - self.cursor % 3;
+        start..start + 3
+    }
+}
+
+/// `sequence` in space-separated triplets like `format_triplets`, with the
+/// edited codon, the selection and the cursor styled on top of `color`.
+pub fn create_sequence_spans(sequence: &str, color: Color, highlight: &EditHighlight) -> Vec<Span<'static>> {
+    let codon = highlight.codon();
+    let mut spans = Vec::new();
+
+    for (position, base) in sequence.chars().enumerate() {
+        if position > 0 && position % 3 == 0 {
+            spans.push(Span::raw(" "));
+        }
+
+        let mut style = Style::default().fg(color);
+        if highlight.selection.as_ref().is_some_and(|selection| selection.contains(&position)) {
+            style = style.bg(Color::Blue);
+        } else if codon.contains(&position) {
+            style = style.bg(Color::DarkGray);
+        }
+        if highlight.show_cursor && position == highlight.cursor {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        spans.push(Span::styled(base.to_string(), style));
+    }
+
+    if highlight.show_cursor && highlight.cursor >= sequence.chars().count() {
+        spans.push(Span::styled("_", Style::default().fg(color).add_modifier(Modifier::SLOW_BLINK)));
+    }
+
+    spans
+}
+
+fn amino_color(amino: &str) -> Color {
+    if amino == "*" { Color::Red } else { Color::White }
+}
+
+/// The next-base guide for `partial_codon`, translated with `code`; stops are
+/// shown in red and codons that can start translation are marked.
+pub fn create_codon_completion_display(partial_codon: &str, code: &GeneticCode) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(vec![
+        Span::raw("Table: "),
+        Span::styled(code.to_string(), Style::default().fg(Color::Yellow)),
+    ])];
 
     let mut current_codon_text = vec![
         Span::raw("Current codon: "),
@@ -70,7 +125,7 @@ pub fn create_codon_completion_display(partial_codon: &str) -> Vec<Line<'static>
                 let mut possible_aminos = Vec::new();
                 for &third_base in &nucleotides {
                     let codon = format!("{first_base}{second_base}{third_base}");
-                    let amino = dna_codon_to_amino_acid(&codon);
+                    let amino = dna_codon_to_amino_acid(&codon, code);
                     possible_aminos.push(amino);
                 }
 
@@ -81,7 +136,7 @@ pub fn create_codon_completion_display(partial_codon: &str) -> Vec<Line<'static>
                     if i > 0 {
                         colored_amino_list.push(Span::raw("/"));
                     }
-                    colored_amino_list.push(Span::styled(amino.clone(), Style::default().fg(Color::White)));
+                    colored_amino_list.push(Span::styled(amino.clone(), Style::default().fg(amino_color(amino))));
                 }
                 row.extend(colored_amino_list);
 
@@ -102,12 +157,16 @@ pub fn create_codon_completion_display(partial_codon: &str) -> Vec<Line<'static>
                 let codon = format!("{first_base}{second_base}{third_base}");
                 let display_third = if third_base == 'T' { 'U' } else { third_base };
 
-                let amino = dna_codon_to_amino_acid(&codon);
+                let amino = dna_codon_to_amino_acid(&codon, code);
 
-                lines.push(Line::from(vec![
+                let mut row = vec![
                     Span::styled(format!("{display_first}{display_second}{display_third} → "), Style::default().fg(Color::Cyan)),
-                    Span::styled(amino, Style::default().fg(Color::White)),
-                ]));
+                    Span::styled(amino.clone(), Style::default().fg(amino_color(&amino))),
+                ];
+                if code.is_start(codon.as_bytes()) {
+                    row.push(Span::styled(" (start)", Style::default().fg(Color::Green)));
+                }
+                lines.push(Line::from(row));
             }
         },
         _ => {
@@ -1,4 +1,6 @@
 pub mod display;
+pub mod orf_map;
 pub mod renderer;
-pub use display::{format_triplets, create_codon_completion_display};
-pub use renderer::render_ui;
+pub use display::{EditHighlight, format_triplets, create_codon_completion_display, create_sequence_spans};
+pub use orf_map::{OrfMapGeometry, create_orf_map_lines};
+pub use renderer::{render_ui, orf_map_geometry};
@@ -0,0 +1,141 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use crate::App;
+use crate::sequence::{READING_FRAME_LABELS, find_reading_frames};
+
+const LABEL_WIDTH: u16 = 4;
+
+/// Maps the six frame tracks inside the ORF map block to sequence positions.
+/// Long sequences are scaled so each column covers several bases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrfMapGeometry {
+    /// Inside the block's borders, labels included
+    pub area: Rect,
+    pub sequence_len: usize,
+    pub bases_per_column: usize,
+}
+
+impl OrfMapGeometry {
+    pub fn new(area: Rect, sequence_len: usize) -> Self {
+        let track_width = area.width.saturating_sub(LABEL_WIDTH).max(1) as usize;
+        OrfMapGeometry { area, sequence_len, bases_per_column: sequence_len.div_ceil(track_width).max(1) }
+    }
+
+    pub fn track_columns(&self) -> usize {
+        self.sequence_len.div_ceil(self.bases_per_column)
+    }
+
+    /// Track column, counted from the end of the labels, holding `position`.
+    pub fn column_of(&self, position: usize) -> usize {
+        position / self.bases_per_column
+    }
+
+    /// The frame and first sequence position under a terminal cell, if it lies on a track.
+    pub fn hit(&self, column: u16, row: u16) -> Option<(usize, usize)> {
+        let track_start = self.area.x + LABEL_WIDTH;
+        if row < self.area.y || column < track_start || column >= self.area.x + self.area.width {
+            return None;
+        }
+
+        let frame = (row - self.area.y) as usize;
+        let position = (column - track_start) as usize * self.bases_per_column;
+        (frame < READING_FRAME_LABELS.len() && position < self.sequence_len).then_some((frame, position))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrackMark {
+    Empty,
+    Orf,
+    SelectedOrf,
+    Start,
+    Stop,
+}
+
+impl TrackMark {
+    fn span(self, is_reverse: bool, is_cursor: bool) -> Span<'static> {
+        let (symbol, style) = match self {
+            TrackMark::Empty => ("·", Style::default().fg(Color::DarkGray)),
+            TrackMark::Orf => ("━", Style::default().fg(Color::Cyan)),
+            TrackMark::SelectedOrf => ("━", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
+            TrackMark::Start if is_reverse => ("◀", Style::default().fg(Color::Green)),
+            TrackMark::Start => ("▶", Style::default().fg(Color::Green)),
+            TrackMark::Stop => ("┃", Style::default().fg(Color::Red)),
+        };
+        let style = if is_cursor { style.bg(Color::DarkGray) } else { style };
+        Span::styled(symbol, style)
+    }
+}
+
+/// One line per reading frame with ORF spans, start and stop codons, and the
+/// cursor column highlighted. Codons are marked at their 5' base.
+pub fn create_orf_map_lines(app: &App, geometry: &OrfMapGeometry) -> Vec<Line<'static>> {
+    let sequence_len = geometry.sequence_len;
+    let columns = geometry.track_columns();
+    let cursor_column = geometry.column_of(app.cursor_position().min(sequence_len.saturating_sub(1)));
+    let frames = find_reading_frames(&app.input.to_uppercase());
+
+    READING_FRAME_LABELS.iter().enumerate().map(|(frame, label)| {
+        let is_reverse = frame >= 3;
+        let mut marks = vec![TrackMark::Empty; columns];
+
+        for (orf_index, orf) in app.orfs.iter().enumerate().filter(|(_, orf)| orf.frame == frame) {
+            let mark = if app.selected_orf == Some(orf_index) { TrackMark::SelectedOrf } else { TrackMark::Orf };
+            let span = &mut marks[geometry.column_of(orf.start)..=geometry.column_of(orf.end - 1)];
+            for column_mark in span.iter_mut().filter(|column_mark| **column_mark != TrackMark::SelectedOrf) {
+                *column_mark = mark;
+            }
+        }
+
+        if let Some(frame_dna) = frames.get(frame) {
+            for (codon_index, codon) in frame_dna.as_bytes().chunks_exact(3).enumerate() {
+                let frame_position = frame % 3 + codon_index * 3;
+                let position = if is_reverse { sequence_len - 1 - frame_position } else { frame_position };
+                if app.genetic_code.is_stop(codon) {
+                    marks[geometry.column_of(position)] = TrackMark::Stop;
+                } else if app.orf_options.is_start(codon) {
+                    marks[geometry.column_of(position)] = TrackMark::Start;
+                }
+            }
+        }
+
+        let mut spans = vec![Span::styled(format!("{label:<width$}", width = LABEL_WIDTH as usize),
+                                          Style::default().fg(if is_reverse { Color::Yellow } else { Color::Green }))];
+        spans.extend(marks.iter().enumerate().map(|(column, mark)| mark.span(is_reverse, column == cursor_column)));
+        Line::from(spans)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_click_selects_innermost_orf() {
+        let mut app = App::new();
+        app.orf_options = crate::sequence::OrfOptions::near_cognate();
+        app.set_input("CTGAAAATGCCCTAA");
+        assert_eq!(app.orfs.len(), 2);
+
+        let geometry = OrfMapGeometry::new(Rect::new(10, 5, 40, 6), app.input.len());
+        assert_eq!(geometry.bases_per_column, 1);
+        assert_eq!(geometry.hit(9 + LABEL_WIDTH, 5), None);
+
+        let (frame, position) = geometry.hit(10 + LABEL_WIDTH + 7, 5).unwrap();
+        app.select_orf_at(frame, position);
+        assert_eq!(app.selected_orf().unwrap().protein, "MP");
+
+        app.select_orf_at(frame, 2);
+        assert_eq!(app.selected_orf().unwrap().protein, "MKMP");
+        // Typing past the stop leaves the selected ORF in place
+        app.on_key('A');
+        assert_eq!(app.selected_orf().unwrap().protein, "MKMP");
+
+        let lines = create_orf_map_lines(&app, &OrfMapGeometry::new(geometry.area, app.input.len()));
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0].spans.len(), 1 + app.input.len());
+    }
+}
@@ -1,19 +1,42 @@
+use std::rc::Rc;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Gauge},
+    widgets::{Block, Borders, Cell, Paragraph, Gauge, Row, Sparkline, SparklineBar, Table, TableState},
     Frame,
 };
 
 use crate::{
     App,
-    protein::DatasetProgress,
+    MatchMode,
+    app::FilePromptKind,
+    protein::{DatasetProgress, MassType, Profile, ProteinProperties, SmallProtein, TopologyPrediction, PKA_BJELLQVIST, format_blocks, peptide_mass},
     sequence::*,
-    ui::{format_triplets, create_codon_completion_display},
+    ui::{EditHighlight, create_sequence_spans, create_codon_completion_display, OrfMapGeometry, create_orf_map_lines},
 };
 
 // Helper functions to eliminate code duplication
+fn format_start_codon(protein: &SmallProtein) -> String {
+    protein.start_codon.as_ref().map_or_else(|| "NA".to_string(), ToString::to_string)
+}
+
+fn format_protein_masses(protein: &SmallProtein) -> String {
+    let mass = |mass_type| peptide_mass(&protein.aa_seq, mass_type, &[]);
+    match (mass(MassType::Average), mass(MassType::Monoisotopic)) {
+        (Ok(average), Ok(monoisotopic)) => format!("{average:.2} Da average, {monoisotopic:.4} Da monoisotopic"),
+        (Err(e), _) | (_, Err(e)) => format!("NA ({e})"),
+    }
+}
+
+fn format_phylo_csf(protein: &SmallProtein, precision: Option<usize>) -> String {
+    match (protein.phylo_csf_mean, precision) {
+        (Some(phylo), Some(precision)) => format!("{phylo:.precision$}"),
+        (Some(phylo), None) => phylo.to_string(),
+        (None, _) => "NA".to_string(),
+    }
+}
+
 fn create_conditional_style(condition: bool, true_color: Color, false_color: Color) -> Style {
     if condition {
         Style::default().fg(true_color)
@@ -69,6 +92,32 @@ pub fn render_ui(f: &mut Frame, app: &App) {
         return;
     }
 
+    let (chunks, right_panel) = main_layout(f.area());
+
+    render_title(f, app, chunks[0]);
+    render_sequence_strands(f, app, &chunks[1..4]);
+    render_orf_map(f, app, chunks[4]);
+    render_amino_acid_section(f, app, chunks[5]);
+    render_right_panel(f, app, right_panel);
+    render_status_bar(f, app, chunks[6]);
+
+    if app.show_protein_searcher {
+        if app.show_protein_detail {
+            render_protein_detail(f, app);
+        } else {
+            render_protein_searcher(f, app);
+        }
+    } else if app.show_pmf_results {
+        render_pmf_results(f, app);
+    }
+
+    if let Some(path) = &app.file_prompt {
+        render_file_prompt(f, path, app.file_prompt_kind);
+    }
+}
+
+/// The left column's sections, top to bottom, and the right panel.
+fn main_layout(area: Rect) -> (Rc<[Rect]>, Rect) {
     let main_horizontal_split = Layout::default()
         .direction(Direction::Horizontal)
         .margin(2)
@@ -76,7 +125,7 @@ pub fn render_ui(f: &mut Frame, app: &App) {
             Constraint::Percentage(70),
             Constraint::Percentage(30),
         ])
-        .split(f.area());
+        .split(area);
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -85,24 +134,32 @@ pub fn render_ui(f: &mut Frame, app: &App) {
             Constraint::Length(5),
             Constraint::Length(5),
             Constraint::Length(5),
+            Constraint::Length(8),
             Constraint::Min(12),
             Constraint::Length(3),
         ])
         .split(main_horizontal_split[0]);
 
-    render_title(f, app, chunks[0]);
-    render_sequence_strands(f, app, &chunks[1..4]);
-    render_amino_acid_section(f, app, chunks[4]);
-    render_right_panel(f, app, main_horizontal_split[1]);
-    render_status_bar(f, app, chunks[5]);
+    (chunks, main_horizontal_split[1])
+}
 
-    if app.show_protein_searcher {
-        if app.show_protein_detail {
-            render_protein_detail(f, app);
-        } else {
-            render_protein_searcher(f, app);
-        }
-    }
+/// Where the ORF map tracks are drawn for a terminal of size `area`, for mapping mouse clicks.
+pub fn orf_map_geometry(app: &App, area: Rect) -> OrfMapGeometry {
+    let (chunks, _) = main_layout(area);
+    OrfMapGeometry::new(Block::default().borders(Borders::ALL).inner(chunks[4]), app.input.len())
+}
+
+fn render_orf_map(f: &mut Frame, app: &App, area: Rect) {
+    let geometry = orf_map_geometry(app, f.area());
+    let title = match app.selected_orf() {
+        Some(orf) => format!("ORF Map: {} {}-{} selected (Tab/Shift+Tab, Esc to clear)", orf.frame_label(), orf.start + 1, orf.end),
+        None if geometry.bases_per_column > 1 => format!("ORF Map ({} nt per column, click an ORF)", geometry.bases_per_column),
+        None => "ORF Map (click an ORF or Tab to select)".to_string(),
+    };
+
+    let map_widget = Paragraph::new(create_orf_map_lines(app, &geometry))
+        .block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(map_widget, area);
 }
 
 fn render_title(f: &mut Frame, app: &App, area: Rect) {
@@ -116,7 +173,25 @@ fn render_title(f: &mut Frame, app: &App, area: Rect) {
         Span::raw("   "),
     ];
 
+    if !app.parse_report.is_clean() {
+        spans.push(Span::styled(
+            format!("({} rows rejected)", app.parse_report.rejected.len()),
+            Style::default().fg(Color::Yellow)
+        ));
+        spans.push(Span::raw("   "));
+    }
+
     spans.extend(create_strand_mode_spans(app.is_positive_strand));
+    spans.push(Span::raw("   Match: "));
+    spans.push(match app.match_mode {
+        MatchMode::Nucleotide => Span::styled("DNA", Style::default().fg(Color::Green)),
+        MatchMode::Protein => Span::styled(
+            format!("Protein ({})", app.protein_scoring.matrix.name()),
+            Style::default().fg(Color::Magenta),
+        ),
+    });
+    spans.push(Span::raw("   Code: "));
+    spans.push(Span::styled(app.genetic_code.to_string(), Style::default().fg(Color::Yellow)));
 
     let title_widget = Paragraph::new(vec![Line::from(spans)])
         .block(Block::default().borders(Borders::ALL));
@@ -124,32 +199,31 @@ fn render_title(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_sequence_strands(f: &mut Frame, app: &App, areas: &[Rect]) {
-    let formatted_input = format_triplets(&app.input);
-    let input_text = Line::from(vec![
-        Span::raw("Positive Strand: "),
-        Span::styled(&formatted_input, Style::default().fg(Color::Green)),
-    ]);
-    let input_widget = Paragraph::new(vec![input_text])
-        .block(Block::default().borders(Borders::ALL))
+    let highlight = |show_cursor| EditHighlight { cursor: app.cursor, selection: app.selection_range(), show_cursor };
+
+    let mode = if app.overwrite_mode { "OVR" } else { "INS" };
+    let editor_title = match app.selection_range() {
+        Some(selection) => format!("{mode} | selected {}-{} ({} nt)", selection.start + 1, selection.end, selection.len()),
+        None => format!("{mode} | cursor {} (codon {})", app.cursor + 1, app.cursor / 3 + 1),
+    };
+
+    let mut input_spans = vec![Span::raw("Positive Strand: ")];
+    input_spans.extend(create_sequence_spans(&app.input, Color::Green, &highlight(app.is_positive_strand)));
+    let input_widget = Paragraph::new(vec![Line::from(input_spans)])
+        .block(Block::default().title(editor_title).borders(Borders::ALL))
         .wrap(ratatui::widgets::Wrap { trim: true });
     f.render_widget(input_widget, areas[0]);
 
-    let formatted_complementary = format_triplets(&app.complementary);
-    let complementary_text = Line::from(vec![
-        Span::raw("Negative Strand: "),
-        Span::styled(&formatted_complementary, Style::default().fg(Color::Yellow)),
-    ]);
-    let complementary_widget = Paragraph::new(vec![complementary_text])
+    let mut complementary_spans = vec![Span::raw("Negative Strand: ")];
+    complementary_spans.extend(create_sequence_spans(&app.complementary, Color::Yellow, &highlight(!app.is_positive_strand)));
+    let complementary_widget = Paragraph::new(vec![Line::from(complementary_spans)])
         .block(Block::default().borders(Borders::ALL))
         .wrap(ratatui::widgets::Wrap { trim: true });
     f.render_widget(complementary_widget, areas[1]);
 
-    let formatted_mrna = format_triplets(&app.mrna);
-    let mrna_text = Line::from(vec![
-        Span::raw("mRNA:           "),
-        Span::styled(&formatted_mrna, Style::default().fg(Color::Magenta)),
-    ]);
-    let mrna_widget = Paragraph::new(vec![mrna_text])
+    let mut mrna_spans = vec![Span::raw("mRNA:           ")];
+    mrna_spans.extend(create_sequence_spans(&app.mrna, Color::Magenta, &highlight(false)));
+    let mrna_widget = Paragraph::new(vec![Line::from(mrna_spans)])
         .block(Block::default().borders(Borders::ALL))
         .wrap(ratatui::widgets::Wrap { trim: true });
     f.render_widget(mrna_widget, areas[2]);
@@ -167,14 +241,39 @@ fn render_amino_acid_section(f: &mut Frame, app: &App, area: Rect) {
 
     render_amino_acid_sequence(f, app, amino_chunks[0]);
     render_codon_completion(f, app, amino_chunks[1]);
-    render_protein_match(f, app, amino_chunks[2]);
+    render_protein_hits_section(f, app, amino_chunks[2]);
 }
 
 fn render_amino_acid_sequence(f: &mut Frame, app: &App, area: Rect) {
+    let sequence_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(6)])
+        .split(area);
+    let profile = app.profile(&app.query_protein(), &app.input);
+    render_profile(f, &profile, sequence_chunks[1], "l/{/}");
+    let area = sequence_chunks[0];
+
+    if let Some(orf) = app.selected_orf() {
+        let orf_widget = Paragraph::new(vec![
+            Line::from(vec![
+                Span::raw(format!("{} {}-{}, ", orf.frame_label(), orf.start + 1, orf.end)),
+                Span::styled(orf.start_codon.replace('T', "U"), Style::default().fg(Color::Green)),
+                Span::raw(format!(" start, {} aa", orf.protein.len())),
+            ]),
+            Line::from(Span::styled(orf.protein.clone(), Style::default().fg(Color::Magenta))),
+        ])
+            .block(Block::default().title("Selected ORF").borders(Borders::ALL))
+            .wrap(ratatui::widgets::Wrap { trim: true });
+        f.render_widget(orf_widget, area);
+        return;
+    }
+
     let mut amino_spans = vec![Span::raw("Amino Acids: ")];
 
-    for (amino, color) in app.amino_acids_colored.iter() {
-        amino_spans.push(Span::styled(amino, Style::default().fg(*color)));
+    let edited_codon = app.cursor / 3;
+    for (codon_index, (amino, color)) in app.amino_acids_colored.iter().enumerate() {
+        let style = Style::default().fg(*color);
+        amino_spans.push(Span::styled(amino, if codon_index == edited_codon { style.bg(Color::DarkGray) } else { style }));
     }
 
     let amino_widget = Paragraph::new(vec![Line::from(amino_spans)])
@@ -183,14 +282,116 @@ fn render_amino_acid_sequence(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(amino_widget, area);
 }
 
+/// Bars for `width` columns; profiles with fewer points than columns are stretched.
+/// A bar is highlighted when a window it averages is centred in a highlight.
+fn profile_bars(profile: &Profile, width: usize) -> Vec<SparklineBar> {
+    let (low, high) = profile.kind.range();
+    let points = profile.values.len();
+    let centre = profile.window / 2;
+
+    (0..width).map(|column| {
+        let start = column * points / width;
+        let end = ((column + 1) * points / width).max(start + 1);
+        let bucket = &profile.values[start..end];
+        let mean = bucket.iter().sum::<f64>() / bucket.len() as f64;
+        let height = ((mean - low) / (high - low) * 100.0).clamp(0.0, 100.0).round() as u64;
+        let highlighted = profile.highlights.iter()
+            .any(|region| region.start < end + centre && start + centre < region.end);
+        SparklineBar::from(height).style(highlighted.then(|| Style::default().fg(Color::Red)))
+    }).collect()
+}
+
+fn render_profile(f: &mut Frame, profile: &Profile, area: Rect, keys: &str) {
+    let mut title = format!("{} (window {}; {keys})", profile.kind.name(), profile.window);
+    if !profile.highlights.is_empty() {
+        let regions = profile.highlights.iter().map(|region| format!("{}-{}", region.start + 1, region.end)).collect::<Vec<_>>();
+        title.push_str(&format!(" TM: {}", regions.join(", ")));
+    }
+    let block = Block::default().title(title).borders(Borders::ALL);
+
+    if profile.is_empty() {
+        let message = format!("Sequence shorter than the {}-position window", profile.window);
+        let empty = Paragraph::new(Line::from(Span::styled(message, Style::default().fg(Color::DarkGray))))
+            .block(block)
+            .wrap(ratatui::widgets::Wrap { trim: true });
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let width = block.inner(area).width as usize;
+    let sparkline = Sparkline::default()
+        .block(block)
+        .data(profile_bars(profile, width))
+        .max(100)
+        .style(Style::default().fg(Color::Cyan));
+    f.render_widget(sparkline, area);
+}
+
 fn render_codon_completion(f: &mut Frame, app: &App, area: Rect) {
     let partial_codon = app.get_current_partial_codon();
-    let codon_completion = create_codon_completion_display(&partial_codon);
+    let codon_completion = create_codon_completion_display(&partial_codon, app.genetic_code);
     let codon_completion_widget = Paragraph::new(codon_completion)
         .block(Block::default().title("Codon Completion Guide").borders(Borders::ALL));
     f.render_widget(codon_completion_widget, area);
 }
 
+fn render_protein_hits_section(f: &mut Frame, app: &App, area: Rect) {
+    let hit_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(app.max_protein_hits.min(8) as u16 + 3),
+            Constraint::Min(5),
+        ])
+        .split(area);
+
+    render_protein_hits(f, app, hit_chunks[0]);
+    render_protein_match(f, app, hit_chunks[1]);
+}
+
+fn render_protein_hits(f: &mut Frame, app: &App, area: Rect) {
+    let header = Row::new(vec!["#", "ID", "Bits", "E-value", "Ident", "Cov", "Str"])
+        .style(Style::default().fg(Color::Cyan));
+
+    let rows: Vec<Row> = app.protein_hits.iter().enumerate().map(|(i, hit)| {
+        let protein = &app.small_proteins[hit.protein_index];
+        let is_active = app.active_hit.as_ref() == Some(hit);
+        Row::new(vec![
+            Cell::from(format!("{}{}", if is_active { "*" } else { " " }, i + 1)),
+            Cell::from(protein.id.clone()),
+            Cell::from(format!("{:.1}", hit.bit_score)),
+            Cell::from(format!("{:.1e}", hit.e_value)),
+            Cell::from(format!("{:.1}%", hit.identity)),
+            Cell::from(format!("{:.1}%", hit.coverage)),
+            Cell::from(match hit.frame {
+                Some(frame) => READING_FRAME_LABELS[frame],
+                None if hit.is_positive_strand => "+",
+                None => "-",
+            }),
+        ])
+        .style(create_conditional_style(hit.is_significant(app.significance_threshold), Color::White, Color::DarkGray))
+    }).collect();
+
+    let table = Table::new(rows, [
+        Constraint::Length(4),
+        Constraint::Min(10),
+        Constraint::Length(6),
+        Constraint::Length(8),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(3),
+    ])
+    .header(header)
+    .row_highlight_style(create_selection_style(true))
+    .block(Block::default()
+        .title(format!("Protein Hits ({})", app.protein_hits.len()))
+        .borders(Borders::ALL));
+
+    let mut table_state = TableState::default().with_selected(
+        (!app.protein_hits.is_empty()).then_some(app.selected_hit_index)
+    );
+    f.render_stateful_widget(table, area, &mut table_state);
+}
+
 fn render_protein_match(f: &mut Frame, app: &App, area: Rect) {
     let protein_text = if app.is_loading_proteins {
         vec![Line::from(vec![
@@ -201,7 +402,18 @@ fn render_protein_match(f: &mut Frame, app: &App, area: Rect) {
             Span::styled(error, Style::default().fg(Color::Red)),
         ])]
     } else if let Some(protein) = &app.closest_protein {
-        build_protein_info_lines(protein, &app.matching_positions)
+        build_protein_info_lines(app, protein)
+    } else if let Some(best_hit) = app.protein_hits.first() {
+        vec![
+            Line::from(vec![
+                Span::styled("No significant match", Style::default().fg(Color::Yellow)),
+            ]),
+            Line::from(vec![
+                Span::raw("Best E-value: "),
+                Span::styled(format!("{:.2e}", best_hit.e_value), Style::default().fg(Color::DarkGray)),
+                Span::raw(format!(" (threshold {:.0e})", app.significance_threshold)),
+            ]),
+        ]
     } else {
         vec![Line::from(vec![
             Span::styled("No matching protein found", Style::default().fg(Color::DarkGray)),
@@ -209,12 +421,13 @@ fn render_protein_match(f: &mut Frame, app: &App, area: Rect) {
     };
 
     let protein_widget = Paragraph::new(protein_text)
-        .block(Block::default().title("Closest Small Protein Match").borders(Borders::ALL))
+        .block(Block::default().title("Active Match").borders(Borders::ALL))
         .wrap(ratatui::widgets::Wrap { trim: true });
     f.render_widget(protein_widget, area);
 }
 
-fn build_protein_info_lines(protein: &crate::SmallProtein, matching_positions: &[bool]) -> Vec<Line<'static>> {
+fn build_protein_info_lines(app: &App, protein: &crate::SmallProtein) -> Vec<Line<'static>> {
+    let matching_positions = &app.matching_positions;
     let mut rna_seq_spans = Vec::new();
     let mut triplet_count = 0;
 
@@ -231,37 +444,142 @@ fn build_protein_info_lines(protein: &crate::SmallProtein, matching_positions: &
         }
     }
 
-    vec![
+    let mut lines = Vec::new();
+
+    if let Some(hit) = &app.active_hit {
+        let alignment = &hit.alignment;
+        lines.push(Line::from(vec![
+            Span::raw("Alignment Score: "),
+            Span::styled(alignment.score.to_string(), Style::default().fg(Color::Green)),
+            Span::raw(" Bits: "),
+            Span::styled(format!("{:.1}", hit.bit_score), Style::default().fg(Color::Green)),
+            Span::raw(" E-value: "),
+            Span::styled(
+                format!("{:.2e}", hit.e_value),
+                create_conditional_style(hit.is_significant(app.significance_threshold), Color::Green, Color::Red),
+            ),
+        ]));
+        lines.push(Line::from(vec![
+            Span::raw("Identity: "),
+            Span::styled(format!("{:.1}%", alignment.identity()), Style::default().fg(Color::Yellow)),
+        ]));
+        lines.push(Line::from(vec![
+            Span::raw("Query: "),
+            Span::styled(format!("{}-{}", alignment.query_start + 1, alignment.query_end), Style::default().fg(Color::Cyan)),
+            Span::raw(" Target: "),
+            Span::styled(format!("{}-{}", alignment.target_start + 1, alignment.target_end), Style::default().fg(Color::Cyan)),
+        ]));
+        lines.push(Line::from(create_labeled_span("CIGAR: ", alignment.cigar.clone(), Color::Magenta)));
+        if let Some(frame) = hit.frame {
+            lines.push(Line::from(create_labeled_span("Frame: ", READING_FRAME_LABELS[frame].to_string(), Color::Magenta)));
+        }
+    }
+
+    lines.extend([
         Line::from(create_labeled_span("Species: ", protein.species.clone(), Color::Green)),
         Line::from(create_labeled_span("ID: ", protein.id.clone(), Color::Yellow)),
         Line::from(create_labeled_span("Length: ", protein.length.to_string(), Color::Blue)),
         Line::from(create_labeled_span("Chromosome: ", protein.chromosome.clone(), Color::Cyan)),
         Line::from(create_labeled_span("Start: ", protein.start.to_string(), Color::Green)),
         Line::from(create_labeled_span("Stop: ", protein.stop.to_string(), Color::Yellow)),
-        Line::from(create_labeled_span("Strand: ", protein.strand.clone(), Color::Blue)),
-        Line::from(create_labeled_span("Blocks: ", protein.blocks.clone(), Color::Cyan)),
-        Line::from(create_labeled_span("Start Codon: ", protein.start_codon.clone(), Color::Green)),
-        Line::from(create_labeled_span("PhyloCSF Mean: ", protein.phylo_csf_mean.to_string(), Color::Yellow)),
+        Line::from(create_labeled_span("Strand: ", protein.strand.to_string(), Color::Blue)),
+        Line::from(create_labeled_span("Blocks: ", format_blocks(&protein.blocks), Color::Cyan)),
+        Line::from(create_labeled_span("Start Codon: ", format_start_codon(protein), Color::Green)),
+        Line::from(create_labeled_span("PhyloCSF Mean: ", format_phylo_csf(protein, None), Color::Yellow)),
+        Line::from(create_labeled_span("Source: ", protein.source.clone(), Color::DarkGray)),
         Line::from({
             let mut spans = vec![Span::raw("RNA Seq: ")];
             spans.extend(rna_seq_spans);
             spans
         }),
-        Line::from(create_labeled_span("AA Seq: ", protein.aa_seq.clone(), Color::Magenta)),
-    ]
+        Line::from(create_labeled_span("AA Seq: ", protein.aa_seq.to_string(), Color::Magenta)),
+    ]);
+
+    lines
 }
 
 fn render_right_panel(f: &mut Frame, app: &App, area: Rect) {
+    let records_height = if app.records.is_empty() { 0 } else { app.records.len().min(6) as u16 + 3 };
     let right_panel_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
+            Constraint::Length(records_height),
             Constraint::Length(20),
             Constraint::Min(15),
         ])
         .split(area);
 
-    render_sequence_analysis(f, app, right_panel_chunks[0]);
-    render_protein_analysis(f, app, right_panel_chunks[1]);
+    if !app.records.is_empty() {
+        render_record_list(f, app, right_panel_chunks[0]);
+    }
+    render_sequence_analysis(f, app, right_panel_chunks[1]);
+    if app.show_orf_panel {
+        render_orf_list(f, app, right_panel_chunks[2]);
+    } else {
+        render_protein_analysis(f, app, right_panel_chunks[2]);
+    }
+}
+
+fn render_orf_list(f: &mut Frame, app: &App, area: Rect) {
+    let header = Row::new(vec!["Frame", "Start", "End", "aa", "Codon"])
+        .style(Style::default().fg(Color::Cyan));
+
+    // 1-based inclusive coordinates, as in the orfs command
+    let rows: Vec<Row> = app.orfs.iter().map(|orf| {
+        let codon_color = if orf.start_codon == "ATG" { Color::Green } else { Color::Yellow };
+        Row::new(vec![
+            Cell::from(orf.frame_label()),
+            Cell::from((orf.start + 1).to_string()),
+            Cell::from(orf.end.to_string()),
+            Cell::from(orf.protein.len().to_string()),
+            Cell::from(orf.start_codon.replace('T', "U")).style(Style::default().fg(codon_color)),
+        ])
+    }).collect();
+
+    let title = format!("ORFs: {} ({}, ≥{} aa; n/+/-)",
+                        app.orfs.len(), app.orf_options.start_label(), app.orf_options.min_length);
+    let table = Table::new(rows, [
+        Constraint::Length(5),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Length(4),
+        Constraint::Min(5),
+    ])
+    .header(header)
+    .row_highlight_style(create_selection_style(true))
+    .block(Block::default().title(title).borders(Borders::ALL));
+
+    let mut table_state = TableState::default().with_selected(app.selected_orf);
+    f.render_stateful_widget(table, area, &mut table_state);
+}
+
+fn render_record_list(f: &mut Frame, app: &App, area: Rect) {
+    let header = Row::new(vec!["#", "ID", "Len", "GC"])
+        .style(Style::default().fg(Color::Cyan));
+
+    let rows: Vec<Row> = app.records.iter().enumerate().map(|(i, record)| {
+        Row::new(vec![
+            Cell::from((i + 1).to_string()),
+            Cell::from(record.id.clone()),
+            Cell::from(record.sequence.len().to_string()),
+            Cell::from(format!("{:.0}%", calculate_gc_content(&record.sequence))),
+        ])
+    }).collect();
+
+    let table = Table::new(rows, [
+        Constraint::Length(4),
+        Constraint::Min(8),
+        Constraint::Length(6),
+        Constraint::Length(4),
+    ])
+    .header(header)
+    .row_highlight_style(create_selection_style(true))
+    .block(Block::default()
+        .title(format!("Records {}/{} ([ / ])", app.current_record + 1, app.records.len()))
+        .borders(Borders::ALL));
+
+    let mut table_state = TableState::default().with_selected(Some(app.current_record));
+    f.render_stateful_widget(table, area, &mut table_state);
 }
 
 fn render_sequence_analysis(f: &mut Frame, app: &App, area: Rect) {
@@ -308,15 +626,15 @@ fn render_sequence_analysis(f: &mut Frame, app: &App, area: Rect) {
         ]),
         Line::from(vec![
             Span::raw("Start Codons: "),
-            Span::styled(count_start_codons(&app.input).to_string(), Style::default().fg(Color::Green)),
+            Span::styled(count_start_codons(&app.input, app.genetic_code).to_string(), Style::default().fg(Color::Green)),
         ]),
         Line::from(vec![
             Span::raw("Stop Codons: "),
-            Span::styled(count_stop_codons(&app.input).to_string(), Style::default().fg(Color::Red)),
+            Span::styled(count_stop_codons(&app.input, app.genetic_code).to_string(), Style::default().fg(Color::Red)),
         ]),
         Line::from(vec![
             Span::raw("ORFs: "),
-            Span::styled(count_orfs(&app.input).to_string(), Style::default().fg(Color::Cyan)),
+            Span::styled(app.orfs.len().to_string(), Style::default().fg(Color::Cyan)),
         ]),
         Line::from(vec![Span::raw("")]),
         Line::from(vec![
@@ -339,38 +657,92 @@ fn render_sequence_analysis(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(composition_widget, area);
 }
 
+/// pH the panels report the net charge at.
+const ANALYSIS_PH: f64 = 7.0;
+
+fn format_optional(value: Option<f64>, precision: usize) -> String {
+    value.map_or_else(|| "NA".to_string(), |value| format!("{value:.precision$}"))
+}
+
+/// ProtParam-style property lines shared by the analysis panel and the protein detail view.
+fn create_property_lines(properties: &ProteinProperties) -> Vec<Line<'static>> {
+    let stability = match properties.instability_index {
+        Some(_) if properties.is_unstable() => " (unstable)",
+        Some(_) => " (stable)",
+        None => "",
+    };
+    let charge_color = match properties.net_charge {
+        charge if charge > 0.5 => Color::Green,
+        charge if charge < -0.5 => Color::Red,
+        _ => Color::White,
+    };
+
+    vec![
+        Line::from(create_labeled_span("Isoelectric Point: ", format_optional(properties.isoelectric_point, 2), Color::Yellow)),
+        Line::from(vec![
+            Span::raw(format!("Net Charge (pH {:.1}): ", properties.ph)),
+            Span::styled(format!("{:+.2}", properties.net_charge), Style::default().fg(charge_color)),
+        ]),
+        Line::from(create_labeled_span("GRAVY: ", format_optional(properties.gravy, 3), Color::Blue)),
+        Line::from(create_labeled_span("Instability Index: ", format!("{}{stability}", format_optional(properties.instability_index, 2)), Color::Magenta)),
+        Line::from(create_labeled_span("Aliphatic Index: ", format_optional(properties.aliphatic_index, 2), Color::Cyan)),
+        Line::from(create_labeled_span("Ext. Coeff. (M⁻¹cm⁻¹): ",
+                                       format!("{} / {} reduced", properties.extinction.cystines, properties.extinction.reduced), Color::White)),
+    ]
+}
+
+/// 1-based inclusive, like the other coordinates shown.
+fn format_region(region: &std::ops::Range<usize>) -> String {
+    format!("{}-{}", region.start + 1, region.end)
+}
+
+/// Predicted signal peptide and transmembrane helices for the protein detail view.
+fn create_topology_lines(prediction: &TopologyPrediction) -> Vec<Line<'static>> {
+    let signal_peptide = match &prediction.signal_peptide {
+        Some(signal) => format!("cleaved after {} (n {}, h {}, c {})", signal.cleavage_site(),
+                                format_region(&signal.n_region), format_region(&signal.h_region), format_region(&signal.c_region)),
+        None => "none".to_string(),
+    };
+    let tm_helices = if prediction.tm_helices.is_empty() {
+        "none".to_string()
+    } else {
+        prediction.tm_helices.iter()
+            .map(|helix| format!("{} ({:.2})", format_region(&helix.range), helix.hydropathy))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    vec![
+        Line::from(create_labeled_span("Topology: ", prediction.summary().to_string(), Color::Green)),
+        Line::from(create_labeled_span("Signal Peptide: ", signal_peptide, Color::Yellow)),
+        Line::from(create_labeled_span("TM Helices: ", tm_helices, Color::Red)),
+    ]
+}
+
 fn render_protein_analysis(f: &mut Frame, app: &App, area: Rect) {
-    let (positive_charges, negative_charges) = count_charged_residues(&app.input);
-    let protein_lines = vec![
+    let protein = app.query_protein();
+    let properties = ProteinProperties::new(&protein, ANALYSIS_PH, &PKA_BJELLQVIST);
+    let source = match app.selected_orf() {
+        Some(orf) => format!("ORF {} {}-{}", orf.frame_label(), orf.start + 1, orf.end),
+        None => "frame +1".to_string(),
+    };
+
+    let mut protein_lines = vec![
         Line::from(vec![
             Span::styled("Protein Properties", Style::default().fg(Color::Cyan)),
+            Span::styled(format!(" ({source})"), Style::default().fg(Color::DarkGray)),
         ]),
         Line::from(vec![Span::raw("")]),
         Line::from(vec![
             Span::raw("Amino Acid Length: "),
-            Span::styled(calculate_amino_acid_length(&app.input).to_string(), Style::default().fg(Color::White)),
+            Span::styled(properties.length.to_string(), Style::default().fg(Color::White)),
         ]),
         Line::from(vec![
             Span::raw("Est. Molecular Weight: "),
-            Span::styled(format!("{:.1} Da", estimate_molecular_weight(&app.input)), Style::default().fg(Color::Yellow)),
-        ]),
-        Line::from(vec![
-            Span::raw("Hydrophobicity Index: "),
-            Span::styled(format!("{:.1}%", calculate_hydrophobicity_index(&app.input)), Style::default().fg(Color::Blue)),
-        ]),
-        Line::from(vec![
-            Span::raw("Positive Charges: "),
-            Span::styled(positive_charges.to_string(), Style::default().fg(Color::Green)),
-        ]),
-        Line::from(vec![
-            Span::raw("Negative Charges: "),
-            Span::styled(negative_charges.to_string(), Style::default().fg(Color::Red)),
-        ]),
-        Line::from(vec![
-            Span::raw("Net Charge: "),
-            Span::styled((positive_charges - negative_charges).to_string(), Style::default().fg(Color::White)),
+            Span::styled(format_optional(peptide_mass(&protein, MassType::Average, &[]).ok(), 1) + " Da", Style::default().fg(Color::Yellow)),
         ]),
     ];
+    protein_lines.extend(create_property_lines(&properties));
 
     let protein_widget = Paragraph::new(protein_lines)
         .block(Block::default().title("Protein Analysis").borders(Borders::ALL));
@@ -378,19 +750,131 @@ fn render_protein_analysis(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
-    let status_text = if app.input.is_empty() {
-        "Enter DNA sequence (A, T, G, C). Press 'q' to quit, 's' to toggle strand mode, 'p' for protein searcher."
+    let status_text = if !app.records.is_empty() {
+        "Press '[' / ']' to step through records, 'q' to quit, 's' to toggle strand mode, 'x' to toggle DNA/protein matching, 'm' to change the protein matrix, '<' / '>' to change genetic code, 'o' for ORFs, 'p' for protein searcher, ↑/↓ + Enter to pick a hit."
+    } else if app.input.is_empty() {
+        "Enter or paste DNA or RNA (A, C, G, T/U, IUPAC codes like N, R, Y; Shift+S/N/M), or Ctrl+O to open a file, Ctrl+F to search a peak list. Press 'q' to quit, 's' to toggle strand mode, 'p' for protein searcher."
     } else {
-        "Continue typing or press 'q' to quit, 's' to toggle strand mode, 'p' for protein searcher."
+        "Continue typing (←/→ move, Ctrl codon-wise, Shift selects, Home/End, Ins overwrite, Del, Ctrl+Z/Ctrl+Y undo/redo, Ctrl+R reverse complement) or press 'q' to quit, 's' to toggle strand mode, 'x' to toggle DNA/protein matching, 'm' to change the protein matrix, '<' / '>' to change genetic code, 'o' for ORFs, 'l' / '{' / '}' for profiles, 'p' for protein searcher, Ctrl+F for a peak list, ↑/↓ + Enter to pick a hit."
     };
 
-    let status_widget = Paragraph::new(vec![Line::from(vec![
-        Span::styled(status_text, Style::default().fg(Color::White)),
-    ])])
+    let status_span = match &app.status_message {
+        Some(message) => Span::styled(message.clone(), Style::default().fg(Color::Yellow)),
+        None => Span::styled(status_text, Style::default().fg(Color::White)),
+    };
+
+    let status_widget = Paragraph::new(vec![Line::from(vec![status_span])])
     .block(Block::default().title("Status").borders(Borders::ALL));
     f.render_widget(status_widget, area);
 }
 
+fn render_file_prompt(f: &mut Frame, path: &str, kind: FilePromptKind) {
+    let area = f.area();
+    let prompt_area = Rect::new(area.width / 6, area.height.saturating_sub(5) / 2, area.width * 2 / 3, 5.min(area.height));
+
+    f.render_widget(ratatui::widgets::Clear, prompt_area);
+    let prompt_widget = Paragraph::new(vec![
+        Line::from(vec![
+            Span::raw("Path: "),
+            Span::styled(path.to_string(), Style::default().fg(Color::Green)),
+            Span::styled("_", Style::default().fg(Color::Green).add_modifier(Modifier::SLOW_BLINK)),
+        ]),
+        Line::from(Span::styled("Enter: open | Esc: cancel", Style::default().fg(Color::DarkGray))),
+    ])
+    .block(Block::default()
+        .title(match kind {
+            FilePromptKind::Sequences => "Open FASTA/FASTQ File",
+            FilePromptKind::PeakList => "Search Peak List (one mass per line)",
+        })
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan)));
+    f.render_widget(prompt_widget, prompt_area);
+}
+
+fn render_pmf_results(f: &mut Frame, app: &App) {
+    let area = f.area();
+    let popup_area = Rect::new(area.width / 8, area.height / 8, area.width * 3 / 4, area.height * 3 / 4);
+    f.render_widget(ratatui::widgets::Clear, popup_area);
+
+    let options = &app.pmf_options;
+    let ions = match options.peak_charge {
+        0 => "neutral".to_string(),
+        1 => "[M+H]+".to_string(),
+        charge => format!("[M+{charge}H]{charge}+"),
+    };
+    let title = format!("Peptide Mass Fingerprint: {} peaks, {}, ≤{} missed, ±{} ppm, {ions}",
+                        app.peak_list.len(), options.digest.protease.name(), options.digest.missed_cleavages,
+                        options.tolerance_ppm);
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let inner_area = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(9), Constraint::Length(3)])
+        .split(inner_area);
+
+    let header = Row::new(vec!["#", "Protein", "Species", "Matched", "Coverage"])
+        .style(Style::default().fg(Color::Cyan));
+    let rows: Vec<Row> = app.pmf_hits.iter().enumerate().map(|(rank, hit)| {
+        let protein = &app.small_proteins[hit.protein_index];
+        Row::new(vec![
+            Cell::from((rank + 1).to_string()),
+            Cell::from(protein.id.clone()),
+            Cell::from(protein.species.clone()),
+            Cell::from(format!("{}/{}", hit.matched_peptides(), hit.candidate_peptides)),
+            Cell::from(format!("{:.0}%", hit.coverage * 100.0)),
+        ])
+    }).collect();
+    let table = Table::new(rows, [
+        Constraint::Length(4),
+        Constraint::Min(16),
+        Constraint::Min(12),
+        Constraint::Length(8),
+        Constraint::Length(9),
+    ])
+    .header(header)
+    .row_highlight_style(create_selection_style(true))
+    .block(Block::default().title(match app.is_building_peptide_index() {
+        true => "Ranked Proteins (digesting...)".to_string(),
+        false => format!("Ranked Proteins ({})", app.pmf_hits.len()),
+    }).borders(Borders::ALL));
+    let mut table_state = TableState::default().with_selected((!app.pmf_hits.is_empty()).then_some(app.selected_pmf_hit));
+    f.render_stateful_widget(table, chunks[0], &mut table_state);
+
+    let peptide_lines: Vec<Line> = match app.pmf_hits.get(app.selected_pmf_hit) {
+        Some(hit) => {
+            let protein = &app.small_proteins[hit.protein_index];
+            hit.matches.iter().map(|found| {
+                let (start, end) = (found.peptide.start as usize, found.peptide.end as usize);
+                Line::from(vec![
+                    Span::styled(format!("{:>10.4} ", found.peak), Style::default().fg(Color::Yellow)),
+                    Span::styled(format!("{:>+6.1} ppm ", found.error_ppm), Style::default().fg(Color::DarkGray)),
+                    Span::raw(format!("{}-{} ", start + 1, end)),
+                    Span::styled(protein.aa_seq[start..end].to_string(), Style::default().fg(Color::Magenta)),
+                ])
+            }).collect()
+        },
+        None if app.is_building_peptide_index() => vec![Line::from(Span::styled(
+            format!("Digesting the dataset with {}...", options.digest.protease.name()),
+            Style::default().fg(Color::Yellow),
+        ))],
+        None => vec![Line::from(Span::styled("No peaks matched a peptide", Style::default().fg(Color::DarkGray)))],
+    };
+    let peptides_widget = Paragraph::new(peptide_lines)
+        .block(Block::default().title("Matched Peptides").borders(Borders::ALL));
+    f.render_widget(peptides_widget, chunks[1]);
+
+    let help_widget = create_help_widget(vec![Line::from(Span::styled(
+        "↑/↓: Navigate | Enter: Show protein | Tab: Next protease | Ctrl+F: Other peak list | Esc: Close",
+        Style::default().fg(Color::White),
+    ))]);
+    f.render_widget(help_widget, chunks[2]);
+}
+
 fn render_loading_screen(f: &mut Frame, app: &App) {
     let area = f.area();
 
@@ -406,8 +890,12 @@ fn render_loading_screen(f: &mut Frame, app: &App) {
     f.render_widget(ratatui::widgets::Clear, loading_area);
 
     // Main loading container
+    let loading_title = match &app.current_dataset_source {
+        Some(source) => format!("Loading Dataset: {source}"),
+        None => "Loading Dataset".to_string(),
+    };
     let loading_block = Block::default()
-        .title("Loading Dataset")
+        .title(loading_title)
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Cyan));
     f.render_widget(loading_block, loading_area);
@@ -431,6 +919,7 @@ fn render_loading_screen(f: &mut Frame, app: &App) {
     // Status text
     let (status_text, progress_ratio) = match &app.dataset_progress {
         Some(DatasetProgress::CheckingCache) => ("Checking local cache...".to_string(), 0.1),
+        Some(DatasetProgress::ReadingSnapshot) => ("Reading cached snapshot...".to_string(), 0.5),
         Some(DatasetProgress::Downloading { bytes_downloaded, total_bytes }) => {
             if let Some(total) = total_bytes {
                 let ratio = (*bytes_downloaded as f64) / (*total as f64);
@@ -442,8 +931,15 @@ fn render_loading_screen(f: &mut Frame, app: &App) {
                     *bytes_downloaded as f64 / 1_048_576.0), 0.4)
             }
         },
-        Some(DatasetProgress::Extracting) => ("Extracting compressed file...".to_string(), 1.0), // 100% for extracting
-        Some(DatasetProgress::Parsing { .. }) => ("Loading complete!".to_string(), 1.0), // Treat parsing as complete
+        Some(DatasetProgress::Verifying) => ("Verifying download...".to_string(), 0.8),
+        Some(DatasetProgress::Parsing { lines_parsed, bytes_read, total_bytes }) => {
+            let text = format!("Parsing... {lines_parsed} proteins ({:.1} MB read)", *bytes_read as f64 / 1_048_576.0);
+            match total_bytes {
+                Some(total) if *total > 0 => (text, (*bytes_read as f64 / *total as f64).min(1.0) * 0.2 + 0.8), // 80% to 100%
+                _ => (text, 0.9),
+            }
+        },
+        Some(DatasetProgress::Source { name, .. }) => (format!("Loading {name}..."), 0.1),
         Some(DatasetProgress::Complete) => ("Loading complete!".to_string(), 1.0),
         Some(DatasetProgress::Error(err)) => (format!("Error: {err}"), 0.0),
         None => ("Initializing...".to_string(), 0.0),
@@ -560,6 +1056,9 @@ fn render_protein_searcher(f: &mut Frame, app: &App) {
                 crate::SearchField::MaxLength => "Max Length",
                 crate::SearchField::MinPhyloCSF => "Min PhyloCSF",
                 crate::SearchField::MaxPhyloCSF => "Max PhyloCSF",
+                crate::SearchField::Source => "Source",
+                crate::SearchField::TmHelices => "TM Helices",
+                crate::SearchField::SignalPeptide => "Signal Peptide",
             };
             Line::from(vec![
                 Span::styled(field_name, Style::default().fg(Color::Yellow)),
@@ -589,7 +1088,10 @@ fn render_protein_searcher(f: &mut Frame, app: &App) {
 
     let results_widget = Paragraph::new(results_lines)
         .block(Block::default()
-            .title(format!("Results ({}/{})", app.filtered_proteins.len(), app.small_proteins.len()))
+            .title(match app.is_predicting_topology() {
+                true => format!("Results ({}/{}; predicting topology...)", app.filtered_proteins.len(), app.small_proteins.len()),
+                false => format!("Results ({}/{})", app.filtered_proteins.len(), app.small_proteins.len()),
+            })
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::Cyan)))
         .wrap(ratatui::widgets::Wrap { trim: true });
@@ -616,15 +1118,15 @@ fn render_protein_searcher(f: &mut Frame, app: &App) {
             ]),
             Line::from(vec![
                 Span::raw("Strand: "),
-                Span::styled(&selected_protein.strand, Style::default().fg(Color::Magenta)),
+                Span::styled(selected_protein.strand.to_string(), Style::default().fg(Color::Magenta)),
                 Span::raw(" Length: "),
                 Span::styled(selected_protein.length.to_string(), Style::default().fg(Color::White)),
                 Span::raw(" PhyloCSF: "),
-                Span::styled(format!("{:.2}", selected_protein.phylo_csf_mean), Style::default().fg(Color::Yellow)),
+                Span::styled(format_phylo_csf(selected_protein, Some(2)), Style::default().fg(Color::Yellow)),
             ]),
             Line::from(vec![
                 Span::raw("Start Codon: "),
-                Span::styled(&selected_protein.start_codon, Style::default().fg(Color::Green)),
+                Span::styled(format_start_codon(selected_protein), Style::default().fg(Color::Green)),
             ]),
             Line::from(vec![
                 Span::raw("AA Seq: "),
@@ -632,7 +1134,7 @@ fn render_protein_searcher(f: &mut Frame, app: &App) {
                     if selected_protein.aa_seq.len() > 50 {
                         format!("{}...", &selected_protein.aa_seq[..50])
                     } else {
-                        selected_protein.aa_seq.clone()
+                        selected_protein.aa_seq.to_string()
                     },
                     Style::default().fg(Color::Magenta),
                 ),
@@ -712,6 +1214,7 @@ fn render_protein_detail(f: &mut Frame, app: &App) {
         .constraints([
             Constraint::Length(3),
             Constraint::Min(1),
+            Constraint::Length(6),
             Constraint::Length(2),
         ])
         .split(inner_area);
@@ -731,7 +1234,7 @@ fn render_protein_detail(f: &mut Frame, app: &App) {
     f.render_widget(header, detail_chunks[0]);
 
     if let Some(protein) = &app.detailed_protein {
-        let sequence_lines = vec![
+        let mut sequence_lines = vec![
             Line::from(vec![
                 Span::raw("ID: "),
                 Span::styled(&protein.id, Style::default().fg(Color::Yellow)),
@@ -746,7 +1249,7 @@ fn render_protein_detail(f: &mut Frame, app: &App) {
             ]),
             Line::from(vec![
                 Span::raw("Strand: "),
-                Span::styled(&protein.strand, Style::default().fg(Color::Magenta)),
+                Span::styled(protein.strand.to_string(), Style::default().fg(Color::Magenta)),
             ]),
             Line::from(vec![
                 Span::raw("Start: "),
@@ -758,32 +1261,45 @@ fn render_protein_detail(f: &mut Frame, app: &App) {
                 Span::raw("Length: "),
                 Span::styled(protein.length.to_string(), Style::default().fg(Color::Blue)),
             ]),
+            Line::from(vec![
+                Span::raw("Mass: "),
+                Span::styled(format_protein_masses(protein), Style::default().fg(Color::Yellow)),
+            ]),
             Line::from(vec![
                 Span::raw("Blocks: "),
-                Span::styled(protein.blocks.clone(), Style::default().fg(Color::Cyan)),
+                Span::styled(format_blocks(&protein.blocks), Style::default().fg(Color::Cyan)),
             ]),
             Line::from(vec![
                 Span::raw("Start Codon: "),
-                Span::styled(protein.start_codon.clone(), Style::default().fg(Color::Green)),
+                Span::styled(format_start_codon(protein), Style::default().fg(Color::Green)),
             ]),
             Line::from(vec![
                 Span::raw("PhyloCSF Mean: "),
-                Span::styled(protein.phylo_csf_mean.to_string(), Style::default().fg(Color::Yellow)),
+                Span::styled(format_phylo_csf(protein, None), Style::default().fg(Color::Yellow)),
             ]),
+            Line::from(vec![
+                Span::raw("Source: "),
+                Span::styled(&protein.source, Style::default().fg(Color::Cyan)),
+            ]),
+        ];
+        sequence_lines.extend(create_property_lines(&ProteinProperties::new(&protein.aa_seq, ANALYSIS_PH, &PKA_BJELLQVIST)));
+        sequence_lines.extend(create_topology_lines(&TopologyPrediction::predict(&protein.aa_seq)));
+        sequence_lines.extend([
             Line::from(vec![
                 Span::raw("RNA Seq: "),
-                Span::styled(protein.rna_seq.clone(), Style::default().fg(Color::White)),
+                Span::styled(protein.rna_seq.to_string(), Style::default().fg(Color::White)),
             ]),
             Line::from(vec![
                 Span::raw("AA Seq: "),
-                Span::styled(protein.aa_seq.clone(), Style::default().fg(Color::Magenta)),
+                Span::styled(protein.aa_seq.to_string(), Style::default().fg(Color::Magenta)),
             ]),
-        ];
+        ]);
 
         let sequence_widget = Paragraph::new(sequence_lines)
             .block(Block::default().title("Sequence Details").borders(Borders::ALL))
             .wrap(ratatui::widgets::Wrap { trim: true });
         f.render_widget(sequence_widget, detail_chunks[1]);
+        render_profile(f, &app.profile(&protein.aa_seq, &protein.rna_seq), detail_chunks[2], "Tab/{/}");
     } else {
         let no_detail = Paragraph::new(vec![Line::from(vec![
             Span::styled("No protein detail available", Style::default().fg(Color::DarkGray)),
@@ -794,10 +1310,10 @@ fn render_protein_detail(f: &mut Frame, app: &App) {
 
     let help_lines = vec![
         Line::from(vec![
-            Span::styled("Enter: Select & Close | Esc: Return to Search | ↑/↓: Scroll", Style::default().fg(Color::White)),
+            Span::styled("Enter: Select & Close | Esc: Return to Search | Tab: Next profile | {/}: Profile window", Style::default().fg(Color::White)),
         ]),
     ];
 
     let help_widget = create_help_widget(help_lines);
-    f.render_widget(help_widget, detail_chunks[2]);
+    f.render_widget(help_widget, detail_chunks[3]);
 }